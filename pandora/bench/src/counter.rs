@@ -0,0 +1,133 @@
+//! The externalities wrapper counting the storage accesses of the runtime
+
+use externalities::{ExtensionStore, Externalities};
+use primitives::{storage::ChildStorageKey, H256};
+use std::any::{Any, TypeId};
+use std::cell::Cell;
+
+/// The externalities which delegate to the inner one and count the storage reads and writes
+pub struct CountingExt<'a, E> {
+    inner: &'a mut E,
+    reads: Cell<u64>,
+    writes: u64,
+}
+
+impl<'a, E: Externalities> CountingExt<'a, E> {
+    /// Wrap the externalities
+    ///
+    /// @inner  the externalities to delegate to
+    pub fn new(inner: &'a mut E) -> Self {
+        CountingExt {
+            inner: inner,
+            reads: Cell::new(0),
+            writes: 0,
+        }
+    }
+
+    /// The count of storage reads
+    pub fn reads(&self) -> u64 {
+        self.reads.get()
+    }
+
+    /// The count of storage writes
+    pub fn writes(&self) -> u64 {
+        self.writes
+    }
+
+    fn read(&self) {
+        self.reads.set(self.reads.get() + 1);
+    }
+
+    fn write(&mut self) {
+        self.writes += 1;
+    }
+}
+
+impl<'a, E: Externalities> Externalities for CountingExt<'a, E> {
+    fn storage(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.read();
+        self.inner.storage(key)
+    }
+
+    fn storage_hash(&self, key: &[u8]) -> Option<H256> {
+        self.read();
+        self.inner.storage_hash(key)
+    }
+
+    fn child_storage_hash(&self, storage_key: ChildStorageKey, key: &[u8]) -> Option<H256> {
+        self.read();
+        self.inner.child_storage_hash(storage_key, key)
+    }
+
+    fn original_storage(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.read();
+        self.inner.original_storage(key)
+    }
+
+    fn original_child_storage(&self, storage_key: ChildStorageKey, key: &[u8]) -> Option<Vec<u8>> {
+        self.read();
+        self.inner.original_child_storage(storage_key, key)
+    }
+
+    fn original_storage_hash(&self, key: &[u8]) -> Option<H256> {
+        self.read();
+        self.inner.original_storage_hash(key)
+    }
+
+    fn original_child_storage_hash(&self, storage_key: ChildStorageKey, key: &[u8]) -> Option<H256> {
+        self.read();
+        self.inner.original_child_storage_hash(storage_key, key)
+    }
+
+    fn child_storage(&self, storage_key: ChildStorageKey, key: &[u8]) -> Option<Vec<u8>> {
+        self.read();
+        self.inner.child_storage(storage_key, key)
+    }
+
+    fn kill_child_storage(&mut self, storage_key: ChildStorageKey) {
+        self.write();
+        self.inner.kill_child_storage(storage_key)
+    }
+
+    fn clear_prefix(&mut self, prefix: &[u8]) {
+        self.write();
+        self.inner.clear_prefix(prefix)
+    }
+
+    fn clear_child_prefix(&mut self, storage_key: ChildStorageKey, prefix: &[u8]) {
+        self.write();
+        self.inner.clear_child_prefix(storage_key, prefix)
+    }
+
+    fn place_storage(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
+        self.write();
+        self.inner.place_storage(key, value)
+    }
+
+    fn place_child_storage(&mut self, storage_key: ChildStorageKey, key: Vec<u8>, value: Option<Vec<u8>>) {
+        self.write();
+        self.inner.place_child_storage(storage_key, key, value)
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.inner.chain_id()
+    }
+
+    fn storage_root(&mut self) -> H256 {
+        self.inner.storage_root()
+    }
+
+    fn child_storage_root(&mut self, storage_key: ChildStorageKey) -> Vec<u8> {
+        self.inner.child_storage_root(storage_key)
+    }
+
+    fn storage_changes_root(&mut self, parent: H256) -> Result<Option<H256>, ()> {
+        self.inner.storage_changes_root(parent)
+    }
+}
+
+impl<'a, E: Externalities> ExtensionStore for CountingExt<'a, E> {
+    fn extension_by_type_id(&mut self, type_id: TypeId) -> Option<&mut dyn Any> {
+        self.inner.extension_by_type_id(type_id)
+    }
+}
@@ -0,0 +1,304 @@
+//! # Pandora Bench
+//!
+//! The benchmarks of the pandora, oracle and price dispatchables. Each call is executed natively
+//! against a `TestExternalities` seeded at several scales, and the execution time and the storage
+//! reads/writes are reported, which are the inputs of setting real weights.
+//!
+//! Run with `cargo run --release -p pandora-bench [scale...]`, the default scales are 10, 1k and
+//! 100k dboxes, oracle candidates and price reports.
+
+mod counter;
+
+use codec::Encode;
+use counter::CountingExt;
+use pandora_runtime::{
+    pandora::Status, price, AccountId, Balance, BalancesConfig, BlockNumber, BlockTime,
+    BuildStorage, ElectionEra, GenesisConfig, MinStaking, Oracle, OracleConfig, Origin, Pandora, PandoraConfig,
+    Price, PriceConfig, RoundLength, System, SystemConfig,
+};
+use primitives::twox_128;
+use runtime_io::TestExternalities;
+use sr_primitives::traits::OnFinalize;
+use std::time::Duration;
+use std::time::Instant;
+use support::traits::Get;
+
+/// The default scales of the benchmarks
+const DEFAULT_SCALES: [u64; 3] = [10, 1_000, 100_000];
+/// The free balance of each endowed account
+const ENDOWMENT: Balance = 1 << 60;
+/// The unit price of dbox
+const UNIT_PRICE: Balance = 1_000_000_000;
+/// The maximum count of oracle candidates bonded by calls, the rest are seeded into storage
+/// directly, since each bonding re-encodes the whole candidate list
+const MAX_BONDED_CANDIDATES: u64 = 1_000;
+/// The maximum blocks to wait for the settlement to be done
+const MAX_SETTLING_BLOCKS: BlockNumber = 1_000_000;
+
+/// The accumulated cost of calls
+#[derive(Default, Clone, Copy)]
+struct Measurement {
+    elapsed: Duration,
+    reads: u64,
+    writes: u64,
+    calls: u64,
+}
+
+impl Measurement {
+    /// Accumulate the cost of another call
+    ///
+    /// @other  the measurement of the call
+    fn add(&mut self, other: Measurement) {
+        self.elapsed += other.elapsed;
+        self.reads += other.reads;
+        self.writes += other.writes;
+        self.calls += other.calls;
+    }
+}
+
+/// Get the account of the player
+///
+/// @index  the index of player
+fn player(index: u64) -> AccountId {
+    let mut raw = [0u8; 32];
+    raw[..8].copy_from_slice(&index.to_le_bytes());
+    raw.into()
+}
+
+/// Get the system account
+///
+/// @index  the index of system account
+fn system_account(index: u8) -> AccountId {
+    [0xff - index; 32].into()
+}
+
+fn root() -> Origin {
+    system::RawOrigin::Root.into()
+}
+
+/// Build the externalities with the endowed players
+///
+/// @players    the count of players
+fn new_test_ext(players: u64) -> TestExternalities {
+    let mut balances: Vec<(AccountId, Balance)> =
+        (0..players).map(|i| (player(i), ENDOWMENT)).collect();
+    balances.push((system_account(0), ENDOWMENT));
+
+    GenesisConfig {
+        system: Some(SystemConfig {
+            code: vec![],
+            changes_trie_config: Default::default(),
+        }),
+        aura: None,
+        grandpa: None,
+        indices: None,
+        balances: Some(BalancesConfig {
+            balances: balances,
+            vesting: vec![],
+        }),
+        sudo: None,
+        pandora: Some(PandoraConfig {
+            cashier_account: system_account(0),
+            reserve_account: system_account(1),
+            pool_account: system_account(2),
+            last_player_account: system_account(3),
+            team_account: system_account(4),
+            operator_account: system_account(5),
+            dbox_unit_price: None,
+            status: None,
+            max_active_dboxes_count: None,
+            settlement_weight_ratio: None,
+        }),
+        oracle: Some(OracleConfig {
+            cashier_account: system_account(0),
+            oracles: vec![],
+            candidates: vec![],
+        }),
+        price: Some(PriceConfig {
+            cashier_account: system_account(0),
+        }),
+    }
+    .build_storage()
+    .expect("Genesis config is valid")
+    .into()
+}
+
+/// Execute the closure and count its cost
+///
+/// @ext    the externalities
+/// @f      the closure to be measured
+fn measure<R>(ext: &mut TestExternalities, f: impl FnOnce() -> R) -> (R, Measurement) {
+    let mut inner = ext.ext();
+    let mut counting = CountingExt::new(&mut inner);
+    let start = Instant::now();
+    let result = externalities::set_and_run_with_externalities(&mut counting, f);
+    let elapsed = start.elapsed();
+
+    let measurement = Measurement {
+        elapsed: elapsed,
+        reads: counting.reads(),
+        writes: counting.writes(),
+        calls: 1,
+    };
+    (result, measurement)
+}
+
+/// Print the average cost per call
+///
+/// @scale          the scale of storage
+/// @name           the name of call
+/// @measurement    the accumulated cost
+fn report(scale: u64, name: &str, measurement: &Measurement) {
+    if measurement.calls == 0 {
+        return;
+    }
+    let calls = measurement.calls;
+    println!(
+        "{:>8} {:<40} {:>8} {:>12} {:>8} {:>8}",
+        scale,
+        name,
+        calls,
+        measurement.elapsed.as_micros() / calls as u128,
+        measurement.reads / calls,
+        measurement.writes / calls,
+    );
+}
+
+/// Benchmark the pandora calls with `scale` active dboxes
+///
+/// @scale  the count of dboxes
+fn bench_pandora(scale: u64) {
+    let mut ext = new_test_ext(scale + 1);
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        Pandora::init(root(), UNIT_PRICE).expect("Game is inited");
+        // The limitation is only changed at the start of next round, so it is seeded directly
+        runtime_io::storage::set(
+            &twox_128(b"PandoraModule MaxActiveDboxesCount"),
+            &(scale * 2).encode(),
+        );
+        Pandora::set_status(root(), Status::Running).expect("Game is running");
+        for i in 0..scale {
+            Pandora::create_dbox(Origin::signed(player(i))).expect("Dbox is created");
+        }
+    });
+
+    let (result, measurement) = measure(&mut ext, || {
+        Pandora::create_dbox(Origin::signed(player(scale)))
+    });
+    result.expect("Dbox is created");
+    report(scale, "pandora::create_dbox", &measurement);
+
+    let dbox_id = ext.execute_with(|| Pandora::dbox_of_owner_by_index((player(0), 0)));
+    let (result, measurement) = measure(&mut ext, || {
+        Pandora::open_dbox(Origin::signed(player(0)), dbox_id)
+    });
+    result.expect("Dbox is opened");
+    report(scale, "pandora::open_dbox", &measurement);
+
+    // Finalize blocks until the round is settled
+    let mut running = Measurement::default();
+    let mut beginning = Measurement::default();
+    let mut settling = Measurement::default();
+    let mut block_number: BlockNumber = 1;
+    while block_number < MAX_SETTLING_BLOCKS {
+        block_number += 1;
+        let (status, timeout) = ext.execute_with(|| {
+            System::set_block_number(block_number);
+            (Pandora::game_status(), Pandora::timeout())
+        });
+        let (_, measurement) = measure(&mut ext, || {
+            <Pandora as OnFinalize<BlockNumber>>::on_finalize(block_number)
+        });
+
+        match status {
+            Status::Running if timeout > BlockTime::get() => running.add(measurement),
+            Status::Running => beginning.add(measurement),
+            _ => settling.add(measurement),
+        }
+        if beginning.calls > 0 && ext.execute_with(Pandora::game_status) != Status::Settling {
+            break;
+        }
+    }
+    report(scale, "pandora::on_finalize (running)", &running);
+    report(scale, "pandora::on_finalize (begin settling)", &beginning);
+    report(scale, "pandora::on_finalize (settling)", &settling);
+}
+
+/// Benchmark the oracle calls with `scale` candidates
+///
+/// @scale  the count of candidates
+fn bench_oracle(scale: u64) {
+    let mut ext = new_test_ext(scale + 1);
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        for i in 0..scale.min(MAX_BONDED_CANDIDATES) {
+            let amount = MinStaking::get() + i as Balance;
+            Oracle::bond(Origin::signed(player(i)), amount).expect("Candidate is bonded");
+        }
+        let candidates: Vec<AccountId> = (0..scale).map(player).collect();
+        runtime_io::storage::set(&twox_128(b"OracleStorage Candidates"), &candidates.encode());
+    });
+
+    let (result, measurement) = measure(&mut ext, || {
+        Oracle::bond(Origin::signed(player(scale)), MinStaking::get())
+    });
+    result.expect("Candidate is bonded");
+    report(scale, "oracle::bond", &measurement);
+
+    let election = ElectionEra::get();
+    ext.execute_with(|| System::set_block_number(election));
+    let (_, measurement) = measure(&mut ext, || {
+        <Oracle as OnFinalize<BlockNumber>>::on_finalize(election)
+    });
+    report(scale, "oracle::on_finalize (election)", &measurement);
+}
+
+/// Benchmark the price calls with `scale` reports
+///
+/// @scale  the count of price reports
+fn bench_price(scale: u64) {
+    let mut ext = new_test_ext(0);
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        // The report is encoded as the tuple of reporter and price
+        let reports: Vec<(AccountId, price::Price)> = (0..scale)
+            .map(|i| (player(i), 1_000 + i as price::Price))
+            .collect();
+        runtime_io::storage::set(&twox_128(b"PriceStorage PriceReports"), &reports.encode());
+    });
+
+    let (_, measurement) = measure(&mut ext, || {
+        <Price as OnFinalize<BlockNumber>>::on_finalize(1)
+    });
+    report(scale, "price::on_finalize", &measurement);
+
+    let round_end = RoundLength::get();
+    ext.execute_with(|| System::set_block_number(round_end));
+    let (_, measurement) = measure(&mut ext, || {
+        <Price as OnFinalize<BlockNumber>>::on_finalize(round_end)
+    });
+    report(scale, "price::on_finalize (round end)", &measurement);
+}
+
+fn main() {
+    let scales: Vec<u64> = std::env::args()
+        .skip(1)
+        .map(|s| s.parse().expect("Scale should be a number"))
+        .collect();
+    let scales = if scales.is_empty() {
+        DEFAULT_SCALES.to_vec()
+    } else {
+        scales
+    };
+
+    println!(
+        "{:>8} {:<40} {:>8} {:>12} {:>8} {:>8}",
+        "scale", "call", "calls", "time (us)", "reads", "writes"
+    );
+    for &scale in scales.iter() {
+        bench_pandora(scale);
+        bench_oracle(scale);
+        bench_price(scale);
+    }
+}
@@ -0,0 +1,20 @@
+//! Runtime API definition for oracle module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use rstd::prelude::*;
+
+sr_api::decl_runtime_apis! {
+    pub trait OracleApi<AccountId, Balance> where
+        AccountId: Codec,
+        Balance: Codec,
+    {
+        /// Get the acting oracles
+        fn oracles() -> Vec<AccountId>;
+        /// Get the candidates of oracles
+        fn candidates() -> Vec<AccountId>;
+        /// Get the staked funds of the oracle or candidate
+        fn staked(who: AccountId) -> Balance;
+    }
+}
@@ -0,0 +1,110 @@
+//! RPC interface for the oracle module.
+
+use client::blockchain::HeaderBackend;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sr_primitives::{
+    generic::BlockId,
+    traits::{Block as BlockT, ProvideRuntimeApi},
+};
+use std::sync::Arc;
+
+pub use self::gen_client::Client as OracleClient;
+pub use oracle_module_rpc_runtime_api::OracleApi as OracleRuntimeApi;
+
+#[rpc]
+pub trait OracleApi<BlockHash, AccountId, Balance> {
+    /// Get the acting oracles
+    #[rpc(name = "oracle_oracles")]
+    fn oracles(&self, at: Option<BlockHash>) -> Result<Vec<AccountId>>;
+
+    /// Get the candidates of oracles
+    #[rpc(name = "oracle_candidates")]
+    fn candidates(&self, at: Option<BlockHash>) -> Result<Vec<AccountId>>;
+
+    /// Get the staked funds of the oracle or candidate
+    #[rpc(name = "oracle_staked")]
+    fn staked(&self, who: AccountId, at: Option<BlockHash>) -> Result<Balance>;
+}
+
+/// A struct that implements the [`OracleApi`].
+pub struct Oracle<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Oracle<C, B> {
+    /// Create new `Oracle` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Oracle {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+    /// The call to runtime failed.
+    RuntimeError,
+}
+
+impl From<Error> for i64 {
+    fn from(e: Error) -> i64 {
+        match e {
+            Error::RuntimeError => 1,
+        }
+    }
+}
+
+/// Convert the error of runtime api to rpc error
+fn runtime_error<E: std::fmt::Debug>(message: &str, e: E) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(Error::RuntimeError.into()),
+        message: message.into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+impl<C, Block, AccountId, Balance> OracleApi<<Block as BlockT>::Hash, AccountId, Balance>
+    for Oracle<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static,
+    C: ProvideRuntimeApi,
+    C: HeaderBackend<Block>,
+    C::Api: OracleRuntimeApi<Block, AccountId, Balance>,
+    AccountId: Codec,
+    Balance: Codec,
+{
+    fn oracles(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<AccountId>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        api.oracles(&at)
+            .map_err(|e| runtime_error("Unable to query oracles.", e))
+    }
+
+    fn candidates(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<AccountId>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        api.candidates(&at)
+            .map_err(|e| runtime_error("Unable to query candidates.", e))
+    }
+
+    fn staked(&self, who: AccountId, at: Option<<Block as BlockT>::Hash>) -> Result<Balance> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        api.staked(&at, who)
+            .map_err(|e| runtime_error("Unable to query staked funds.", e))
+    }
+}
@@ -11,12 +11,17 @@ use rstd::result;
 use sr_primitives::traits::{
     Bounded, CheckedAdd, CheckedSub, EnsureOrigin, Hash, Saturating, Zero,
 };
+use sr_primitives::weights::SimpleDispatchInfo;
 use support::traits::{
     ChangeMembers, Currency, ExistenceRequirement, Get, LockIdentifier, LockableCurrency,
     ReservableCurrency, WithdrawReason, WithdrawReasons,
 };
 use support::{
-    decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue,
+    decl_event, decl_module, decl_storage,
+    dispatch::Result,
+    ensure,
+    metadata::{DescribeType, ModuleTypeMetadata, TypeMetadata},
+    StorageMap, StorageValue,
 };
 use system::{ensure_root, ensure_signed};
 
@@ -24,7 +29,7 @@ use system::{ensure_root, ensure_signed};
 mod oracle_test;
 
 /// The status of oracle
-#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq)]
+#[derive(Encode, Decode, DescribeType, Copy, Clone, Debug, PartialEq)]
 pub enum OracleStatus {
     None,
     /// The oracle is active
@@ -42,7 +47,7 @@ impl Default for OracleStatus {
 }
 
 /// The info struct for statistic information of oracle
-#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
 pub struct OracleInfo<Balance> {
     /// Total jobs requested
     pub total_jobs: u64,
@@ -61,7 +66,7 @@ pub struct OracleInfo<Balance> {
 }
 
 /// The job struct
-#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
 pub struct Job<BlockNumber, Balance, AccountId> {
     /// The requestor of the job
     pub from: AccountId,
@@ -104,6 +109,8 @@ pub trait Trait: balances::Trait {
     type MinStaking: Get<BalanceOf<Self>>;
     /// The origin that's responsible for slashing malicious oracles.
     // type MaliciousSlashOrigin: EnsureOrigin<Self::Origin>;
+    /// The origin that's responsible for managing the module.
+    type AdminOrigin: EnsureOrigin<Self::Origin>;
     /// The maxium count of working oracles.
     type Count: Get<u16>;
     /// The duration in which oracles should report and be paid.
@@ -122,7 +129,7 @@ pub trait Trait: balances::Trait {
 }
 
 /// Unbond record of an oracle/candidate
-#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, DescribeType)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct Unbond<Balance, BlockNumber> {
     amount: Balance,
@@ -130,7 +137,7 @@ pub struct Unbond<Balance, BlockNumber> {
 }
 
 /// The ledger of oracle's staked token.
-#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, DescribeType)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct Ledger<Balance: Default, BlockNumber> {
     /// Total locked funds
@@ -187,6 +194,8 @@ decl_event!(
         JobCancelled(AccountId, BlockNumber, Hash),
         /// Job fulfilled
         JobFulfilled(AccountId, BlockNumber, Hash),
+        /// Cashier account is changed from the old account to the new one
+        CashierChanged(AccountId, AccountId),
     }
 );
 
@@ -225,6 +234,33 @@ decl_storage! {
         /// The nonce value for hash of job
         Nonce: u64;
     }
+    add_extra_genesis {
+        // The acting oracles with their staked funds
+        config(oracles): Vec<(T::AccountId, BalanceOf<T>)>;
+        // The candidates with their staked funds
+        config(candidates): Vec<(T::AccountId, BalanceOf<T>)>;
+        build(|config: &Self| {
+            let oracles: Vec<T::AccountId> = config.oracles.iter().map(|(who, _)| who.clone()).collect();
+            let candidates: Vec<T::AccountId> = config.candidates.iter().map(|(who, _)| who.clone()).collect();
+            assert!(
+                oracles.len() <= T::Count::get() as usize,
+                "Too many oracles at genesis"
+            );
+            for (index, who) in oracles.iter().chain(candidates.iter()).enumerate() {
+                assert!(
+                    !oracles.iter().chain(candidates.iter()).skip(index + 1).any(|x| x == who),
+                    "Duplicated oracle or candidate at genesis"
+                );
+            }
+
+            // The funds are bonded the same way as the `bond` call
+            for (who, amount) in config.oracles.iter().chain(config.candidates.iter()) {
+                Module::<T>::do_bond(who, *amount).expect("Genesis stake should be bonded");
+            }
+            <Oracles<T>>::put(oracles);
+            <Candidates<T>>::put(candidates);
+        })
+    }
 }
 
 decl_module! {
@@ -267,6 +303,31 @@ decl_module! {
         //     Ok(())
         // }
 
+        /// Rotate the cashier account, the funds of cashier are moved to the new account
+        ///
+        /// The free and reserved funds are repatriated without transfer fee, so the new account
+        /// should exist
+        ///
+        /// @origin     the initiator
+        /// @new_account    the new cashier account
+        #[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+        pub fn set_cashier_account(origin, new_account: T::AccountId) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
+            let old_account = Self::cashier_account();
+            ensure!(old_account != new_account, "New value should be different from current value");
+            ensure!(!T::Currency::total_balance(&new_account).is_zero(), "New account does not exist");
+
+            T::Currency::reserve(&old_account, T::Currency::free_balance(&old_account))?;
+            let _ = T::Currency::repatriate_reserved(
+                &old_account,
+                &new_account,
+                T::Currency::reserved_balance(&old_account),
+            )?;
+            <CashierAccount<T>>::put(&new_account);
+            Self::deposit_event(RawEvent::CashierChanged(old_account, new_account));
+            Ok(())
+        }
+
         /// Unbond amount
         ///
         /// @origin     the sender
@@ -314,6 +375,22 @@ decl_module! {
     }
 }
 
+/// The types of storage and events, with the aliases named by the storage
+impl<T: Trait> ModuleTypeMetadata for Module<T> {
+    fn types() -> Vec<TypeMetadata> {
+        vec![
+            OracleStatus::type_metadata(),
+            OracleInfo::<BalanceOf<T>>::type_metadata(),
+            JobOf::<T>::type_metadata(),
+            Unbond::<BalanceOf<T>, T::BlockNumber>::type_metadata(),
+            LedgerOf::<T>::type_metadata(),
+            TypeMetadata::alias("BalanceOf", "Balance"),
+            TypeMetadata::alias("JobOf", "Job<BlockNumber, Balance, AccountId>"),
+            TypeMetadata::alias("LedgerOf", "Ledger<Balance, BlockNumber>"),
+        ]
+    }
+}
+
 /// Helper functions
 impl<T: Trait> Module<T> {
     /// Elect oracles
@@ -688,6 +765,8 @@ pub trait OracleMixedIn<T: system::Trait> {
     fn on_request_fulfilled(oracle: &T::AccountId, id: T::Hash) -> Result;
     /// Predicate if one oracle is valid.
     fn is_valid(who: &T::AccountId) -> bool;
+    /// Predicate if the job is assigned to the oracle and not fulfilled yet.
+    fn is_assigned(oracle: &T::AccountId, id: T::Hash) -> bool;
 }
 
 /// External interface
@@ -824,6 +903,15 @@ impl<T: Trait> OracleMixedIn<T> for Module<T> {
         let oracles = Self::oracles();
         oracles.contains(who)
     }
+
+    /// Check if the job is assigned to the oracle, the job is removed once it is fulfilled or
+    /// cancelled, so an existing job is not fulfilled yet
+    ///
+    /// @oracle the account of oracle
+    /// @id     the id of the requested job
+    fn is_assigned(oracle: &T::AccountId, id: T::Hash) -> bool {
+        <Jobs<T>>::exists(id) && Self::job(id).oracle == *oracle
+    }
 }
 
 /// Help functions go here
@@ -832,4 +920,11 @@ impl<T: Trait> Module<T> {
     fn block_number() -> T::BlockNumber {
         <system::Module<T>>::block_number()
     }
+
+    /// Get the staked funds of the oracle or candidate
+    ///
+    /// @who    the oracle or candidate
+    pub fn get_staked(who: &T::AccountId) -> BalanceOf<T> {
+        Self::ledger(who).staked
+    }
 }
@@ -7,7 +7,7 @@ mod tests {
     use primitives::u32_trait::{_1, _2};
     use primitives::{Blake2Hasher, H256};
     use runtime_io::TestExternalities;
-    use sr_primitives::weights::Weight;
+    use sr_primitives::weights::{DispatchClass, GetDispatchInfo, Weight};
     use sr_primitives::Perbill;
     use sr_primitives::{
         testing::Header,
@@ -96,6 +96,7 @@ mod tests {
         pub const ReportInterval: u64 = 10;
         pub const ElectionEra: u64 = 10;
         pub const LockedDuration: u64 = 20;
+        pub const Admin: u64 = 10000;
     }
 
     impl Trait for Test {
@@ -105,6 +106,7 @@ mod tests {
         type MinStaking = MinStaking;
 
         // type MaliciousSlashOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, OracleCollective>;
+        type AdminOrigin = system::EnsureSignedBy<Admin, u64>;
         type Count = Count;
         type ReportInteval = ReportInterval;
         type ElectionEra = ElectionEra;
@@ -137,6 +139,13 @@ mod tests {
     // This function basically just builds a genesis storage key/value store according to
     // our desired mockup.
     fn new_test_ext() -> runtime_io::TestExternalities {
+        new_test_ext_with_oracles(vec![], vec![])
+    }
+
+    fn new_test_ext_with_oracles(
+        oracles: Vec<(u64, Balance)>,
+        candidates: Vec<(u64, Balance)>,
+    ) -> runtime_io::TestExternalities {
         let mut t = system::GenesisConfig::default()
             .build_storage::<Test>()
             .unwrap();
@@ -162,6 +171,8 @@ mod tests {
         // Add config for oracle
         GenesisConfig::<Test> {
             cashier_account: CASHIER_ACCOUNT,
+            oracles: oracles,
+            candidates: candidates,
         }
         .assimilate_storage(&mut t)
         .unwrap();
@@ -169,6 +180,22 @@ mod tests {
         t.into()
     }
 
+    #[test]
+    fn it_works_for_genesis_oracles() {
+        new_test_ext_with_oracles(vec![(ALICE, 100), (BOB, 200)], vec![(DAVE, 300)]).execute_with(
+            || {
+                assert_eq!(Oracle::oracles(), [ALICE, BOB]);
+                assert_eq!(Oracle::candidates(), [DAVE]);
+                assert_eq!(Oracle::ledger(BOB).staked, 200);
+                assert_eq!(Oracle::ledger(DAVE).locked, 300);
+                assert_err!(
+                    Oracle::bond(Origin::signed(DAVE), 99_701),
+                    "Cannot stake more funds than owned"
+                );
+            },
+        );
+    }
+
     #[test]
     fn it_works_for_bonding() {
         new_test_ext().execute_with(|| {
@@ -513,4 +540,47 @@ mod tests {
             assert_eq!(info.total_witnessed_jobs, 1);
         })
     }
+
+    #[test]
+    fn it_works_for_admin_operations() {
+        new_test_ext().execute_with(|| {
+            const NEW_CASHIER: u64 = 10002;
+            // The funds of cashier are moved to the new account
+            assert_err!(
+                Oracle::set_cashier_account(Origin::signed(RAY), NEW_CASHIER),
+                "Not authorized"
+            );
+            assert_err!(
+                Oracle::set_cashier_account(Origin::signed(ADMIN_ACCOUNT), NEW_CASHIER),
+                "New account does not exist"
+            );
+            assert_ok!(Balances::transfer(Origin::signed(ALICE), NEW_CASHIER, 1_000));
+            assert_ok!(Oracle::set_cashier_account(
+                Origin::signed(ADMIN_ACCOUNT),
+                NEW_CASHIER
+            ));
+            assert_eq!(Oracle::cashier_account(), NEW_CASHIER);
+            assert_eq!(Balances::free_balance(&NEW_CASHIER), 1_000 + 100_000);
+            assert_eq!(Balances::total_balance(&CASHIER_ACCOUNT), 0);
+            // Rotating the cashier is an operational call
+            let call = Call::<Test>::set_cashier_account(CASHIER_ACCOUNT);
+            assert_eq!(call.get_dispatch_info().class, DispatchClass::Operational);
+        })
+    }
+
+    #[test]
+    fn it_works_for_type_metadata() {
+        use support::metadata::{undescribed_types, ModuleTypeMetadata};
+
+        // The types of storage and events are described
+        assert_eq!(
+            undescribed_types(
+                &Oracle::types(),
+                &Oracle::storage_metadata(),
+                Event::<Test>::metadata(),
+                &[]
+            ),
+            Vec::<String>::new()
+        );
+    }
 }
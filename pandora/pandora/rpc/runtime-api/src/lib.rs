@@ -0,0 +1,28 @@
+//! Runtime API definition for pandora module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use rstd::prelude::*;
+
+pub use pandora::{DboxInfo, Player, RoundInfo, RoundSummary};
+
+sr_api::decl_runtime_apis! {
+    pub trait PandoraApi<AccountId, Hash, Balance, BlockNumber> where
+        AccountId: Codec,
+        Hash: Codec,
+        Balance: Codec,
+        BlockNumber: Codec,
+    {
+        /// Get the dboxes of the player with their pending values
+        fn player_dboxes(who: AccountId) -> Vec<DboxInfo<Hash, Balance>>;
+        /// Get the summary of current round
+        fn round_info() -> RoundInfo<AccountId, Balance>;
+        /// Get the totals of the player
+        fn player_info(who: AccountId) -> Player<Balance>;
+        /// Get the money which would be paid if the dbox is opened right now
+        fn open_payout(dbox_id: Hash) -> Option<Balance>;
+        /// Get the summaries of rounds, from the latest one to the older ones
+        fn round_summaries(from: u64, count: u64) -> Vec<(u64, RoundSummary<AccountId, Balance, BlockNumber>)>;
+    }
+}
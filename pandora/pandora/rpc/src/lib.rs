@@ -0,0 +1,170 @@
+//! RPC interface for the pandora module.
+
+use client::blockchain::HeaderBackend;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use pandora_module_rpc_runtime_api::{DboxInfo, Player, RoundInfo, RoundSummary};
+use sr_primitives::{
+    generic::BlockId,
+    traits::{Block as BlockT, ProvideRuntimeApi},
+};
+use std::sync::Arc;
+
+pub use self::gen_client::Client as PandoraClient;
+pub use pandora_module_rpc_runtime_api::PandoraApi as PandoraRuntimeApi;
+
+#[rpc]
+pub trait PandoraApi<BlockHash, AccountId, Hash, Balance, BlockNumber> {
+    /// Get the dboxes of the player with their pending values
+    #[rpc(name = "pandora_playerDboxes")]
+    fn player_dboxes(
+        &self,
+        who: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<DboxInfo<Hash, Balance>>>;
+
+    /// Get the summary of current round
+    #[rpc(name = "pandora_roundInfo")]
+    fn round_info(&self, at: Option<BlockHash>) -> Result<RoundInfo<AccountId, Balance>>;
+
+    /// Get the totals of the player
+    #[rpc(name = "pandora_playerInfo")]
+    fn player_info(&self, who: AccountId, at: Option<BlockHash>) -> Result<Player<Balance>>;
+
+    /// Get the money which would be paid if the dbox is opened right now
+    #[rpc(name = "pandora_openPayout")]
+    fn open_payout(&self, dbox_id: Hash, at: Option<BlockHash>) -> Result<Option<Balance>>;
+
+    /// Get the summaries of rounds, from the latest one to the older ones
+    #[rpc(name = "pandora_roundSummaries")]
+    fn round_summaries(
+        &self,
+        from: u64,
+        count: u64,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<(u64, RoundSummary<AccountId, Balance, BlockNumber>)>>;
+}
+
+/// A struct that implements the [`PandoraApi`].
+pub struct Pandora<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Pandora<C, B> {
+    /// Create new `Pandora` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Pandora {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+    /// The call to runtime failed.
+    RuntimeError,
+}
+
+impl From<Error> for i64 {
+    fn from(e: Error) -> i64 {
+        match e {
+            Error::RuntimeError => 1,
+        }
+    }
+}
+
+/// Convert the error of runtime api to rpc error
+fn runtime_error<E: std::fmt::Debug>(message: &str, e: E) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(Error::RuntimeError.into()),
+        message: message.into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+impl<C, Block, AccountId, Hash, Balance, BlockNumber>
+    PandoraApi<<Block as BlockT>::Hash, AccountId, Hash, Balance, BlockNumber> for Pandora<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static,
+    C: ProvideRuntimeApi,
+    C: HeaderBackend<Block>,
+    C::Api: PandoraRuntimeApi<Block, AccountId, Hash, Balance, BlockNumber>,
+    AccountId: Codec,
+    Hash: Codec,
+    Balance: Codec,
+    BlockNumber: Codec,
+{
+    fn player_dboxes(
+        &self,
+        who: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<DboxInfo<Hash, Balance>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        api.player_dboxes(&at, who)
+            .map_err(|e| runtime_error("Unable to query dboxes of player.", e))
+    }
+
+    fn round_info(
+        &self,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<RoundInfo<AccountId, Balance>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        api.round_info(&at)
+            .map_err(|e| runtime_error("Unable to query round info.", e))
+    }
+
+    fn player_info(
+        &self,
+        who: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Player<Balance>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        api.player_info(&at, who)
+            .map_err(|e| runtime_error("Unable to query player info.", e))
+    }
+
+    fn open_payout(
+        &self,
+        dbox_id: Hash,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<Balance>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        api.open_payout(&at, dbox_id)
+            .map_err(|e| runtime_error("Unable to query open payout.", e))
+    }
+
+    fn round_summaries(
+        &self,
+        from: u64,
+        count: u64,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<(u64, RoundSummary<AccountId, Balance, BlockNumber>)>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        api.round_summaries(&at, from, count)
+            .map_err(|e| runtime_error("Unable to query round summaries.", e))
+    }
+}
@@ -6,6 +6,7 @@
 #![recursion_limit = "256"]
 
 // mod pandora;
+mod migration;
 #[cfg(test)]
 mod pandora_test;
 
@@ -13,22 +14,83 @@ use support::{
     decl_event, decl_module, decl_storage,
     dispatch::Result,
     ensure,
-    traits::{Currency, ExistenceRequirement, Get, Randomness, ReservableCurrency},
+    metadata::{DescribeType, ModuleTypeMetadata, TypeMetadata},
+    traits::{
+        Currency, ExistenceRequirement, Get, Imbalance, Randomness, ReservableCurrency,
+        WithdrawReason,
+    },
     StorageMap, StorageValue,
 };
 
 use codec::{Decode, Encode};
+use price::{Price, PriceProvider};
 use rstd::prelude::*;
-use sr_primitives::traits::{Hash, Saturating, Zero};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sr_primitives::traits::{
+    CheckedAdd, CheckedMul, CheckedSub, EnsureOrigin, Hash, One, SaturatedConversion, Saturating,
+    UniqueSaturatedFrom, Zero,
+};
+use sr_primitives::weights::{SimpleDispatchInfo, Weight};
+use sr_primitives::Perbill;
 use system::ensure_signed;
 
+/// The version of storage layout, bumped when the layout of stored structs is changed
+const STORAGE_VERSION: u32 = 1;
+
+/// The subject of randomness for drawing jackpot
+const JACKPOT_SUBJECT: &[u8] = b"pandora/jackpot";
+
+/// The weight of reading a storage item
+///
+/// The calls and the settling steps are dominated by the storage access, so their weights are
+/// derived from the reads and writes on their paths, which are reported by `pandora-bench`
+const DB_READ_WEIGHT: Weight = 200;
+/// The weight of writing a storage item, or depositing an event
+const DB_WRITE_WEIGHT: Weight = 500;
+/// The weight of the bookkeeping in `on_finalize`
+const FINALIZE_WEIGHT: Weight = db_weight(6, 2);
+/// The weight consumed by `may_draw_jackpot`
+const DRAW_JACKPOT_WEIGHT: Weight = db_weight(5, 3);
+/// The weight consumed by `begin_settling`
+const BEGIN_SETTLING_WEIGHT: Weight = db_weight(14, 7);
+/// The weight consumed by `end_settling`
+const END_SETTLING_WEIGHT: Weight = db_weight(13, 17);
+/// The weight of migrating an item of storage
+const MIGRATION_ITEM_WEIGHT: Weight = db_weight(4, 4);
+/// The weight of creating a dbox, with the commission of three invitor tiers
+const CREATE_DBOX_WEIGHT: Weight = db_weight(81, 44);
+/// The maximum active dboxes if it is not configured
+const DEFAULT_MAX_ACTIVE_DBOXES_COUNT: u64 = 1000;
+/// The maximum latest dboxes of a player searched for paying the fee of a game move
+const MAX_FEE_DBOXES: u64 = 10;
+
+/// Get the weight of storage reads and writes
+///
+/// @reads  the count of reads
+/// @writes the count of writes
+const fn db_weight(reads: Weight, writes: Weight) -> Weight {
+    reads * DB_READ_WEIGHT + writes * DB_WRITE_WEIGHT
+}
+
+/// Get the weight of claiming, the prizes of the latest dboxes of the round are searched
+///
+/// @max_latest the maximum count of latest dboxes
+fn claim_weight(max_latest: u64) -> Weight {
+    let searched = (max_latest as Weight).saturating_mul(2);
+    db_weight(24, 14).saturating_add(searched.saturating_mul(DB_READ_WEIGHT))
+}
+
 /// Status defines the game status
 /// # Status
 /// - Inited    The game is inited
 /// - Running   The game is running, players can create box, upgrade it or open it
 /// - Settling  The game has expired, and system is settling the pending bonus and prize
+/// - Paused    The game is paused, players can only open or claim their dboxes
 /// - Stopped   The game is stopped
-#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq)]
+/// - EmergencyShutdown The game is shut down, players can only withdraw their money
+#[derive(Encode, Decode, DescribeType, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum Status {
     None,
     Inited,
@@ -36,6 +98,7 @@ pub enum Status {
     Settling,
     Paused,
     Stopped,
+    EmergencyShutdown,
 }
 
 impl Default for Status {
@@ -45,15 +108,14 @@ impl Default for Status {
 }
 
 /// The status definition for dbox
-#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq)]
+#[derive(Encode, Decode, DescribeType, Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum DboxStatus {
     None,
     /// The dbox is locked
     Locked(u64),
     /// The dbox is created
     Active,
-    /// The dbox is opening
-    Opening,
     /// The dbox is opened
     Opened,
 }
@@ -65,7 +127,7 @@ impl Default for DboxStatus {
 }
 
 /// The dbox struct
-#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
 pub struct Dbox<Hash, Balance, AccountId> {
     /// The hash of the dbox
     pub id: Hash,
@@ -79,16 +141,68 @@ pub struct Dbox<Hash, Balance, AccountId> {
     pub version: u64,
     /// The invitor of the dbox
     pub invitor: Option<AccountId>,
-    /// The position when dbox is requested to open
-    pub open_position: u64,
-    /// The per-dbox bonus
-    pub bonus_per_dbox: Balance,
-    /// The bonus position of this dbox, we use it to keep the pending bonus dbox position
-    pub bonus_position: u64,
+    /// The round in which the dbox is created
+    pub round: u64,
+    /// The bonus-per-dbox accumulator of the round when the bonus of dbox was last settled
+    pub bonus_snapshot: Balance,
+}
+
+/// The summary of a round
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct RoundSummary<AccountId, Balance, BlockNumber> {
+    /// The block at which the round starts
+    pub start_block: BlockNumber,
+    /// The block at which the round ends, None if the round is not ended
+    pub end_block: Option<BlockNumber>,
+    /// The count of dboxes created in the round
+    pub dbox_count: u64,
+    /// The money of prize pool when the round ends
+    pub pool: Balance,
+    /// The last player of the round
+    pub last_player: Option<AccountId>,
+    /// The bonus paid for dboxes of the round
+    pub total_bonus: Balance,
+    /// The prize paid for dboxes of the round
+    pub total_prize: Balance,
+    /// The commission paid for dboxes of the round
+    pub total_commission: Balance,
+}
+
+/// The jackpot of a settled round, drawn to a lucky dbox of the round
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Jackpot<Balance, BlockNumber> {
+    /// The amount of jackpot
+    pub amount: Balance,
+    /// The first position of dboxes in the round
+    pub first_position: u64,
+    /// The position after the last dbox in the round
+    pub last_position: u64,
+    /// The block at which the jackpot is drawn, the seed is unknown when the round ends
+    pub draw_at: BlockNumber,
+    /// The position of the lucky dbox, None if the jackpot is not drawn yet
+    pub lucky_position: Option<u64>,
+    /// True if the jackpot is claimed
+    pub claimed: bool,
+}
+
+/// The prize of a settled round, claimed by the owners of the latest dboxes
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
+pub struct Prize<Balance> {
+    /// The prize for each of the latest dboxes
+    pub average_prize: Balance,
+    /// The extra prize for the last player
+    pub last_player_prize: Balance,
+    /// The first index of the latest dboxes
+    pub first_index: u64,
+    /// The index after the last one of the latest dboxes
+    pub last_index: u64,
 }
 
 /// The status of player
-#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq)]
+#[derive(Encode, Decode, DescribeType, Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum PlayerStatus {
     None,
     /// The player is active
@@ -103,8 +217,43 @@ impl Default for PlayerStatus {
     }
 }
 
+/// The policy for pending money of forbidden players
+#[derive(Encode, Decode, DescribeType, Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum BonusPolicy {
+    /// The pending money is kept until the player is unforbidden
+    Freeze,
+    /// The pending money can be redirected to the reserve account
+    Redirect,
+}
+
+impl Default for BonusPolicy {
+    fn default() -> Self {
+        BonusPolicy::Freeze
+    }
+}
+
+/// The system accounts of the game
+#[derive(Encode, Decode, DescribeType, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum SystemAccount {
+    /// The account keeping the money of players
+    Cashier,
+    /// The reserve account
+    Reserve,
+    /// The pool account
+    Pool,
+    /// The last player account
+    LastPlayer,
+    /// The team account
+    Team,
+    /// The operator account
+    Operator,
+}
+
 /// The player struct
-#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
 pub struct Player<Balance> {
     /// Total bonus received
     pub total_bonus: Balance,
@@ -116,16 +265,96 @@ pub struct Player<Balance> {
     pub status: PlayerStatus,
 }
 
+/// The referral stats of an account
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct Referral<Balance> {
+    /// The level of the account in the referral tree, the root is at level 0
+    pub depth: u32,
+    /// The count of direct invitees
+    pub invitees: u64,
+    /// The count of invitees in all commission tiers
+    pub descendants: u64,
+    /// The commission received from each tier
+    pub commissions: Vec<Balance>,
+}
+
+/// The dbox info queried by dashboards
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct DboxInfo<Hash, Balance> {
+    /// The hash of the dbox
+    pub id: Hash,
+    /// The position at which the dbox is created
+    pub create_position: u64,
+    /// The status of dbox
+    pub status: DboxStatus,
+    /// The round in which the dbox is created
+    pub round: u64,
+    /// The version of dbox
+    pub version: u64,
+    /// The pending bonus of dbox
+    pub pending_bonus: Balance,
+    /// True if the pending bonus will be doubled when the dbox is opened
+    pub double: bool,
+    /// The unclaimed prize of dbox
+    pub pending_prize: Balance,
+}
+
+/// The summary of current round queried by dashboards
+#[derive(Encode, Decode, DescribeType, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct RoundInfo<AccountId, Balance> {
+    /// The round number
+    pub round: u64,
+    /// The game status
+    pub status: Status,
+    /// The remaining timeout of the round
+    pub timeout: u32,
+    /// The money of prize pool
+    pub pool: Balance,
+    /// The average prize for latest dboxes
+    pub average_prize: Balance,
+    /// The latest dboxes with their owners and positions
+    pub latest_dboxes: Vec<(AccountId, u64)>,
+}
+
+/// The money of a new dbox, split before any of it is paid
+struct MoneySplit<AccountId, Balance> {
+    /// The bonus added for each active dbox of the round
+    bonus_per_dbox: Balance,
+    /// The money added to the ledger of each system account
+    ledgers: Vec<(AccountId, Balance)>,
+    /// The commission sent to the invitor of each tier
+    commissions: Vec<(AccountId, Balance)>,
+    /// The sum of commissions paid by the cashier
+    total_commission: Balance,
+}
+
 /// The module's configuration trait.
 pub trait Trait: balances::Trait {
     /// Define the expiration in seconds for one round of game
     type Expiration: Get<u32>;
+    /// The seconds elapsed in each block, by which the timeout of round is reduced
+    type BlockTime: Get<u32>;
     /// Max latest dboxes to share the money of prize pool
     type MaxLatest: Get<u64>;
     /// Define min unit price for dbox
     type MinUnitPrice: Get<BalanceOf<Self>>;
     /// Define max unit price of dbox
     type MaxUnitPrice: Get<BalanceOf<Self>>;
+    /// The provider of oracle price for the fiat pricing mode
+    type PriceProvider: PriceProvider<Self::BlockNumber>;
+    /// The maximum age in blocks of the oracle price
+    type MaxPriceAge: Get<Self::BlockNumber>;
+    /// The amount of balance for one token
+    type TokenUnit: Get<BalanceOf<Self>>;
+    /// The source of randomness
+    type Randomness: Randomness<Self::Hash>;
+    /// The maximum count of round summaries kept in storage
+    type MaxRounds: Get<u64>;
+    /// The maximum count of items migrated in one block
+    type MigrationBatchSize: Get<u64>;
     /// The bonus ratio for previous active dbox
     type DboxRatio: Get<u32>;
     /// The reserve ratio for the dbox
@@ -138,8 +367,16 @@ pub trait Trait: balances::Trait {
     type TeamRatio: Get<u32>;
     /// The operator ratio
     type OperatorRatio: Get<u32>;
-    /// The invitor ratio
-    type InvitorRatio: Get<u32>;
+    /// The commission ratios for invitors, from the direct invitor to the upper ones
+    type InvitorRatios: Get<Vec<u32>>;
+    /// The royalty ratio paid to operator when a listed dbox is sold
+    type RoyaltyRatio: Get<u32>;
+    /// The period in blocks in which the fees of a player are subsidized at most `MaxSubsidies` times
+    type SubsidyPeriod: Get<Self::BlockNumber>;
+    /// The maximum count of fees subsidized by the operator for a player in one period
+    type MaxSubsidies: Get<u32>;
+    /// The origin which can manage the game
+    type AdminOrigin: EnsureOrigin<Self::Origin>;
     /// The currency type
     type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
     /// The overarching event type.
@@ -149,6 +386,17 @@ pub trait Trait: balances::Trait {
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 type DboxOf<T> = Dbox<<T as system::Trait>::Hash, BalanceOf<T>, <T as system::Trait>::AccountId>;
 type PlayerOf<T> = Player<BalanceOf<T>>;
+type ReferralOf<T> = Referral<BalanceOf<T>>;
+type DboxInfoOf<T> = DboxInfo<<T as system::Trait>::Hash, BalanceOf<T>>;
+type RoundInfoOf<T> = RoundInfo<<T as system::Trait>::AccountId, BalanceOf<T>>;
+type PrizeOf<T> = Prize<BalanceOf<T>>;
+type RoundSummaryOf<T> = RoundSummary<
+    <T as system::Trait>::AccountId,
+    BalanceOf<T>,
+    <T as system::Trait>::BlockNumber,
+>;
+type JackpotOf<T> = Jackpot<BalanceOf<T>, <T as system::Trait>::BlockNumber>;
+type MoneySplitOf<T> = MoneySplit<<T as system::Trait>::AccountId, BalanceOf<T>>;
 
 decl_event!(
     pub enum Event<T>
@@ -156,31 +404,62 @@ decl_event!(
         Hash = <T as system::Trait>::Hash,
         BlockNumber = <T as system::Trait>::BlockNumber,
         AccountId = <T as system::Trait>::AccountId,
+        Balance = BalanceOf<T>,
     {
         /// New dbox is created
         DboxCreated(Hash, AccountId),
-        /// Dobx is opening
-        DboxOpening(Hash, AccountId),
         /// Dobx is opened
         DboxOpened(Hash),
         /// Dbox is upgraded
         DboxUpgraded(Hash, AccountId),
+        /// Bonus and prize of dbox are claimed
+        DboxClaimed(Hash, AccountId, Balance, Balance),
+        /// Dbox is transferred
+        DboxTransferred(Hash, AccountId, AccountId),
+        /// Dbox is listed for sale
+        DboxListed(Hash, AccountId, Balance),
+        /// Listing of dbox is cancelled
+        DboxUnlisted(Hash, AccountId),
+        /// Dbox is sold, with the price and the royalty
+        DboxSold(Hash, AccountId, AccountId, Balance, Balance),
         /// Game is inited
-        GameInited(BlockNumber, AccountId),
-        /// Game is inited
-        GameRunning(BlockNumber, Option<AccountId>),
+        GameInited(BlockNumber),
+        /// Game is running
+        GameRunning(BlockNumber),
         /// Game is settling
         GameSettling(BlockNumber),
+        /// Game is paused
+        GamePaused(BlockNumber),
         /// Game is stopped
-        GameStopped(BlockNumber, AccountId),
+        GameStopped(BlockNumber),
+        /// Game is shut down in emergency, with the share of reserve and pool for each active dbox
+        GameShutdown(BlockNumber, Balance),
+        /// Money of the dbox is withdrawn in emergency
+        EmergencyWithdrawn(Hash, AccountId, Balance),
+        /// System account is changed from the old account to the new one
+        SystemAccountChanged(SystemAccount, AccountId, AccountId),
+        /// Storage is migrated to the version
+        StorageMigrated(u32),
+        /// Unit price of dbox is changed
+        UnitPriceChanged(Balance),
+        /// Jackpot of the round is drawn to the lucky dbox
+        JackpotDrawn(u64, Hash, Balance),
+        /// Player is forbidden with the reason code
+        PlayerForbidden(AccountId, u32),
+        /// Player is unforbidden
+        PlayerUnforbidden(AccountId),
+        /// Pending money of the dbox owned by a forbidden player is redirected to the reserve
+        BonusRedirected(Hash, AccountId, Balance),
+        /// Fee of a game move is paid out of the value of the dbox
+        FeePaidByDbox(Hash, AccountId, Balance),
+        /// Fee of a game move is subsidized by the operator
+        FeeSubsidized(AccountId, Balance),
     }
 );
 
 // This module's storage items.
 decl_storage! {
     trait Store for Module<T: Trait> as PandoraModule {
-        /// The admin account
-        AdminAccount get(admin_account) config(): T::AccountId;
         /// The cashier account
         CashierAccount get(cashier_account) config(): T::AccountId;
         /// The reserve account
@@ -201,12 +480,25 @@ decl_storage! {
         Timeout get(timeout): u32;
         /// The unit price of dbox
         DboxUnitPrice get(dbox_unit_price): BalanceOf<T>;
+        /// The fiat price of dbox in the same precision as oracle price, None if pricing is fixed
+        FiatUnitPrice get(fiat_unit_price): Option<Price>;
         /// The round number
         RoundCount get(round_count): u64;
         // The start position of current round
         RoundStartDbox get(round_start_dbox): u64;
-        // The bonus dbox position of current round
-        BonusDbox get(bonus_dbox): u64;
+        /// The accumulated bonus for each active dbox of a round, kept for the dboxes to claim
+        /// whether the summary of the round is pruned or not
+        BonusPerDbox get(bonus_per_dbox): map u64 => BalanceOf<T>;
+        /// The prize of each settled round, kept for the latest dboxes to claim like the bonus
+        RoundPrizes get(round_prize): map u64 => PrizeOf<T>;
+        /// The percentage of prize pool drawn to a lucky dbox of the round
+        JackpotRatio get(jackpot_ratio): u32;
+        /// The jackpot of each settled round
+        Jackpots get(jackpot): map u64 => Option<JackpotOf<T>>;
+        /// The round whose jackpot is waiting to be drawn
+        PendingJackpotRound get(pending_jackpot_round): Option<u64>;
+        /// The summaries of latest rounds
+        Rounds get(round_summary): map u64 => Option<RoundSummaryOf<T>>;
         // All the dboxes
         DboxOwner get(owner_of): map T::Hash => Option<T::AccountId>;
         /// The active dbox count
@@ -221,37 +513,85 @@ decl_storage! {
         AllDboxesCount get(all_dboxes_count): u64;
         /// The map for hash to position in array of dboxes
         AllDboxesIndex: map T::Hash => u64;
-        /// All opening dboxes
-        /// Get index of array by create positon
-        AllOpeningDboxesMap get(opening_dbox_by_position): map u64 => u64;
-        /// Get create position by index of array
-        AllOpeningDboxesArray get(opening_dbox_by_index): map u64 => u64;
-        /// All opening dboxes count
-        AllOpeningDboxesCount get(all_opening_doxes_count): u64;
         /// The owned dboxes array
         OwnedDboxesArray get(dbox_of_owner_by_index): map (T::AccountId, u64) => T::Hash;
         /// The count of dbox owned by some a account
         OwnedDboxesCount get(owned_dbox_count): map T::AccountId => u64;
         /// The map between hash to position of dboxes for some a account
         OwnedDboxesIndex: map T::Hash => u64;
+        /// The price of listed dboxes
+        DboxListings get(dbox_listing): map T::Hash => Option<BalanceOf<T>>;
         /// Latest dboxes
         LatestDboxes get(latest_dbox_by_index): map u64 => (T::AccountId, u64);
         /// The last dboxes index
         LastDboxIndex get(last_dbox_index): u64;
         /// The count of latest dboxes
         LatestDboxesCount get(latest_dboxes_count): u64;
-        /// The count of latest dboxes which have received prize
-        ReleasedDboxesCount get(released_dboxes_count): u64;
         /// The average prize for latest dboxes
         AveragePrize get(average_prize): BalanceOf<T>;
         // All Players
         AllPlayers get(player): map T::AccountId => PlayerOf<T>;
         /// The count of all players
         AllPlayersCount get(player_count): u64;
-        /// The maximus ops for each block
-        MaxOps get(max_ops): u32;
+        /// The reason code why the player is forbidden
+        ForbiddenReasons get(forbidden_reason): map T::AccountId => Option<u32>;
+        /// The policy for pending money of forbidden players
+        ForbiddenBonusPolicy get(forbidden_bonus_policy): BonusPolicy;
+        /// The invitor of player, recorded once at the first dbox of player
+        Invitors get(invitor_of): map T::AccountId => Option<T::AccountId>;
+        /// The referral stats of account
+        Referrals get(referral): map T::AccountId => ReferralOf<T>;
+        /// The share of reserve, pool and prize of last player for each active dbox of current round
+        /// after shutdown
+        EmergencyShare get(emergency_share): BalanceOf<T>;
+        /// The start block of current subsidy period of player and the count of fees subsidized in it
+        Subsidies get(subsidy_of): map T::AccountId => (T::BlockNumber, u32);
+        /// The fraction of maximum block weight which can be used for settling
+        SettlementWeightRatio get(settlement_weight_ratio): Perbill = Perbill::from_percent(10);
+        /// The weight of settling reserved in `on_initialize` of current block
+        SettlementWeight: Weight;
         /// The nonce value for hash of dbox
         Nonce: u64;
+        /// The version of storage layout, new chains start with the latest version
+        StorageVersion get(storage_version) build(|_| STORAGE_VERSION): u32;
+        /// The position of next item to be migrated
+        MigrationCursor get(migration_cursor): u64;
+    }
+    add_extra_genesis {
+        // The game is inited at genesis if the unit price is set
+        config(dbox_unit_price): Option<BalanceOf<T>>;
+        // Either inited or running, inited by default
+        config(status): Option<Status>;
+        config(max_active_dboxes_count): Option<u64>;
+        config(settlement_weight_ratio): Option<Perbill>;
+        build(|config: &Self| {
+            let dbox_unit_price = match config.dbox_unit_price {
+                Some(dbox_unit_price) => dbox_unit_price,
+                None => {
+                    assert!(config.status.is_none(), "Unit price is required to init the game");
+                    return;
+                }
+            };
+            let max_active_dboxes_count = config
+                .max_active_dboxes_count
+                .unwrap_or(DEFAULT_MAX_ACTIVE_DBOXES_COUNT);
+            Module::<T>::check_unit_price(dbox_unit_price).expect("Invalid unit price of dbox");
+            assert!(
+                max_active_dboxes_count > 0 && max_active_dboxes_count < 1_000_000,
+                "Invalid max active dboxes count"
+            );
+
+            Module::<T>::do_init(dbox_unit_price, max_active_dboxes_count);
+            if let Some(ratio) = config.settlement_weight_ratio {
+                assert!(!ratio.is_zero(), "Invalid settlement weight ratio");
+                SettlementWeightRatio::put(ratio);
+            }
+            match config.status.unwrap_or(Status::Inited) {
+                Status::Inited => {},
+                Status::Running => Module::<T>::start_game().expect("Game can not be started"),
+                _ => panic!("Game should be either inited or running at genesis"),
+            }
+        })
     }
 }
 
@@ -265,10 +605,15 @@ decl_module! {
 
         // Expiration for a round
         const Expiration: u32 = T::Expiration::get();
+        const BlockTime: u32 = T::BlockTime::get();
         const MaxLatest: u64 = T::MaxLatest::get();
         // Price limitation
         const MinUnitPrice: BalanceOf<T> = T::MinUnitPrice::get();
         const MaxUnitPrice: BalanceOf<T> = T::MaxUnitPrice::get();
+        const MaxPriceAge: T::BlockNumber = T::MaxPriceAge::get();
+        const TokenUnit: BalanceOf<T> = T::TokenUnit::get();
+        const MaxRounds: u64 = T::MaxRounds::get();
+        const MigrationBatchSize: u64 = T::MigrationBatchSize::get();
         // Ratios for bonus
         const DboxRatio: u32 = T::DboxRatio::get();
         const ReserveRatio: u32 = T::ReserveRatio::get();
@@ -276,116 +621,229 @@ decl_module! {
         const LastPlayerRatio: u32 = T::LastPlayerRatio::get();
         const TeamRatio: u32 = T::TeamRatio::get();
         const OperatorRatio: u32 = T::OperatorRatio::get();
-        const InvitorRatio: u32 = T::InvitorRatio::get();
+        const InvitorRatios: Vec<u32> = T::InvitorRatios::get();
+        const RoyaltyRatio: u32 = T::RoyaltyRatio::get();
 
         /// Initialize the game with price
         ///
         /// @origin
         /// @dbox_unit_price    the price of dbox
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(3, 8))]
         pub fn init(origin, dbox_unit_price: BalanceOf<T>) -> Result {
-            // Check signature
-            let sender = ensure_signed(origin)?;
             // Check priviledge
-            ensure!(sender == Self::admin_account(), "Not authorized");
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
             ensure!(!GameStatus::exists(), "Already inited");
-            // Check unit price
-            ensure!(dbox_unit_price > T::MinUnitPrice::get(), "Unit price is too low");
-            ensure!(dbox_unit_price <= T::MaxUnitPrice::get(), "Unit price is too high");
-            // Init each account of ledger
-            let accounts: Vec<T::AccountId> = vec![Self::admin_account(), Self::cashier_account(), Self::reserve_account(),
-                Self::pool_account(), Self::last_player_account(), Self::team_account(), Self::operator_account()];
-
-            for account in accounts.iter() {
-                let balance = <BalanceOf<T>>::zero();
-                <Ledger<T>>::insert(account, balance);
-            }
-            // TODO: config?
-            let default_max_value = 1000;
-            MaxActiveDboxesCount::put(&default_max_value);
-            MaxPresetActiveDboxesCount::put(&default_max_value);
-
-            GameStatus::put(Status::Inited);
-            Timeout::put(T::Expiration::get());
-            <DboxUnitPrice<T>>::put(dbox_unit_price);
-            RoundCount::put(1);
-            RoundStartDbox::put(0);
-            BonusDbox::put(0);
-            <AveragePrize<T>>::put(<BalanceOf<T>>::zero());
-            MaxOps::put(100);
+            Self::check_unit_price(dbox_unit_price)?;
+
+            Self::do_init(dbox_unit_price, DEFAULT_MAX_ACTIVE_DBOXES_COUNT);
             // Trigger event
-            Self::deposit_event(RawEvent::GameInited(Self::block_number(), sender.clone()));
+            Self::deposit_event(RawEvent::GameInited(Self::block_number()));
             Ok(())
         }
 
-        /// Set the new status for the game
+        /// Set the new status for the game, the following transitions are permitted
+        ///
+        /// 1. Inited -> Running
+        /// 2. Running -> Paused
+        /// 3. Paused -> Running
+        /// 4. Running -> Stopped, when there is no pending dbox
+        /// 5. Running, Settling, Paused or Stopped -> EmergencyShutdown
         ///
         /// @origin
         /// @new_status new status of the system
-        // pub fn set_status(origin, new_status: Status) -> Result {
-        //     let sender = ensure_signed(origin)?;
-        //     ensure!(sender == Self::admin_account(), "Not authorized");
-        //     ensure!(GameStatus::exists(), "Not inited");
-        //     ensure!(new_status != Status::Inited, "Invalid new status");
-        //     ensure!(new_status != GameStatus::get(), "New status should be different from current status");
-
-        //     GameStatus::put(new_status);
-        //     let block_number = Self::block_number();
-        //     // Trigger event
-        //     match new_status {
-        //         Status::Running => Self::deposit_event(RawEvent::GameRunning(block_number, Some(sender.clone()))),
-        //         Status::Stopped => Self::deposit_event(RawEvent::GameStopped(block_number, sender.clone())),
-        //         _ => (),
-        //     }
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(10, 8))]
+        pub fn set_status(origin, new_status: Status) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
+            ensure!(GameStatus::exists(), "Not inited");
 
-        //     Ok(())
-        // }
+            let status = GameStatus::get();
+            ensure!(new_status != status, "New status should be different from current status");
+
+            let block_number = Self::block_number();
+            match (status, new_status) {
+                (Status::Inited, Status::Running) => {
+                    Self::start_game()?;
+                    Self::deposit_event(RawEvent::GameRunning(block_number));
+                },
+                (Status::Paused, Status::Running) => {
+                    GameStatus::put(new_status);
+                    Self::deposit_event(RawEvent::GameRunning(block_number));
+                },
+                (Status::Running, Status::Paused) => {
+                    GameStatus::put(new_status);
+                    Self::deposit_event(RawEvent::GamePaused(block_number));
+                },
+                (Status::Running, Status::Stopped) => {
+                    ensure!(!Self::has_pending_dboxes(), "Pending dboxes exist");
+                    GameStatus::put(new_status);
+                    Self::deposit_event(RawEvent::GameStopped(block_number));
+                },
+                (Status::Running, Status::EmergencyShutdown)
+                | (Status::Settling, Status::EmergencyShutdown)
+                | (Status::Paused, Status::EmergencyShutdown)
+                | (Status::Stopped, Status::EmergencyShutdown) => {
+                    Self::shutdown();
+                },
+                _ => return Err("Invalid status transition"),
+            }
+
+            Ok(())
+        }
 
-        /// Set the new status for the game
+        /// Set the fiat price of dbox, the unit price is recomputed from oracle price each round
         ///
         /// @origin
-        /// @value new status of the system
-        pub fn set_status(origin, value: u8) -> Result {
-            let sender = ensure_signed(origin)?;
-            ensure!(sender == Self::admin_account(), "Not authorized");
+        /// @fiat_unit_price    the fiat price, None for fixed unit price
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(1, 1))]
+        pub fn set_fiat_unit_price(origin, fiat_unit_price: Option<Price>) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
             ensure!(GameStatus::exists(), "Not inited");
+            ensure!(fiat_unit_price != Some(0), "Fiat unit price should not be zero");
 
-            let new_status: Status = match value {
-                0 => Status::None,
-                1 => Status::Inited,
-                2 => Status::Running,
-                3 => Status::Settling,
-                4 => Status::Paused,
-                5 => Status::Stopped,
-                _ => return Err("Invalid status value"),
-            };
-
-            ensure!(new_status != Status::Inited, "Invalid new status");
-            ensure!(new_status != GameStatus::get(), "New status should be different from current status");
-
-            GameStatus::put(new_status);
-            let block_number = Self::block_number();
-            // Trigger event
-            match new_status {
-                Status::Running => Self::deposit_event(RawEvent::GameRunning(block_number, Some(sender.clone()))),
-                Status::Stopped => Self::deposit_event(RawEvent::GameStopped(block_number, sender.clone())),
-                _ => (),
+            match fiat_unit_price {
+                Some(fiat_unit_price) => FiatUnitPrice::put(fiat_unit_price),
+                None => FiatUnitPrice::kill(),
             }
+            Ok(())
+        }
+
+        /// Set the percentage of prize pool drawn to a lucky dbox
+        ///
+        /// @origin
+        /// @ratio  the percentage of prize pool
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(2, 1))]
+        pub fn set_jackpot_ratio(origin, ratio: u32) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
+            ensure!(GameStatus::exists(), "Not inited");
+            ensure!(ratio != Self::jackpot_ratio(), "New value should be different from current value");
+            ensure!(ratio <= 100, "Invalid range");
 
+            JackpotRatio::put(ratio);
             Ok(())
         }
 
-        /// Set the maximus ops for each block
+        /// Set the fraction of maximum block weight which can be used for settling
         ///
         /// @origin
-        /// @new_max_ops new max ops
-        pub fn set_max_ops(origin, new_max_ops: u32) -> Result {
-            let sender = ensure_signed(origin)?;
-            ensure!(sender == Self::admin_account(), "Not authorized");
+        /// @ratio  new weight ratio
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(2, 1))]
+        pub fn set_settlement_weight_ratio(origin, ratio: Perbill) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
             ensure!(GameStatus::exists(), "Not inited");
-            ensure!(new_max_ops != Self::max_ops(), "New value should be different from current value");
-            ensure!(new_max_ops > 0 && new_max_ops <= 10_000, "Invalid range"); // FIXME: (0, 10_000]
+            ensure!(ratio != Self::settlement_weight_ratio(), "New value should be different from current value");
+            ensure!(!ratio.is_zero(), "Invalid range");
+
+            SettlementWeightRatio::put(ratio);
+            Ok(())
+        }
+
+        /// Forbid the player from playing, the pending money is handled by the bonus policy
+        ///
+        /// @origin
+        /// @who    the player
+        /// @reason the reason code
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(2, 3))]
+        pub fn forbid_player(origin, who: T::AccountId, reason: u32) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
+            ensure!(<AllPlayers<T>>::exists(&who), "Player does not exist");
+            let mut player = Self::player(&who);
+            ensure!(player.status == PlayerStatus::Active, "Player is not active");
+
+            player.status = PlayerStatus::Forbidden;
+            <AllPlayers<T>>::insert(&who, player);
+            <ForbiddenReasons<T>>::insert(&who, reason);
+            Self::deposit_event(RawEvent::PlayerForbidden(who, reason));
+            Ok(())
+        }
+
+        /// Unforbid the player, the frozen money can be claimed again
+        ///
+        /// @origin
+        /// @who    the player
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(1, 3))]
+        pub fn unforbid_player(origin, who: T::AccountId) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
+            let mut player = Self::player(&who);
+            ensure!(player.status == PlayerStatus::Forbidden, "Player is not forbidden");
+
+            player.status = PlayerStatus::Active;
+            <AllPlayers<T>>::insert(&who, player);
+            <ForbiddenReasons<T>>::remove(&who);
+            Self::deposit_event(RawEvent::PlayerUnforbidden(who));
+            Ok(())
+        }
+
+        /// Set the policy for pending money of forbidden players
+        ///
+        /// @origin
+        /// @policy the bonus policy
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(1, 1))]
+        pub fn set_forbidden_bonus_policy(origin, policy: BonusPolicy) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
+            ensure!(policy != Self::forbidden_bonus_policy(), "New value should be different from current value");
+
+            ForbiddenBonusPolicy::put(policy);
+            Ok(())
+        }
+
+        /// Redirect the pending money of the dbox owned by a forbidden player to the reserve account
+        ///
+        /// @origin
+        /// @dbox_id    the dbox id
+        #[weight = SimpleDispatchInfo::FixedOperational(claim_weight(T::MaxLatest::get()))]
+        pub fn redirect_forbidden_bonus(origin, dbox_id: T::Hash) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
+            ensure!(Self::forbidden_bonus_policy() == BonusPolicy::Redirect, "Bonus of forbidden player is frozen");
+            let _ = Self::ensure_status(vec![Status::Running, Status::Settling, Status::Paused])?;
+            let owner = Self::owner_of(dbox_id).ok_or("Dbox does not exist")?;
+            ensure!(Self::is_forbidden(&owner), "Player is not forbidden");
+
+            Self::do_claim(&owner, dbox_id, true, false)
+        }
 
-            MaxOps::put(new_max_ops);
+        /// Rotate the system account, the ledger balance is moved to the new account
+        ///
+        /// The free and reserved funds of cashier are repatriated without transfer fee, so the new
+        /// cashier should exist
+        ///
+        /// @origin
+        /// @kind   the kind of system account
+        /// @new_account    the new account
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(10, 8))]
+        pub fn set_system_account(origin, kind: SystemAccount, new_account: T::AccountId) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
+            ensure!(!<Ledger<T>>::exists(&new_account), "System account is not allowed");
+            ensure!(!<AllPlayers<T>>::exists(&new_account), "Player is not allowed");
+            ensure!(Self::owned_dbox_count(&new_account) == 0, "Player is not allowed");
+
+            let old_account = Self::system_account(kind);
+            if kind == SystemAccount::Cashier {
+                ensure!(
+                    !T::Currency::total_balance(&new_account).is_zero(),
+                    "New account does not exist"
+                );
+                // The money of players is kept by the cashier
+                T::Currency::reserve(&old_account, T::Currency::free_balance(&old_account))?;
+                let _ = T::Currency::repatriate_reserved(
+                    &old_account,
+                    &new_account,
+                    T::Currency::reserved_balance(&old_account),
+                )?;
+            }
+            // From now on, all state transition operations should be infailable
+            if <Ledger<T>>::exists(&old_account) {
+                let balance = <Ledger<T>>::take(&old_account);
+                <Ledger<T>>::insert(&new_account, balance);
+            }
+            match kind {
+                SystemAccount::Cashier => <CashierAccount<T>>::put(&new_account),
+                SystemAccount::Reserve => <ReserveAccount<T>>::put(&new_account),
+                SystemAccount::Pool => <PoolAccount<T>>::put(&new_account),
+                SystemAccount::LastPlayer => <LastPlayerAccount<T>>::put(&new_account),
+                SystemAccount::Team => <TeamAccount<T>>::put(&new_account),
+                SystemAccount::Operator => <OperatorAccount<T>>::put(&new_account),
+            }
+            Self::deposit_event(RawEvent::SystemAccountChanged(kind, old_account, new_account));
             Ok(())
         }
 
@@ -393,9 +851,9 @@ decl_module! {
         ///
         /// @origin
         /// @max_active_dboxes_count    maximum active dboxes permitted
+        #[weight = SimpleDispatchInfo::FixedOperational(db_weight(1, 1))]
         pub fn preset_max_active_dboxes_count(origin, max_active_dboxes_count: u64) -> Result {
-            let sender = ensure_signed(origin)?;
-            ensure!(sender == Self::admin_account(), "Not authorized");
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
             ensure!(max_active_dboxes_count > 0 && max_active_dboxes_count < 1_000_000, "Invalid preset max active dboxes count");
             ensure!(max_active_dboxes_count != Self::max_preset_active_dboxes_count(), "New value should be different from current value");
 
@@ -422,6 +880,7 @@ decl_module! {
         /// Create a dbox
         ///
         /// @origin the creator
+        #[weight = SimpleDispatchInfo::FixedNormal(CREATE_DBOX_WEIGHT)]
         pub fn create_dbox(origin) -> Result {
             let invitor = None;
             let sender = ensure_signed(origin)?;
@@ -429,6 +888,7 @@ decl_module! {
             let _ = Self::ensure_status(vec![Status::Running])?;
             // Check if the account is system account
             ensure!(!<Ledger<T>>::exists(&sender), "System account is not allowed");
+            Self::ensure_not_forbidden(&sender)?;
 
             let _ = Self::do_create_dbox(&sender, invitor, true)?;
             // TODO: cashier_account?
@@ -439,12 +899,14 @@ decl_module! {
         ///
         /// @origin the creator
         /// @invitor the invitor of the new dbox
+        #[weight = SimpleDispatchInfo::FixedNormal(CREATE_DBOX_WEIGHT + db_weight(10, 8))]
         pub fn create_dbox_with_invitor(origin, invitor: Option<T::AccountId>) -> Result {
             let sender = ensure_signed(origin)?;
             let _ = Self::check_inviting(&invitor, &sender)?;
             let _ = Self::ensure_status(vec![Status::Running])?;
             // Check if the account is system account
             ensure!(!<Ledger<T>>::exists(&sender), "System account is not allowed");
+            Self::ensure_not_forbidden(&sender)?;
 
             let _ = Self::do_create_dbox(&sender, invitor, true)?;
             // TODO: cashier_account?
@@ -461,6 +923,7 @@ decl_module! {
         ///
         /// @origin
         /// @dbox_id    the dbox id
+        #[weight = SimpleDispatchInfo::FixedNormal(db_weight(30, 15))]
         pub fn open_dbox(origin, dbox_id: T::Hash) -> Result {
             let sender = ensure_signed(origin)?;
             Self::open_dbox_by_id(&sender, dbox_id)
@@ -470,6 +933,7 @@ decl_module! {
         ///
         /// @origin
         /// @index  the index of dbox owned by origin
+        #[weight = SimpleDispatchInfo::FixedNormal(db_weight(32, 15))]
         pub fn open_dbox_by_index(origin, index: u64) -> Result {
             let sender = ensure_signed(origin)?;
             ensure!(<OwnedDboxesArray<T>>::exists((sender.clone(), index)), "Dbox does not exist");
@@ -481,6 +945,7 @@ decl_module! {
         ///
         /// @origin
         /// @dbox_id    the dbox id
+        #[weight = SimpleDispatchInfo::FixedNormal(db_weight(85, 45))]
         pub fn upgrade_dbox(origin, dbox_id: T::Hash) -> Result {
             let sender = ensure_signed(origin)?;
             Self::upgrade_dbox_by_id(&sender, dbox_id)
@@ -490,6 +955,7 @@ decl_module! {
         ///
         /// @origin
         /// @dbox_id    the dbox id
+        #[weight = SimpleDispatchInfo::FixedNormal(db_weight(87, 45))]
         pub fn upgrade_dbox_by_index(origin, index: u64) -> Result {
             let sender = ensure_signed(origin)?;
             ensure!(<OwnedDboxesArray<T>>::exists((sender.clone(), index)), "Dbox does not exist");
@@ -498,54 +964,222 @@ decl_module! {
             Self::upgrade_dbox_by_id(&sender, dbox_id)
         }
 
+        /// Claim the pending bonus of the dbox and the prize it won in a settled round
+        ///
+        /// @origin
+        /// @dbox_id    the dbox id
+        #[weight = SimpleDispatchInfo::FixedNormal(claim_weight(T::MaxLatest::get()))]
+        pub fn claim(origin, dbox_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::claim_dbox_by_id(&sender, dbox_id)
+        }
+
+        /// Withdraw the money of dbox after the game is shut down, including the pending bonus and
+        /// prize, and the emergency share if the dbox is active in current round
+        ///
+        /// @origin
+        /// @dbox_id    the dbox id
+        #[weight = SimpleDispatchInfo::FixedNormal(claim_weight(T::MaxLatest::get()) + db_weight(2, 1))]
+        pub fn emergency_withdraw(origin, dbox_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            let _ = Self::ensure_status(vec![Status::EmergencyShutdown])?;
+            Self::ensure_owner(&sender, dbox_id)?;
+            // Forbidden players withdraw too, nothing can be frozen for later once the game is over
+
+            Self::do_claim(&sender, dbox_id, false, true)
+        }
+
+        /// Transfer the dbox to another player
+        ///
+        /// @origin
+        /// @dbox_id    the dbox id
+        /// @to the receiver of the dbox
+        #[weight = SimpleDispatchInfo::FixedNormal(db_weight(12, 10))]
+        pub fn transfer_dbox(origin, dbox_id: T::Hash, to: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+            let _ = Self::ensure_status(vec![Status::Running, Status::Settling, Status::Paused])?;
+            Self::ensure_owner(&sender, dbox_id)?;
+            Self::ensure_not_forbidden(&sender)?;
+            ensure!(sender != to, "Can not transfer dbox to the sender");
+            ensure!(!<Ledger<T>>::exists(&to), "System account is not allowed");
+
+            Self::do_transfer_dbox(&sender, &to, dbox_id)
+        }
+
+        /// List the active dbox for sale at a fixed price
+        ///
+        /// @origin
+        /// @dbox_id    the dbox id
+        /// @price  the price of the dbox
+        #[weight = SimpleDispatchInfo::FixedNormal(db_weight(7, 2))]
+        pub fn list_dbox(origin, dbox_id: T::Hash, price: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            let _ = Self::ensure_status(vec![Status::Running])?;
+            Self::ensure_owner(&sender, dbox_id)?;
+            Self::ensure_not_forbidden(&sender)?;
+            ensure!(!price.is_zero(), "Price should not be zero");
+
+            let dbox = Self::get_dbox_by_id(dbox_id).unwrap();
+            ensure!(
+                dbox.status == DboxStatus::Active,
+                "The status of dbox should be active"
+            );
+
+            <DboxListings<T>>::insert(dbox_id, price);
+            Self::deposit_event(RawEvent::DboxListed(dbox_id, sender, price));
+            Ok(())
+        }
+
+        /// Cancel the listing of dbox
+        ///
+        /// @origin
+        /// @dbox_id    the dbox id
+        #[weight = SimpleDispatchInfo::FixedNormal(db_weight(4, 2))]
+        pub fn unlist_dbox(origin, dbox_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_owner(&sender, dbox_id)?;
+            ensure!(<DboxListings<T>>::exists(dbox_id), "Dbox is not listed");
+
+            <DboxListings<T>>::remove(dbox_id);
+            Self::deposit_event(RawEvent::DboxUnlisted(dbox_id, sender));
+            Ok(())
+        }
+
+        /// Buy the listed dbox, the price is escrowed and paid before the dbox is transferred
+        ///
+        /// @origin
+        /// @dbox_id    the dbox id
+        /// @max_price  the maximum price the buyer accepts
+        #[weight = SimpleDispatchInfo::FixedNormal(db_weight(20, 16))]
+        pub fn buy_dbox(origin, dbox_id: T::Hash, max_price: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            let _ = Self::ensure_status(vec![Status::Running])?;
+            ensure!(<DboxOwner<T>>::exists(dbox_id), "Dbox does not exist");
+            let price = Self::dbox_listing(dbox_id).ok_or("Dbox is not listed")?;
+            ensure!(price <= max_price, "Price is higher than expected");
+            ensure!(!<Ledger<T>>::exists(&sender), "System account is not allowed");
+            Self::ensure_not_forbidden(&sender)?;
+
+            let seller = Self::owner_of(dbox_id).ok_or("Dbox does not exist")?;
+            ensure!(seller != sender, "Can not buy dbox from the sender");
+            ensure!(!Self::is_forbidden(&seller), "Seller is forbidden");
+            let dbox = Self::get_dbox_by_id(dbox_id).unwrap();
+            ensure!(
+                dbox.status == DboxStatus::Active,
+                "The status of dbox should be active"
+            );
+            // Money can only be repatriated to existing accounts
+            let operator = Self::operator_account();
+            ensure!(!T::Currency::total_balance(&seller).is_zero(), "Seller account does not exist");
+            ensure!(!T::Currency::total_balance(&operator).is_zero(), "Operator account does not exist");
+            Self::check_transfer(&seller, &sender)?;
+            let royalty = price
+                .checked_mul(&T::RoyaltyRatio::get().into())
+                .ok_or("Overflow calculating the royalty")?
+                / 100.into();
+            // Escrow the payment of buyer
+            T::Currency::reserve(&sender, price)?;
+            // Pay the royalty to operator, and the rest to seller
+            let _ = T::Currency::repatriate_reserved(&sender, &operator, royalty)?;
+            let _ = T::Currency::repatriate_reserved(&sender, &seller, price.saturating_sub(royalty))?;
+            // From now on, all state transition operations should be infailable
+            <DboxListings<T>>::remove(dbox_id);
+            Self::do_transfer_dbox(&seller, &sender, dbox_id)?;
+
+            Self::deposit_event(RawEvent::DboxSold(dbox_id, seller, sender, price, royalty));
+            Ok(())
+        }
+
+        /// Callback when a block is initialized
+        ///
+        /// @n  the block number
+        fn on_initialize(_n: T::BlockNumber) {
+            // Migrate the storage of old layout in batches
+            if Self::is_migrating() {
+                Self::migrate_storage();
+                let batch: Weight = T::MigrationBatchSize::get().saturated_into();
+                <system::Module<T>>::register_extra_weight_unchecked(
+                    batch.saturating_mul(MIGRATION_ITEM_WEIGHT),
+                );
+                return;
+            }
+            // The weight of settling is reserved before the extrinsics are applied
+            let weight = Self::settlement_weight();
+            SettlementWeight::put(weight);
+            <system::Module<T>>::register_extra_weight_unchecked(weight);
+        }
+
         /// Callback when a block is finalized
         ///
         /// @n  the block number
         fn on_finalize(_n: T::BlockNumber) {
-            let game_status = GameStatus::get();
+            // The game is suspended until the storage is migrated
+            if Self::is_migrating() {
+                return;
+            }
+            let mut weight = Self::settlement_weight_limit(SettlementWeight::take())
+                .saturating_sub(FINALIZE_WEIGHT);
+            // Draw the jackpot with the seed which was unknown when the round ended
+            if Self::pending_jackpot_round().is_some() {
+                Self::may_draw_jackpot();
+                weight = weight.saturating_sub(DRAW_JACKPOT_WEIGHT);
+            }
             // Check status
+            let game_status = GameStatus::get();
             if game_status != Status::Running && game_status != Status::Settling {
                 return;
             }
-
-            let mut ops:i32 = Self::max_ops() as i32;
             // Update game status
-            if GameStatus::get() == Status::Running {
-                let mut timeout = Self::timeout();
-                timeout = timeout.saturating_sub(10); // TODO: use config value
+            if game_status == Status::Running {
+                let timeout = Self::timeout().saturating_sub(T::BlockTime::get());
+                Timeout::put(timeout);
                 if timeout == 0 {
+                    // The round is closed at once, even if there is not enough weight left
                     let _ = Self::begin_settling();
-                }
-                Timeout::mutate(|n| *n = timeout);
-            }
-            // Loop to drain pending bonus
-            loop {
-                if ops <= 0 {
-                    break;
-                }
-
-                if !Self::drain_bonus(&mut ops) {
-                    break;
+                    weight = weight.saturating_sub(BEGIN_SETTLING_WEIGHT);
                 }
             }
-
-            // Loop to send money to latest boxes
-            if GameStatus::get() == Status::Settling {
-                loop {
-                    if ops <= 0 {
-                        break;
-                    }
-
-                    if !Self::release_prize(&mut ops) {
-                        let _ = Self::end_settling();
-                        break;
-                    }
-                }
+            // Ending of settling is deferred until there is enough weight left
+            if GameStatus::get() == Status::Settling && weight >= END_SETTLING_WEIGHT {
+                let _ = Self::end_settling();
             }
         }
     }
 }
 
+/// The types of storage, events and runtime APIs, with the aliases named by the storage
+impl<T: Trait> ModuleTypeMetadata for Module<T> {
+    fn types() -> Vec<TypeMetadata> {
+        vec![
+            Status::type_metadata(),
+            DboxStatus::type_metadata(),
+            DboxOf::<T>::type_metadata(),
+            RoundSummaryOf::<T>::type_metadata(),
+            JackpotOf::<T>::type_metadata(),
+            PrizeOf::<T>::type_metadata(),
+            PlayerStatus::type_metadata(),
+            BonusPolicy::type_metadata(),
+            SystemAccount::type_metadata(),
+            PlayerOf::<T>::type_metadata(),
+            ReferralOf::<T>::type_metadata(),
+            DboxInfoOf::<T>::type_metadata(),
+            RoundInfoOf::<T>::type_metadata(),
+            TypeMetadata::alias("BalanceOf", "Balance"),
+            TypeMetadata::alias("DboxOf", "Dbox<Hash, Balance, AccountId>"),
+            TypeMetadata::alias("PlayerOf", "Player<Balance>"),
+            TypeMetadata::alias("ReferralOf", "Referral<Balance>"),
+            TypeMetadata::alias("DboxInfoOf", "DboxInfo<Hash, Balance>"),
+            TypeMetadata::alias("RoundInfoOf", "RoundInfo<AccountId, Balance>"),
+            TypeMetadata::alias("PrizeOf", "Prize<Balance>"),
+            TypeMetadata::alias(
+                "RoundSummaryOf",
+                "RoundSummary<AccountId, Balance, BlockNumber>",
+            ),
+            TypeMetadata::alias("JackpotOf", "Jackpot<Balance, BlockNumber>"),
+        ]
+    }
+}
+
 impl<T: Trait> Module<T> {
     /// Check if inviting is ok or not
     ///
@@ -569,16 +1203,45 @@ impl<T: Trait> Module<T> {
                 !<AllPlayers<T>>::exists(invitee),
                 "Invitee should be a new player"
             );
+            ensure!(
+                !<Invitors<T>>::exists(invitee),
+                "Invitor of invitee is already recorded"
+            );
+            // Upper invitors are all players, so the new player can not make a referral cycle
+            ensure!(invitor != invitee, "Invitor should not be the invitee");
             // TODO: add blacklist
         }
 
         Ok(())
     }
 
+    /// Record the invitor of the new player and update referral stats of upper invitors
+    ///
+    /// @invitee    the new player
+    /// @invitor    the direct invitor
+    fn insert_referral(invitee: &T::AccountId, invitor: &T::AccountId) {
+        <Invitors<T>>::insert(invitee, invitor);
+        <Referrals<T>>::mutate(invitor, |referral| referral.invitees += 1);
+        <Referrals<T>>::mutate(invitee, |referral| {
+            referral.depth = Self::referral(invitor).depth.saturating_add(1)
+        });
+        // Only invitees in commission tiers are counted
+        let mut upper = Some(invitor.clone());
+        for _ in 0..T::InvitorRatios::get().len() {
+            let account = match upper {
+                Some(account) => account,
+                None => break,
+            };
+            <Referrals<T>>::mutate(&account, |referral| referral.descendants += 1);
+            upper = Self::invitor_of(&account);
+        }
+    }
+
     /// ensure status is ok or not
     ///
     /// @status_vec the vector of statuses expected
     fn ensure_status(status_vec: Vec<Status>) -> Result {
+        ensure!(!Self::is_migrating(), "Storage is migrating");
         let current_status = GameStatus::get();
         ensure!(
             status_vec.iter().any(|status| current_status == *status),
@@ -587,103 +1250,411 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    /// Check if the dbox is ok for inserting
+    /// Check if the player is forbidden
     ///
-    /// @from   the creator of the dbox
-    /// @dbox_id    the id of dbox
-    fn check_insert(from: &T::AccountId, dbox_id: &T::Hash) -> Result {
-        ensure!(!<DboxOwner<T>>::exists(dbox_id), "Dbox already exists");
-
-        let owned_dbox_count = Self::owned_dbox_count(from);
-        let _new_owned_dbox_count = owned_dbox_count
-            .checked_add(1)
-            .ok_or("Overflow adding a new dbox to account balance")?;
-
-        let all_dboxes_count = Self::all_dboxes_count();
-        let _new_all_dboxes_count = all_dboxes_count
-            .checked_add(1)
-            .ok_or("Overflow adding a new dbox to total supply")?;
+    /// @player the player account
+    fn is_forbidden(player: &T::AccountId) -> bool {
+        Self::player(player).status == PlayerStatus::Forbidden
+    }
 
-        // limit checking
-        let all_active_dboxes_count = Self::all_active_dboxes_count();
-        let new_all_active_dboxes_count = all_active_dboxes_count
-            .checked_add(1)
-            .ok_or("Overflow adding a new dbox to total active dboxes")?;
-        ensure!(
-            new_all_active_dboxes_count <= Self::max_active_dboxes_count(),
-            "Exceed max active dboxes limitation"
-        );
+    /// Get the system account by its kind
+    ///
+    /// @kind   the kind of system account
+    fn system_account(kind: SystemAccount) -> T::AccountId {
+        match kind {
+            SystemAccount::Cashier => Self::cashier_account(),
+            SystemAccount::Reserve => Self::reserve_account(),
+            SystemAccount::Pool => Self::pool_account(),
+            SystemAccount::LastPlayer => Self::last_player_account(),
+            SystemAccount::Team => Self::team_account(),
+            SystemAccount::Operator => Self::operator_account(),
+        }
+    }
 
+    /// Make sure the player is not forbidden
+    ///
+    /// @player the player account
+    fn ensure_not_forbidden(player: &T::AccountId) -> Result {
+        ensure!(!Self::is_forbidden(player), "Player is forbidden");
         Ok(())
     }
 
-    /// Get dbox by hash id
+    /// Check if the game move of the player can succeed and its fee can be paid by the game,
+    /// without changing any storage
     ///
-    /// @dbox_id    the id of dbox
-    fn get_dbox_by_id(dbox_id: T::Hash) -> Option<DboxOf<T>> {
-        // FIXME: what if the dbox does not exists?
-        let index = <AllDboxesIndex<T>>::get(dbox_id);
-        Some(<AllDboxesArray<T>>::get(index))
+    /// @player the signer of the game move
+    /// @call   the game move
+    /// @fee    the transaction fee
+    pub fn can_pay_fee(player: &T::AccountId, call: &Call<T>, fee: BalanceOf<T>) -> Result {
+        let _ = Self::fee_payer(player, call, fee)?;
+        Ok(())
     }
 
-    /// Insert the new player if does not exist
+    /// Pay the fee of a game move for the player, out of the value of one of its latest
+    /// active dboxes, or from the operator account within the subsidy limit of the player
     ///
-    /// @player_account the player account
-    fn may_insert_new_player(player_account: &T::AccountId) -> Result {
-        if !<AllPlayers<T>>::exists(player_account) {
-            let player = PlayerOf::<T> {
-                total_bonus: Zero::zero(),
-                total_prize: Zero::zero(),
-                total_commission: Zero::zero(),
-                status: PlayerStatus::Active,
-            };
+    /// @player the signer of the game move
+    /// @call   the game move
+    /// @fee    the transaction fee
+    pub fn pay_fee(player: &T::AccountId, call: &Call<T>, fee: BalanceOf<T>) -> Result {
+        let payer = Self::fee_payer(player, call, fee)?;
+        if fee.is_zero() {
+            return Ok(());
+        }
 
-            <AllPlayers<T>>::insert(player_account, player);
-            AllPlayersCount::mutate(|n| *n += 1);
+        match payer {
+            Some(mut dbox) => {
+                // The money of dbox is kept by the cashier, and the fee is burnt like other fees
+                let _ = T::Currency::withdraw(
+                    &Self::cashier_account(),
+                    fee,
+                    WithdrawReason::TransactionPayment.into(),
+                    ExistenceRequirement::KeepAlive,
+                )?;
+                dbox.value = dbox.value.saturating_sub(fee);
+                <AllDboxesArray<T>>::insert(dbox.create_position, &dbox);
+                Self::deposit_event(RawEvent::FeePaidByDbox(dbox.id, player.clone(), fee));
+            }
+            None => {
+                let (start, count) = Self::subsidy_window(player);
+                let _ = T::Currency::withdraw(
+                    &Self::operator_account(),
+                    fee,
+                    WithdrawReason::TransactionPayment.into(),
+                    ExistenceRequirement::KeepAlive,
+                )?;
+                <Subsidies<T>>::insert(player, (start, count + 1));
+                Self::deposit_event(RawEvent::FeeSubsidized(player.clone(), fee));
+            }
         }
 
         Ok(())
     }
 
-    /// Split fund of the dbox
+    /// Find the dbox paying the fee of a game move, with its bonus settled, or `None` if the fee
+    /// is subsidized by the operator, at most `MaxSubsidies` times in each `SubsidyPeriod` of the
+    /// player
     ///
-    /// @new_dbox   the newly dbox created
-    fn split_money(new_dbox: &mut DboxOf<T>) -> Result {
-        let money = Self::dbox_unit_price() / 100.into();
-        // Fill bonus info for all active dboxes if any
-        let all_active_dboxes_count = Self::all_active_dboxes_count();
-        if all_active_dboxes_count > 0 {
-            let bonus_amount = money.saturating_mul(T::DboxRatio::get().into());
-            // FIXME: TODO: support u64?
-            new_dbox.bonus_per_dbox = bonus_amount / (all_active_dboxes_count as u32).into();
+    /// @player the signer of the game move
+    /// @call   the game move
+    /// @fee    the transaction fee
+    fn fee_payer(
+        player: &T::AccountId,
+        call: &Call<T>,
+        fee: BalanceOf<T>,
+    ) -> rstd::result::Result<Option<DboxOf<T>>, &'static str> {
+        let _ = Self::ensure_status(vec![Status::Running, Status::Paused])?;
+        Self::ensure_not_forbidden(player)?;
+        let upgraded = Self::check_game_move(player, call)?;
+
+        if fee.is_zero() {
+            return Ok(None);
         }
-        // Give to other game acounts
-        let targets: Vec<(T::AccountId, u32)> = vec![
-            (Self::reserve_account(), T::ReserveRatio::get()),
-            (Self::pool_account(), T::PoolRatio::get()),
-            (Self::last_player_account(), T::LastPlayerRatio::get()),
-            (Self::team_account(), T::TeamRatio::get()),
-            (Self::operator_account(), T::OperatorRatio::get()),
-        ];
 
-        for t in targets.iter() {
-            let account = &t.0;
-            let balance = <Ledger<T>>::get(account);
-            let amount = money.saturating_mul(t.1.into());
-            let new_balance = balance.saturating_add(amount);
-            <Ledger<T>>::insert(account, new_balance);
+        let count = Self::owned_dbox_count(player);
+        for i in (count.saturating_sub(MAX_FEE_DBOXES)..count).rev() {
+            let dbox_id = Self::dbox_of_owner_by_index((player.clone(), i));
+            // The listed dboxes are kept for the buyers, and the upgraded one pays the new dbox
+            if Self::dbox_listing(dbox_id).is_some() || Some(dbox_id) == upgraded {
+                continue;
+            }
+            let mut dbox = match Self::get_dbox_by_id(dbox_id) {
+                Some(dbox) if dbox.status == DboxStatus::Active => dbox,
+                _ => continue,
+            };
+
+            Self::settle_bonus(&mut dbox);
+            if dbox.value >= fee {
+                Self::ensure_can_burn(&Self::cashier_account(), fee)?;
+                return Ok(Some(dbox));
+            }
         }
-        // Send commission to invitor directly
-        if let Some(invitor_account) = &new_dbox.invitor {
-            let commission_amount = money.saturating_mul(T::InvitorRatio::get().into());
-            T::Currency::transfer(
-                &Self::cashier_account(),
-                &invitor_account,
-                commission_amount,
-                ExistenceRequirement::AllowDeath,
-            )?;
+
+        // New players are subsidized too, so their first dbox is free of fee
+        let (_, subsidies) = Self::subsidy_window(player);
+        ensure!(subsidies < T::MaxSubsidies::get(), "Too many subsidized fees");
+        Self::ensure_can_burn(&Self::operator_account(), fee)?;
+
+        Ok(None)
+    }
+
+    /// Get the start and the count of subsidies in current `SubsidyPeriod` of the player
+    ///
+    /// @player the signer of the game move
+    fn subsidy_window(player: &T::AccountId) -> (T::BlockNumber, u32) {
+        let now = <system::Module<T>>::block_number();
+        let (start, count) = Self::subsidy_of(player);
+        if now >= start.saturating_add(T::SubsidyPeriod::get()) {
+            (now, 0)
+        } else {
+            (start, count)
+        }
+    }
+
+    /// Make sure the fee can be withdrawn from the account, keeping it alive
+    ///
+    /// @who    the account paying the fee
+    /// @fee    the transaction fee
+    fn ensure_can_burn(who: &T::AccountId, fee: BalanceOf<T>) -> Result {
+        let free_balance = T::Currency::free_balance(who);
+        ensure!(
+            free_balance >= fee.saturating_add(T::Currency::minimum_balance()),
+            "Not enough money for the fee"
+        );
+        T::Currency::ensure_can_withdraw(
+            who,
+            fee,
+            WithdrawReason::TransactionPayment.into(),
+            free_balance.saturating_sub(fee),
+        )
+    }
+
+    /// Check if the game move of the player can succeed in current state, returning the id of
+    /// the dbox to be upgraded
+    ///
+    /// @player the signer of the game move
+    /// @call   the game move
+    fn check_game_move(
+        player: &T::AccountId,
+        call: &Call<T>,
+    ) -> rstd::result::Result<Option<T::Hash>, &'static str> {
+        let by_index = |index: u64| -> rstd::result::Result<T::Hash, &'static str> {
+            ensure!(
+                <OwnedDboxesArray<T>>::exists((player.clone(), index)),
+                "Dbox does not exist"
+            );
+            Ok(Self::dbox_of_owner_by_index((player.clone(), index)))
+        };
+        let (dbox_id, upgrading) = match call {
+            Call::create_dbox() => return Self::check_creating(player, &None).map(|_| None),
+            Call::create_dbox_with_invitor(invitor) => {
+                return Self::check_creating(player, invitor).map(|_| None)
+            }
+            Call::upgrade_dbox(dbox_id) => (*dbox_id, true),
+            Call::upgrade_dbox_by_index(index) => (by_index(*index)?, true),
+            Call::open_dbox(dbox_id) => (*dbox_id, false),
+            Call::open_dbox_by_index(index) => (by_index(*index)?, false),
+            _ => return Err("Not a game move"),
+        };
+
+        Self::ensure_owner(player, dbox_id)?;
+        let mut dbox = Self::get_dbox_by_id(dbox_id).ok_or("Dbox does not exist")?;
+        ensure!(
+            dbox.status == DboxStatus::Active,
+            "The status of dbox should be active"
+        );
+        if !upgrading {
+            return Ok(None);
+        }
+
+        let _ = Self::ensure_status(vec![Status::Running])?;
+        Self::settle_bonus(&mut dbox);
+        ensure!(dbox.value >= Self::dbox_unit_price(), "Not enough money");
+        Ok(Some(dbox_id))
+    }
+
+    /// Check if the player can create a dbox in current state
+    ///
+    /// @player the creator
+    /// @invitor    the invitor of the new dbox
+    fn check_creating(player: &T::AccountId, invitor: &Option<T::AccountId>) -> Result {
+        let _ = Self::check_inviting(invitor, player)?;
+        let _ = Self::ensure_status(vec![Status::Running])?;
+        ensure!(!<Ledger<T>>::exists(player), "System account is not allowed");
+        ensure!(
+            T::Currency::free_balance(player) >= Self::dbox_unit_price(),
+            "Not enough money"
+        );
+        ensure!(
+            Self::all_active_dboxes_count() < Self::max_active_dboxes_count(),
+            "Exceed max active dboxes limitation"
+        );
+        Ok(())
+    }
+
+    /// Check if the dbox is ok for inserting
+    ///
+    /// @from   the creator of the dbox
+    /// @dbox_id    the id of dbox
+    fn check_insert(from: &T::AccountId, dbox_id: &T::Hash) -> Result {
+        ensure!(!<DboxOwner<T>>::exists(dbox_id), "Dbox already exists");
+
+        let owned_dbox_count = Self::owned_dbox_count(from);
+        let _new_owned_dbox_count = owned_dbox_count
+            .checked_add(1)
+            .ok_or("Overflow adding a new dbox to account balance")?;
+
+        let all_dboxes_count = Self::all_dboxes_count();
+        let _new_all_dboxes_count = all_dboxes_count
+            .checked_add(1)
+            .ok_or("Overflow adding a new dbox to total supply")?;
+
+        // limit checking
+        let all_active_dboxes_count = Self::all_active_dboxes_count();
+        let new_all_active_dboxes_count = all_active_dboxes_count
+            .checked_add(1)
+            .ok_or("Overflow adding a new dbox to total active dboxes")?;
+        ensure!(
+            new_all_active_dboxes_count <= Self::max_active_dboxes_count(),
+            "Exceed max active dboxes limitation"
+        );
+
+        Ok(())
+    }
+
+    /// Get dbox by hash id
+    ///
+    /// @dbox_id    the id of dbox
+    fn get_dbox_by_id(dbox_id: T::Hash) -> Option<DboxOf<T>> {
+        // FIXME: what if the dbox does not exists?
+        let index = <AllDboxesIndex<T>>::get(dbox_id);
+        Some(<AllDboxesArray<T>>::get(index))
+    }
+
+    /// Insert the new player if does not exist
+    ///
+    /// @player_account the player account
+    fn may_insert_new_player(player_account: &T::AccountId) -> Result {
+        if !<AllPlayers<T>>::exists(player_account) {
+            let player = PlayerOf::<T> {
+                total_bonus: Zero::zero(),
+                total_prize: Zero::zero(),
+                total_commission: Zero::zero(),
+                status: PlayerStatus::Active,
+            };
+
+            <AllPlayers<T>>::insert(player_account, player);
+            AllPlayersCount::mutate(|n| *n += 1);
+        }
+
+        Ok(())
+    }
+
+    /// Split the money of a new dbox with checked math, without changing any storage
+    ///
+    /// @invitor    the invitor of the new dbox
+    fn calc_split(
+        invitor: &Option<T::AccountId>,
+    ) -> rstd::result::Result<MoneySplitOf<T>, &'static str> {
+        let money = Self::dbox_unit_price() / 100.into();
+        let share = |ratio: u32| -> rstd::result::Result<BalanceOf<T>, &'static str> {
+            money
+                .checked_mul(&ratio.into())
+                .ok_or("Overflow splitting the money")
+        };
+        // Bonus is shared by all active dboxes if any
+        let all_active_dboxes_count = Self::all_active_dboxes_count();
+        let bonus_per_dbox = if all_active_dboxes_count > 0 {
+            share(T::DboxRatio::get())?
+                / <BalanceOf<T>>::unique_saturated_from(all_active_dboxes_count)
+        } else {
+            Zero::zero()
+        };
+        let targets: Vec<(T::AccountId, u32)> = vec![
+            (Self::reserve_account(), T::ReserveRatio::get()),
+            (Self::pool_account(), T::PoolRatio::get()),
+            (Self::last_player_account(), T::LastPlayerRatio::get()),
+            (Self::team_account(), T::TeamRatio::get()),
+            (Self::operator_account(), T::OperatorRatio::get()),
+        ];
+        let mut ledgers = Vec::with_capacity(targets.len());
+        for (account, ratio) in targets.into_iter() {
+            ledgers.push((account, share(ratio)?));
+        }
+        // Commission is sent to invitors tier by tier
+        let mut commissions = Vec::new();
+        let mut total_commission = <BalanceOf<T>>::zero();
+        let mut invitor = invitor.clone();
+        for ratio in T::InvitorRatios::get().into_iter() {
+            let invitor_account = match invitor {
+                Some(account) => account,
+                None => break,
+            };
+            let commission_amount = share(ratio)?;
+            total_commission = total_commission
+                .checked_add(&commission_amount)
+                .ok_or("Overflow splitting the money")?;
+            invitor = Self::invitor_of(&invitor_account);
+            commissions.push((invitor_account, commission_amount));
+        }
+
+        Ok(MoneySplit {
+            bonus_per_dbox: bonus_per_dbox,
+            ledgers: ledgers,
+            commissions: commissions,
+            total_commission: total_commission,
+        })
+    }
+
+    /// Make sure the cashier can pay the commissions of the split money
+    ///
+    /// @split  the split money
+    /// @income the money received by the cashier before paying
+    fn ensure_can_pay_commissions(split: &MoneySplitOf<T>, income: BalanceOf<T>) -> Result {
+        if split.total_commission.is_zero() {
+            return Ok(());
+        }
+        let cashier = Self::cashier_account();
+        let new_balance = T::Currency::free_balance(&cashier)
+            .saturating_add(income)
+            .checked_sub(&split.total_commission)
+            .ok_or("Cashier can not pay the commissions")?;
+        ensure!(
+            new_balance >= T::Currency::minimum_balance(),
+            "Cashier can not pay the commissions"
+        );
+        T::Currency::ensure_can_withdraw(
+            &cashier,
+            split.total_commission,
+            WithdrawReason::Transfer.into(),
+            new_balance,
+        )
+    }
+
+    /// Pay the split money of the new dbox, the commissions are withdrawn before the ledgers
+    /// are changed
+    ///
+    /// @new_dbox   the new dbox
+    /// @split  the split money, checked by `ensure_can_pay_commissions`
+    fn split_money(new_dbox: &mut DboxOf<T>, split: MoneySplitOf<T>) -> Result {
+        let mut commissions = if split.total_commission.is_zero() {
+            Imbalance::zero()
+        } else {
+            T::Currency::withdraw(
+                &Self::cashier_account(),
+                split.total_commission,
+                WithdrawReason::Transfer.into(),
+                ExistenceRequirement::KeepAlive,
+            )?
+        };
+        if !split.bonus_per_dbox.is_zero() {
+            <BonusPerDbox<T>>::mutate(new_dbox.round, |n| {
+                *n = n.saturating_add(split.bonus_per_dbox)
+            });
+        }
+        // The new dbox only shares bonus of the dboxes created after it
+        new_dbox.bonus_snapshot = Self::bonus_per_dbox(new_dbox.round);
+        // Give to other game acounts
+        for (account, amount) in split.ledgers.iter() {
+            let balance = <Ledger<T>>::get(account);
+            <Ledger<T>>::insert(account, balance.saturating_add(*amount));
+        }
+        // Send commission to invitors directly, tier by tier
+        for (tier, (invitor_account, commission_amount)) in
+            split.commissions.into_iter().enumerate()
+        {
+            let (commission, rest) = commissions.split(commission_amount);
+            T::Currency::resolve_creating(&invitor_account, commission);
+            commissions = rest;
             // Update invitor's commission balance
-            Self::add_commission(&invitor_account, commission_amount)?;
+            Self::add_commission(&invitor_account, new_dbox.round, commission_amount)?;
+            <Referrals<T>>::mutate(&invitor_account, |referral| {
+                if referral.commissions.len() <= tier {
+                    referral.commissions.resize(tier + 1, Zero::zero());
+                }
+                referral.commissions[tier] =
+                    referral.commissions[tier].saturating_add(commission_amount);
+            });
         }
 
         Ok(())
@@ -703,11 +1674,15 @@ impl<T: Trait> Module<T> {
     /// Add the bonus
     ///
     /// @account
+    /// @round  the round of the dbox
     /// @amount
-    fn add_bonus(account: &T::AccountId, amount: BalanceOf<T>) -> Result {
+    fn add_bonus(account: &T::AccountId, round: u64, amount: BalanceOf<T>) -> Result {
         let mut player = Self::player(account);
         player.total_bonus = player.total_bonus.saturating_add(amount);
         <AllPlayers<T>>::insert(account, player);
+        Self::update_round_summary(round, |summary| {
+            summary.total_bonus = summary.total_bonus.saturating_add(amount)
+        });
 
         Ok(())
     }
@@ -715,11 +1690,15 @@ impl<T: Trait> Module<T> {
     /// Add the prize
     ///
     /// @account
+    /// @round  the round of the dbox
     /// @amount
-    fn add_prize(account: &T::AccountId, amount: BalanceOf<T>) -> Result {
+    fn add_prize(account: &T::AccountId, round: u64, amount: BalanceOf<T>) -> Result {
         let mut player = Self::player(account);
         player.total_prize = player.total_prize.saturating_add(amount);
         <AllPlayers<T>>::insert(account, player);
+        Self::update_round_summary(round, |summary| {
+            summary.total_prize = summary.total_prize.saturating_add(amount)
+        });
 
         Ok(())
     }
@@ -727,11 +1706,15 @@ impl<T: Trait> Module<T> {
     /// Add the commission
     ///
     /// @account
+    /// @round  the round of the dbox
     /// @amount
-    fn add_commission(account: &T::AccountId, amount: BalanceOf<T>) -> Result {
+    fn add_commission(account: &T::AccountId, round: u64, amount: BalanceOf<T>) -> Result {
         let mut player = Self::player(account);
         player.total_commission = player.total_commission.saturating_add(amount);
         <AllPlayers<T>>::insert(account, player);
+        Self::update_round_summary(round, |summary| {
+            summary.total_commission = summary.total_commission.saturating_add(amount)
+        });
 
         Ok(())
     }
@@ -776,60 +1759,263 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    /// Send pending bonus
+    /// Check if the sender is the owner of the dbox
     ///
-    /// @dbox
-    fn send_pending_bonus(dbox: &DboxOf<T>) -> Result {
-        let mut prev_dbox = Self::dbox_by_index(dbox.bonus_position);
-        if prev_dbox.status == DboxStatus::Active || prev_dbox.status == DboxStatus::Opening {
-            prev_dbox.value += dbox.bonus_per_dbox;
-            // FIXME:
-            if prev_dbox.status == DboxStatus::Opening
-                && prev_dbox.open_position == dbox.create_position
-            {
-                Self::do_open_dbox(&mut prev_dbox, true, true)?;
-            }
-            // Update dbox
-            <AllDboxesArray<T>>::insert(prev_dbox.create_position, prev_dbox);
+    /// @sender the player
+    /// @dbox_id    id of the dbox
+    fn ensure_owner(sender: &T::AccountId, dbox_id: T::Hash) -> Result {
+        ensure!(<DboxOwner<T>>::exists(dbox_id), "Dbox does not exist");
+        ensure!(
+            Some(sender.clone()) == <DboxOwner<T>>::get(dbox_id),
+            "The owner of the dbox is not the sender"
+        );
+        Ok(())
+    }
+
+    /// Check if a dbox can be moved from one owner to another
+    ///
+    /// @from   the owner of the dbox
+    /// @to the receiver of the dbox
+    fn check_transfer(from: &T::AccountId, to: &T::AccountId) -> Result {
+        ensure!(
+            Self::owned_dbox_count(to).checked_add(1).is_some(),
+            "Overflow adding a new dbox to account balance"
+        );
+        ensure!(
+            Self::owned_dbox_count(from).checked_sub(1).is_some(),
+            "Underflow substracting a dbox from account balance"
+        );
+        Ok(())
+    }
+
+    /// Remove the listing of the dbox if any, a listed dbox is sold as it is when listed
+    ///
+    /// @owner  the owner of the dbox
+    /// @dbox_id    id of the dbox
+    fn remove_listing(owner: &T::AccountId, dbox_id: T::Hash) {
+        if <DboxListings<T>>::exists(dbox_id) {
+            <DboxListings<T>>::remove(dbox_id);
+            Self::deposit_event(RawEvent::DboxUnlisted(dbox_id, owner.clone()));
         }
+    }
+
+    /// Move the dbox from one owner to another, the listing of the dbox is removed
+    ///
+    /// @from   the owner of the dbox
+    /// @to the receiver of the dbox
+    /// @dbox_id    id of the dbox
+    fn do_transfer_dbox(from: &T::AccountId, to: &T::AccountId, dbox_id: T::Hash) -> Result {
+        ensure!(!Self::is_forbidden(to), "Receiver is forbidden");
+        let owned_dbox_count_to = Self::owned_dbox_count(to);
+        let new_owned_dbox_count_to = owned_dbox_count_to
+            .checked_add(1)
+            .ok_or("Overflow adding a new dbox to account balance")?;
+
+        let owned_dbox_count_from = Self::owned_dbox_count(from);
+        let new_owned_dbox_count_from = owned_dbox_count_from
+            .checked_sub(1)
+            .ok_or("Underflow substracting a dbox from account balance")?;
+        // Swap the dbox with the last one of the sender and remove it
+        let dbox_index = <OwnedDboxesIndex<T>>::get(dbox_id);
+        if dbox_index != new_owned_dbox_count_from {
+            let last_dbox_id =
+                <OwnedDboxesArray<T>>::get((from.clone(), new_owned_dbox_count_from));
+            <OwnedDboxesArray<T>>::insert((from.clone(), dbox_index), last_dbox_id);
+            <OwnedDboxesIndex<T>>::insert(last_dbox_id, dbox_index);
+        }
+        <OwnedDboxesArray<T>>::remove((from.clone(), new_owned_dbox_count_from));
+        <OwnedDboxesCount<T>>::insert(from, new_owned_dbox_count_from);
+        // Append the dbox to the receiver
+        <OwnedDboxesArray<T>>::insert((to.clone(), owned_dbox_count_to), dbox_id);
+        <OwnedDboxesCount<T>>::insert(to, new_owned_dbox_count_to);
+        <OwnedDboxesIndex<T>>::insert(dbox_id, owned_dbox_count_to);
+        <DboxOwner<T>>::insert(dbox_id, to);
+        Self::remove_listing(from, dbox_id);
+
+        Self::may_insert_new_player(to)?;
+        Self::deposit_event(RawEvent::DboxTransferred(dbox_id, from.clone(), to.clone()));
 
         Ok(())
     }
 
-    /// Check if the dbox has pending bonus or not
+    /// Get the bonus accumulated by the dbox since its last settlement
     ///
     /// @dbox
-    fn has_pending_bonus(dbox: &DboxOf<T>) -> bool {
+    fn accrued_bonus(dbox: &DboxOf<T>) -> BalanceOf<T> {
+        if dbox.status != DboxStatus::Active {
+            return Zero::zero();
+        }
+        Self::bonus_per_dbox(dbox.round).saturating_sub(dbox.bonus_snapshot)
+    }
+
+    /// Move the accrued bonus into the value of dbox
+    ///
+    /// @dbox
+    fn settle_bonus(dbox: &mut DboxOf<T>) {
+        dbox.value = dbox.value.saturating_add(Self::accrued_bonus(dbox));
+        dbox.bonus_snapshot = Self::bonus_per_dbox(dbox.round);
+    }
+
+    /// Get pending value and flag to indicate if player will get double prize
+    ///
+    /// @dbox
+    /// @return (pending_value, double)
+    pub fn get_pending_bonus(dbox: &DboxOf<T>) -> (BalanceOf<T>, bool) {
+        let pending = dbox.value.saturating_add(Self::accrued_bonus(dbox));
         if Self::is_staled_dbox(dbox) {
-            return false;
+            return (pending, false);
+        }
+
+        (pending, GameStatus::get() == Status::Running)
+    }
+
+    /// Get the dboxes owned by the player with their pending values
+    ///
+    /// @who    the player
+    pub fn get_player_dboxes(who: &T::AccountId) -> Vec<DboxInfoOf<T>> {
+        let count = Self::owned_dbox_count(who);
+        let mut dboxes = Vec::new();
+        for i in 0..count {
+            let dbox_id = Self::dbox_of_owner_by_index((who.clone(), i));
+            if let Some(dbox) = Self::get_dbox_by_id(dbox_id) {
+                let (pending_bonus, double) = Self::get_pending_bonus(&dbox);
+                let (pending_prize, _) = Self::get_pending_prize(&dbox);
+                let pending_prize = pending_prize.saturating_add(Self::get_pending_jackpot(&dbox));
+                dboxes.push(DboxInfo {
+                    id: dbox.id,
+                    create_position: dbox.create_position,
+                    status: dbox.status,
+                    round: dbox.round,
+                    version: dbox.version,
+                    pending_bonus: pending_bonus,
+                    double: double,
+                    pending_prize: pending_prize,
+                });
+            }
+        }
+
+        dboxes
+    }
+
+    /// Get the summary of current round
+    pub fn get_round_info() -> RoundInfoOf<T> {
+        let last_dbox_index = Self::last_dbox_index();
+        let first_index = last_dbox_index.saturating_sub(Self::latest_dboxes_count());
+        let latest_dboxes = (first_index..last_dbox_index)
+            .filter(|i| <LatestDboxes<T>>::exists(i))
+            .map(|i| Self::latest_dbox_by_index(i))
+            .collect();
+
+        RoundInfo {
+            round: Self::round_count(),
+            status: GameStatus::get(),
+            timeout: Self::timeout(),
+            pool: <Ledger<T>>::get(Self::pool_account()),
+            average_prize: Self::average_prize(),
+            latest_dboxes: latest_dboxes,
+        }
+    }
+
+    /// Get the money which would be paid if the dbox is opened right now
+    ///
+    /// @dbox_id    id of the dbox
+    /// @return None if the dbox can not be opened
+    pub fn get_open_payout(dbox_id: T::Hash) -> Option<BalanceOf<T>> {
+        if Self::ensure_status(vec![Status::Running, Status::Settling, Status::Paused]).is_err() {
+            return None;
+        }
+        if !<DboxOwner<T>>::exists(dbox_id) {
+            return None;
+        }
+        let dbox = Self::get_dbox_by_id(dbox_id)?;
+        if dbox.status != DboxStatus::Active {
+            return None;
         }
-        if Self::bonus_dbox() == Self::all_dboxes_count() {
-            return false;
+
+        let (pending, double) = Self::get_pending_bonus(&dbox);
+        if double {
+            Some(pending.saturating_add(pending))
+        } else {
+            Some(pending)
         }
-        true
     }
 
-    /// Get pending bonus and flag to indicate if player will get double prize
+    /// Get the unclaimed prize of dbox and the indexes of latest dboxes it comes from
     ///
     /// @dbox
-    /// @return (has_pending, double)
-    pub fn get_pending_bonus(dbox: &DboxOf<T>) -> (bool, bool) {
-        let status = GameStatus::get();
+    fn get_pending_prize(dbox: &DboxOf<T>) -> (BalanceOf<T>, Vec<u64>) {
+        let mut amount = <BalanceOf<T>>::zero();
+        let mut indexes = Vec::new();
+        // Prize is only available after the round of dbox is settled
+        if !<RoundPrizes<T>>::exists(dbox.round) {
+            return (amount, indexes);
+        }
 
-        if Self::is_staled_dbox(dbox) {
-            return (false, false);
+        let prize = Self::round_prize(dbox.round);
+        for i in prize.first_index..prize.last_index {
+            if !<LatestDboxes<T>>::exists(i) {
+                continue;
+            }
+            let (_player, create_position) = Self::latest_dbox_by_index(i);
+            if create_position != dbox.create_position {
+                continue;
+            }
+
+            amount = amount.saturating_add(prize.average_prize);
+            if i + 1 == prize.last_index {
+                amount = amount.saturating_add(prize.last_player_prize);
+            }
+            indexes.push(i);
         }
 
-        let mut double = false;
-        if status == Status::Running {
-            double = true;
+        (amount, indexes)
+    }
+
+    /// Get the unclaimed jackpot of dbox
+    ///
+    /// @dbox
+    fn get_pending_jackpot(dbox: &DboxOf<T>) -> BalanceOf<T> {
+        match Self::jackpot(dbox.round) {
+            Some(ref jackpot)
+                if !jackpot.claimed && jackpot.lucky_position == Some(dbox.create_position) =>
+            {
+                jackpot.amount
+            }
+            _ => Zero::zero(),
+        }
+    }
+
+    /// Draw the pending jackpot if the committed block is reached
+    ///
+    /// The seed is unknown to the players when the round ends, but it is not unbiasable: with
+    /// `RandomnessCollectiveFlip`, the author of the block before `draw_at` can grind the seed by
+    /// withholding or reordering the block, so the jackpot is only as fair as the block authors
+    fn may_draw_jackpot() {
+        let round = match Self::pending_jackpot_round() {
+            Some(round) => round,
+            None => return,
+        };
+        let mut jackpot = match Self::jackpot(round) {
+            Some(jackpot) => jackpot,
+            None => return PendingJackpotRound::kill(),
+        };
+        if Self::block_number() < jackpot.draw_at {
+            return;
         }
 
-        if Self::bonus_dbox() == Self::all_dboxes_count() {
-            return (false, double);
+        let seed = T::Randomness::random(&(JACKPOT_SUBJECT, round).encode());
+        let random = u64::decode(&mut seed.as_ref()).unwrap_or_default();
+        let count = jackpot.last_position.saturating_sub(jackpot.first_position);
+        if count == 0 {
+            return PendingJackpotRound::kill();
         }
+        let lucky_position = jackpot.first_position + random % count;
+        jackpot.lucky_position = Some(lucky_position);
 
-        (true, double)
+        let dbox = Self::dbox_by_index(lucky_position);
+        Self::deposit_event(RawEvent::JackpotDrawn(round, dbox.id, jackpot.amount));
+        <Jackpots<T>>::insert(round, jackpot);
+        PendingJackpotRound::kill();
     }
 
     /// Check if the dbox is staled or not
@@ -850,10 +2036,13 @@ impl<T: Trait> Module<T> {
         invitor: Option<T::AccountId>,
         transfer: bool,
     ) -> Result {
+        // The invitor is recorded once, and the later dboxes follow the recorded one
+        let new_invitor = if <Invitors<T>>::exists(sender) { None } else { invitor.clone() };
+        let invitor = Self::invitor_of(sender).or(invitor);
         // Generate hash to assign id of dbox
         let nonce = Nonce::get();
         let random_hash = (
-            <randomness_collective_flip::Module<T>>::random_seed(),
+            T::Randomness::random_seed(),
             &sender,
             nonce,
         )
@@ -866,12 +2055,19 @@ impl<T: Trait> Module<T> {
             value: Zero::zero(),
             version: 0,
             invitor: invitor,
-            open_position: 0, // FIXME:
-            bonus_per_dbox: Zero::zero(),
-            bonus_position: Self::round_start_dbox(),
+            round: Self::round_count(),
+            bonus_snapshot: Zero::zero(),
         };
         // Check if we can insert dbox without error
         let _ = Self::check_insert(&sender, &random_hash)?;
+        // Split the money first, so that paying it can not fail after the storage is changed
+        let split = Self::calc_split(&new_dbox.invitor)?;
+        let income = if transfer {
+            Self::dbox_unit_price()
+        } else {
+            Zero::zero()
+        };
+        Self::ensure_can_pay_commissions(&split, income)?;
         if transfer {
             // Transfer fund of buying dbox to our cashier account
             let _ = T::Currency::transfer(
@@ -882,7 +2078,10 @@ impl<T: Trait> Module<T> {
             )?;
         }
         // From now on, all state transition operations should be infailable
-        Self::split_money(&mut new_dbox)?;
+        if let Some(invitor_account) = &new_invitor {
+            Self::insert_referral(sender, invitor_account);
+        }
+        Self::split_money(&mut new_dbox, split)?;
         Self::insert_dbox(&sender, random_hash, &new_dbox)?;
         Self::on_dbox_operation(&sender, &new_dbox)?;
         Self::may_insert_new_player(&sender)?;
@@ -891,23 +2090,6 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    /// Add opening dbox to array
-    ///
-    /// @dbox the dbox to be opened later
-    fn add_opening_dbox(dbox: &DboxOf<T>) -> Result {
-        let all_opening_dboxes_count = Self::all_opening_doxes_count();
-        let new_all_opening_dboxes_count = all_opening_dboxes_count
-            .checked_add(1)
-            .ok_or("Overflow adding a new opening dbox")?;
-
-        AllOpeningDboxesMap::insert(dbox.create_position, all_opening_dboxes_count);
-        AllOpeningDboxesArray::insert(all_opening_dboxes_count, dbox.create_position);
-        // Update count
-        AllOpeningDboxesCount::put(new_all_opening_dboxes_count);
-
-        Ok(())
-    }
-
     /// Open dbox by id
     ///
     /// @sender the player
@@ -919,81 +2101,42 @@ impl<T: Trait> Module<T> {
             Some(sender.clone()) == <DboxOwner<T>>::get(dbox_id),
             "The owner of the dbox is not the sender"
         );
+        Self::ensure_not_forbidden(sender)?;
 
         let mut dbox = Self::get_dbox_by_id(dbox_id).unwrap();
         ensure!(
             dbox.status == DboxStatus::Active,
             "The status of dbox should be active"
         );
-        // Mark open position
-        dbox.open_position = Self::all_dboxes_count();
-        dbox.status = DboxStatus::Opening;
-        // FIXME: Double checking
-        let (has_pending, double) = Self::get_pending_bonus(&dbox);
-        if has_pending {
-            Self::add_opening_dbox(&dbox)?;
-        } else {
-            Self::do_open_dbox(&mut dbox, double, false)?;
-        }
+        // Bonus is accumulated lazily, so the dbox can be opened at once
+        let (_, double) = Self::get_pending_bonus(&dbox);
+        Self::settle_bonus(&mut dbox);
+        Self::do_open_dbox(&mut dbox, double)?;
         // Save status
         <AllDboxesArray<T>>::insert(dbox.create_position, &dbox);
-        // Update counter for running game
-        if double {
+        Self::remove_listing(sender, dbox_id);
+        // The dboxes of current round are counted whatever the game status is
+        if !Self::is_staled_dbox(&dbox) {
             let all_active_dboxes_count = Self::all_active_dboxes_count();
             let new_all_active_dboxes_count = all_active_dboxes_count
                 .checked_sub(1)
                 .ok_or("Underflow substracting a dbox from total active dboxes")?;
 
             AllActiveDboxesCount::put(new_all_active_dboxes_count);
-            Self::on_dbox_operation(&sender, &dbox)?;
         }
-        // Trigger events
-        if !has_pending {
-            Self::deposit_event(RawEvent::DboxOpening(dbox.id, sender.clone()));
+        // Update latest dboxes for running game
+        if double {
+            Self::on_dbox_operation(&sender, &dbox)?;
         }
 
         Ok(())
     }
 
-    /// Remove opening dbox from array
-    ///
-    /// @dbox_position the create position of dbox
-    fn remove_opening_dbox(create_position: u64) -> Result {
-        ensure!(
-            AllOpeningDboxesMap::exists(create_position),
-            "The opening dbox does not existed"
-        );
-        let index = Self::opening_dbox_by_position(create_position);
-
-        let all_opening_dboxes_count = Self::all_opening_doxes_count();
-        let largest_index = all_opening_dboxes_count
-            .checked_sub(1)
-            .ok_or("Underflow removing an opening dbox")?;
-        // Swap
-        let member_to_remove = Self::opening_dbox_by_index(index);
-        if index != largest_index {
-            let temp_position = Self::opening_dbox_by_index(largest_index);
-            AllOpeningDboxesArray::insert(index, temp_position);
-            AllOpeningDboxesArray::insert(largest_index, member_to_remove);
-
-            AllOpeningDboxesMap::insert(temp_position, index);
-            AllOpeningDboxesMap::insert(create_position, largest_index);
-        }
-        // Pop
-        AllOpeningDboxesMap::remove(create_position);
-        AllOpeningDboxesArray::remove(largest_index);
-        // Update count
-        AllOpeningDboxesCount::put(largest_index);
-
-        Ok(())
-    }
-
     /// Settle money for the opened dbox
     ///
     /// @dobx the dbox to be opened
     /// @double true if the value will be doubled, otherwise false
-    /// @remove true if the opening box will be removed from array, otherwise false
-    fn do_open_dbox(dbox: &mut DboxOf<T>, double: bool, remove: bool) -> Result {
+    fn do_open_dbox(dbox: &mut DboxOf<T>, double: bool) -> Result {
         // FIXME: we do not care if transfer is ok or not
         if let Some(player) = Self::owner_of(dbox.id) {
             if !dbox.value.is_zero() {
@@ -1009,7 +2152,7 @@ impl<T: Trait> Module<T> {
                     amount,
                     ExistenceRequirement::AllowDeath,
                 );
-                let _ = Self::add_bonus(&player, amount)?;
+                let _ = Self::add_bonus(&player, dbox.round, amount)?;
 
                 if double {
                     let _ = Self::substract_balance(&Self::reserve_account(), dbox.value)?;
@@ -1017,10 +2160,6 @@ impl<T: Trait> Module<T> {
             }
         }
 
-        if remove {
-            let _ = Self::remove_opening_dbox(dbox.create_position);
-        }
-
         dbox.value = Zero::zero();
         dbox.status = DboxStatus::Opened;
         Self::deposit_event(RawEvent::DboxOpened(dbox.id));
@@ -1040,6 +2179,7 @@ impl<T: Trait> Module<T> {
             Some(sender.clone()) == <DboxOwner<T>>::get(dbox_id),
             "The owner of the dbox is not the sender"
         );
+        Self::ensure_not_forbidden(sender)?;
 
         let mut dbox = Self::get_dbox_by_id(dbox_id).unwrap();
         ensure!(
@@ -1047,6 +2187,7 @@ impl<T: Trait> Module<T> {
             "The status of dbox should be active"
         );
 
+        Self::settle_bonus(&mut dbox);
         ensure!(dbox.value >= Self::dbox_unit_price(), "Not enough money");
 
         // Create another new dbox with money in the old dbox
@@ -1054,6 +2195,7 @@ impl<T: Trait> Module<T> {
         dbox.value = dbox.value.saturating_sub(Self::dbox_unit_price());
         // Save status
         <AllDboxesArray<T>>::insert(dbox.create_position, &dbox);
+        Self::remove_listing(sender, dbox_id);
         // Trigger event
         Self::deposit_event(RawEvent::DboxUpgraded(dbox.id, sender.clone()));
 
@@ -1088,48 +2230,148 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    /// Get last dbox
-    fn get_last_player() -> Option<T::AccountId> {
-        let latest_dboxes_count = Self::latest_dboxes_count();
-        let last_dbox_index = Self::last_dbox_index();
-        if latest_dboxes_count == 0 {
-            return None;
-        }
-        // Sanity checking
-        if !<LatestDboxes<T>>::exists(last_dbox_index - 1) {
-            return None;
-        }
+    /// Claim bonus and prize by id
+    ///
+    /// @sender the player
+    /// @dbox_id id of the dbox
+    fn claim_dbox_by_id(sender: &T::AccountId, dbox_id: T::Hash) -> Result {
+        let _ = Self::ensure_status(vec![Status::Running, Status::Settling, Status::Paused])?;
 
-        Some(<LatestDboxes<T>>::get(last_dbox_index - 1).0)
-    }
+        ensure!(<DboxOwner<T>>::exists(dbox_id), "Dbox does not exist");
+        ensure!(
+            Some(sender.clone()) == <DboxOwner<T>>::get(dbox_id),
+            "The owner of the dbox is not the sender"
+        );
+        Self::ensure_not_forbidden(sender)?;
 
-    /// Reset lastest boxex information
-    fn reset_latest_dboxes() -> Result {
-        let latest_dboxes_count = Self::latest_dboxes_count();
-        let last_dbox_index = Self::last_dbox_index();
+        Self::do_claim(sender, dbox_id, false, false)
+    }
 
-        for i in 1..=latest_dboxes_count {
-            <LatestDboxes<T>>::remove(last_dbox_index - i);
+    /// Pay the pending bonus and prize of the dbox
+    ///
+    /// The redirected money is kept by the cashier and credited to the ledger of the reserve, like
+    /// the other money of the reserve. The emergency withdrawal adds the emergency share to the
+    /// active dbox of current round, and closes the dbox so the share is paid only once.
+    ///
+    /// @owner  the owner of the dbox
+    /// @dbox_id id of the dbox
+    /// @redirected true if the money is paid to the reserve, otherwise to the owner
+    /// @emergency  true if the money is withdrawn after the game is shut down
+    fn do_claim(
+        owner: &T::AccountId,
+        dbox_id: T::Hash,
+        redirected: bool,
+        emergency: bool,
+    ) -> Result {
+        let mut dbox = Self::get_dbox_by_id(dbox_id).unwrap();
+        Self::settle_bonus(&mut dbox);
+        let mut bonus = dbox.value;
+        let shared = emergency && dbox.status == DboxStatus::Active && !Self::is_staled_dbox(&dbox);
+        if shared {
+            bonus = bonus.saturating_add(Self::emergency_share());
+        }
+        let (prize, indexes) = Self::get_pending_prize(&dbox);
+        let jackpot = Self::get_pending_jackpot(&dbox);
+        let prize = prize.saturating_add(jackpot);
+        ensure!(!bonus.is_zero() || !prize.is_zero(), "Nothing to claim");
+
+        let amount = bonus.saturating_add(prize);
+        if redirected {
+            let reserve = Self::reserve_account();
+            <Ledger<T>>::insert(&reserve, <Ledger<T>>::get(&reserve).saturating_add(amount));
+        } else {
+            T::Currency::transfer(
+                &Self::cashier_account(),
+                owner,
+                amount,
+                ExistenceRequirement::AllowDeath,
+            )?;
+        }
+        // From now on, all state transition operations should be infailable
+        if !redirected && !bonus.is_zero() {
+            Self::add_bonus(owner, dbox.round, bonus)?;
+        }
+        if !redirected && !prize.is_zero() {
+            Self::add_prize(owner, dbox.round, prize)?;
+        }
+        for i in indexes.iter() {
+            <LatestDboxes<T>>::remove(i);
+        }
+        if !jackpot.is_zero() {
+            <Jackpots<T>>::mutate(dbox.round, |jackpot| {
+                if let Some(jackpot) = jackpot {
+                    jackpot.claimed = true;
+                }
+            });
+        }
+        // Save status
+        dbox.value = Zero::zero();
+        if emergency && dbox.status == DboxStatus::Active {
+            dbox.status = DboxStatus::Opened;
+        }
+        if shared {
+            AllActiveDboxesCount::mutate(|n| *n = n.saturating_sub(1));
+        }
+        <AllDboxesArray<T>>::insert(dbox.create_position, &dbox);
+        Self::remove_listing(owner, dbox_id);
+        // Trigger event
+        if redirected {
+            Self::deposit_event(RawEvent::BonusRedirected(dbox.id, owner.clone(), amount));
+        } else if emergency {
+            Self::deposit_event(RawEvent::EmergencyWithdrawn(dbox.id, owner.clone(), amount));
+        } else {
+            Self::deposit_event(RawEvent::DboxClaimed(dbox.id, owner.clone(), bonus, prize));
         }
-
-        LastDboxIndex::mutate(|n| *n = 0);
-        LatestDboxesCount::mutate(|n| *n = 0);
-        ReleasedDboxesCount::mutate(|n| *n = 0);
-
-        <AveragePrize<T>>::put(<BalanceOf<T>>::zero());
 
         Ok(())
     }
 
-    /// Begin settling
+    /// Begin settling, the prize is kept for the latest dboxes to claim
     fn begin_settling() -> Result {
+        let round = Self::round_count();
+        // Record the result of the round
+        let last_player = if Self::latest_dboxes_count() > 0 {
+            Some(Self::latest_dbox_by_index(Self::last_dbox_index() - 1).0)
+        } else {
+            None
+        };
+        Self::update_round_summary(round, |summary| {
+            summary.end_block = Some(Self::block_number());
+            summary.dbox_count = Self::all_dboxes_count() - Self::round_start_dbox();
+            summary.pool = <Ledger<T>>::get(Self::pool_account());
+            summary.last_player = last_player;
+        });
         // Calculate average prize
         let latest_dboxes_count = Self::latest_dboxes_count();
         if latest_dboxes_count > 0 {
-            let money = <Ledger<T>>::get(Self::pool_account());
+            let mut money = <Ledger<T>>::get(Self::pool_account());
+            // Part of the pool is kept for the jackpot
+            let jackpot_amount = money.saturating_mul(Self::jackpot_ratio().into()) / 100.into();
+            if !jackpot_amount.is_zero() {
+                money = money.saturating_sub(jackpot_amount);
+                let jackpot = JackpotOf::<T> {
+                    amount: jackpot_amount,
+                    first_position: Self::round_start_dbox(),
+                    last_position: Self::all_dboxes_count(),
+                    draw_at: Self::block_number() + One::one(),
+                    lucky_position: None,
+                    claimed: false,
+                };
+                <Jackpots<T>>::insert(round, jackpot);
+                PendingJackpotRound::put(round);
+            }
             // FIXME: less then 100 dboxes
             let prize_amount = money / (latest_dboxes_count as u32).into();
             <AveragePrize<T>>::put(prize_amount);
+
+            let last_dbox_index = Self::last_dbox_index();
+            let prize = PrizeOf::<T> {
+                average_prize: prize_amount,
+                last_player_prize: <Ledger<T>>::get(Self::last_player_account()),
+                first_index: last_dbox_index - latest_dboxes_count,
+                last_index: last_dbox_index,
+            };
+            <RoundPrizes<T>>::insert(round, prize);
         }
         // TODO: check if average prize is zero?
         GameStatus::put(Status::Settling);
@@ -1138,152 +2380,234 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    /// Drain bonus
-    ///
-    /// @ops    operations occured
-    fn drain_bonus(ops: &mut i32) -> bool {
-        let bonus_dbox = Self::bonus_dbox();
-        if bonus_dbox >= Self::all_dboxes_count() {
-            return Self::process_opening_dboxes(ops);
-        }
-
-        let mut dbox = Self::dbox_by_index(bonus_dbox);
-        loop {
-            if dbox.bonus_position >= dbox.create_position {
-                // Update bonus position
-                BonusDbox::put(bonus_dbox + 1);
-                *ops -= 1;
-                break;
-            }
-            // Send bonus
-            let _ = Self::send_pending_bonus(&dbox);
-            dbox.bonus_position += 1; // Move forward
+    /// End of settling, reset the game and start again
+    fn end_settling() -> Result {
+        // The new round can not start with stale price
+        Self::update_unit_price()?;
+        // reset round positions
+        let all_dboxes_count = Self::all_dboxes_count();
 
-            *ops -= 2;
-            if *ops <= 0 {
-                break;
-            }
+        RoundCount::mutate(|n| *n += 1);
+        RoundStartDbox::put(all_dboxes_count);
+        AllActiveDboxesCount::put(0);
+
+        // FIXME: Reset balances of system accounts
+        let accounts: Vec<T::AccountId> = vec![
+            Self::cashier_account(),
+            Self::reserve_account(),
+            Self::pool_account(),
+            Self::last_player_account(),
+        ];
+
+        for account in accounts.iter() {
+            let balance = <BalanceOf<T>>::zero();
+            <Ledger<T>>::insert(account, balance);
         }
-        // Update dbox
-        <AllDboxesArray<T>>::insert(dbox.create_position, dbox);
 
-        true
+        // Reset lastest dboxes, the entries are kept until the prize is claimed
+        LatestDboxesCount::put(0);
+        <AveragePrize<T>>::put(<BalanceOf<T>>::zero());
+        MaxActiveDboxesCount::put(Self::max_preset_active_dboxes_count());
+        Self::start_round_summary();
+        // Reset status and timeout value
+        Timeout::put(T::Expiration::get());
+        GameStatus::put(Status::Running);
+        // Trigger event
+        Self::deposit_event(RawEvent::GameRunning(Self::block_number()));
+
+        Ok(())
     }
 
-    /// Process opening boxes whose open_position is unreachable
+    /// Shut down the game, the reserve, pool and prize of last player are shared by the active
+    /// dboxes of current round
     ///
-    /// @ops    operations occured
-    fn process_opening_dboxes(ops: &mut i32) -> bool {
-        loop {
-            let all_opening_dboxes_count = Self::all_opening_doxes_count();
-            if all_opening_dboxes_count == 0 {
-                // We have opened all the pending dboxes
-                return false;
+    /// Once the round is settling, the pool and prize of last player are already turned into the
+    /// prizes and jackpot of the round, which are withdrawn like claims, so only the reserve is
+    /// shared. The money left by the division, or all of it if there is no active dbox, is kept in
+    /// the ledger of reserve.
+    fn shutdown() {
+        let settling = GameStatus::get() == Status::Settling;
+        let reserve = Self::reserve_account();
+        let mut money = <Ledger<T>>::get(&reserve);
+        for account in [Self::pool_account(), Self::last_player_account()].iter() {
+            if !settling {
+                money = money.saturating_add(<Ledger<T>>::get(account));
             }
-            // Get the last opening dbox for performance
-            let create_position = AllOpeningDboxesArray::get(all_opening_dboxes_count - 1);
-            // Sanity checking?
-            let mut dbox = Self::dbox_by_index(create_position);
-            assert!(
-                dbox.status == DboxStatus::Opening
-                    && dbox.open_position == Self::all_dboxes_count(),
-                "Status should be opening"
-            );
-            let _ = Self::do_open_dbox(&mut dbox, true, true);
-            // Update dbox
-            <AllDboxesArray<T>>::insert(dbox.create_position, dbox);
+            <Ledger<T>>::insert(account, <BalanceOf<T>>::zero());
+        }
+        let count = <BalanceOf<T>>::unique_saturated_from(Self::all_active_dboxes_count());
+        let share = if count.is_zero() {
+            Zero::zero()
+        } else {
+            money / count
+        };
+        <Ledger<T>>::insert(&reserve, money.saturating_sub(share.saturating_mul(count)));
+        <EmergencyShare<T>>::put(share);
+        GameStatus::put(Status::EmergencyShutdown);
+        Self::deposit_event(RawEvent::GameShutdown(Self::block_number(), share));
+    }
 
-            *ops -= 2;
-            if *ops <= 0 {
-                return true;
-            }
+    /// Start the summary of current round and prune the oldest one
+    fn start_round_summary() {
+        let round = Self::round_count();
+        let summary = RoundSummaryOf::<T> {
+            start_block: Self::block_number(),
+            ..Default::default()
+        };
+        <Rounds<T>>::insert(round, summary);
+        // Only one round is pruned each time, so the storage is bounded
+        if round > T::MaxRounds::get() {
+            Self::prune_round(round - T::MaxRounds::get());
         }
     }
 
-    /// Release prize for latest boxes
-    fn release_prize(ops: &mut i32) -> bool {
-        let released_dboxes_count = Self::released_dboxes_count();
-        let latest_dboxes_count = Self::latest_dboxes_count();
-        let last_dbox_index = Self::last_dbox_index();
+    /// Remove the summary of the round
+    ///
+    /// The accumulators and prizes of the round are kept, so the dboxes of the round can still
+    /// claim their bonus and prize
+    ///
+    /// @round  the round to be pruned
+    fn prune_round(round: u64) {
+        <Rounds<T>>::remove(round);
+    }
 
-        if released_dboxes_count >= latest_dboxes_count {
-            if latest_dboxes_count > 0 {
-                // Send the last big prize
-                if let Some(player) = Self::get_last_player() {
-                    // FIXME: We do not care if transfer is ok or not
-                    let last_player_account = Self::last_player_account();
-                    let last_player_prize = <Ledger<T>>::get(&last_player_account);
-                    let _ = T::Currency::transfer(
-                        &Self::cashier_account(),
-                        &player,
-                        last_player_prize,
-                        ExistenceRequirement::AllowDeath,
-                    );
-                    let _ = Self::add_prize(&player, last_player_prize);
-                    // Reset last player account
-                }
+    /// Update the summary of the round if it is not pruned
+    ///
+    /// @round  the round
+    /// @f  the function to update summary
+    fn update_round_summary<F: FnOnce(&mut RoundSummaryOf<T>)>(round: u64, f: F) {
+        if let Some(mut summary) = Self::round_summary(round) {
+            f(&mut summary);
+            <Rounds<T>>::insert(round, summary);
+        }
+    }
+
+    /// Get the summaries of rounds, from the latest one to the older ones
+    ///
+    /// @from   the latest round to be queried
+    /// @count  the maximum count of rounds
+    pub fn get_round_summaries(from: u64, count: u64) -> Vec<(u64, RoundSummaryOf<T>)> {
+        let count = count.min(T::MaxRounds::get());
+        let mut summaries = Vec::new();
+        let mut round = from.min(Self::round_count());
+        while round > 0 && (summaries.len() as u64) < count {
+            match Self::round_summary(round) {
+                Some(summary) => summaries.push((round, summary)),
+                None => break,
             }
-            *ops -= 5;
-            return false;
+            round -= 1;
         }
 
-        let i = last_dbox_index - (latest_dboxes_count - released_dboxes_count);
-        let (player, _dbox_pos) = <LatestDboxes<T>>::get(i);
+        summaries
+    }
 
-        // Share the last prize
-        let average_prize = Self::average_prize();
-        if !average_prize.is_zero() {
-            // FIXME: we don't care if the transfer is ok or not
-            let _ = T::Currency::transfer(
-                &Self::cashier_account(),
-                &player,
-                average_prize,
-                ExistenceRequirement::AllowDeath,
-            );
-            let _ = Self::add_prize(&player, average_prize);
-        }
+    /// Recompute the unit price from oracle price if the fiat pricing is enabled
+    fn update_unit_price() -> Result {
+        let fiat_unit_price = match Self::fiat_unit_price() {
+            Some(fiat_unit_price) => fiat_unit_price,
+            None => return Ok(()),
+        };
+        let (price, updated_at) = T::PriceProvider::price().ok_or("Price is not available")?;
+        ensure!(!price.is_zero(), "Price is not available");
+        ensure!(
+            Self::block_number() <= updated_at.saturating_add(T::MaxPriceAge::get()),
+            "Price is stale"
+        );
 
-        ReleasedDboxesCount::mutate(|n| *n += 1);
-        *ops -= 3;
+        let unit_price = T::TokenUnit::get()
+            .saturating_mul(<BalanceOf<T>>::unique_saturated_from(fiat_unit_price))
+            / <BalanceOf<T>>::unique_saturated_from(price);
+        let unit_price = unit_price
+            .max(T::MinUnitPrice::get())
+            .min(T::MaxUnitPrice::get());
 
-        true
+        if unit_price != Self::dbox_unit_price() {
+            <DboxUnitPrice<T>>::put(unit_price);
+            Self::deposit_event(RawEvent::UnitPriceChanged(unit_price));
+        }
+        Ok(())
     }
 
-    /// End of settling, reset the game and start again, note that all bonus transfer should be finished before reset
-    fn end_settling() -> Result {
-        // reset round positions
-        let all_dboxes_count = Self::all_dboxes_count();
-
-        RoundCount::mutate(|n| *n += 1);
-        RoundStartDbox::put(all_dboxes_count);
-        BonusDbox::put(all_dboxes_count);
-        AllActiveDboxesCount::put(0);
+    /// Check if the unit price of dbox is in the range
+    ///
+    /// @dbox_unit_price    the price of dbox
+    fn check_unit_price(dbox_unit_price: BalanceOf<T>) -> Result {
+        ensure!(dbox_unit_price > T::MinUnitPrice::get(), "Unit price is too low");
+        ensure!(dbox_unit_price <= T::MaxUnitPrice::get(), "Unit price is too high");
+        Ok(())
+    }
 
-        // FIXME: Reset balances of system accounts
-        let accounts: Vec<T::AccountId> = vec![
-            Self::cashier_account(),
-            Self::reserve_account(),
-            Self::pool_account(),
-            Self::last_player_account(),
-        ];
+    /// Init the ledger and the first round of the game
+    ///
+    /// @dbox_unit_price            the price of dbox
+    /// @max_active_dboxes_count    maximum active dboxes permitted
+    fn do_init(dbox_unit_price: BalanceOf<T>, max_active_dboxes_count: u64) {
+        // Init each account of ledger
+        let accounts: Vec<T::AccountId> = vec![Self::cashier_account(), Self::reserve_account(),
+            Self::pool_account(), Self::last_player_account(), Self::team_account(), Self::operator_account()];
 
         for account in accounts.iter() {
             let balance = <BalanceOf<T>>::zero();
             <Ledger<T>>::insert(account, balance);
         }
+        MaxActiveDboxesCount::put(max_active_dboxes_count);
+        MaxPresetActiveDboxesCount::put(max_active_dboxes_count);
 
-        // Reset lastest dboxes
-        Self::reset_latest_dboxes()?;
-        MaxActiveDboxesCount::put(Self::max_preset_active_dboxes_count());
-        // Reset status and timeout value
+        GameStatus::put(Status::Inited);
         Timeout::put(T::Expiration::get());
-        GameStatus::put(Status::Running);
-        // Trigger event
-        Self::deposit_event(RawEvent::GameRunning(Self::block_number(), None));
+        <DboxUnitPrice<T>>::put(dbox_unit_price);
+        RoundCount::put(1);
+        RoundStartDbox::put(0);
+        <AveragePrize<T>>::put(<BalanceOf<T>>::zero());
+    }
 
+    /// Start the first round of the inited game
+    fn start_game() -> Result {
+        Self::update_unit_price()?;
+        Self::start_round_summary();
+        GameStatus::put(Status::Running);
         Ok(())
     }
 
+    /// Check if there are active dboxes or latest dboxes in current round
+    fn has_pending_dboxes() -> bool {
+        Self::all_active_dboxes_count() > 0 || Self::latest_dboxes_count() > 0
+    }
+
+    /// Get the weight which can be used for settling in current block
+    ///
+    /// @reserved   the weight of settling reserved in `on_initialize`
+    fn settlement_weight_limit(reserved: Weight) -> Weight {
+        let max_weight = T::MaximumBlockWeight::get();
+        let used = <system::Module<T>>::all_extrinsics_weight().saturating_sub(reserved);
+        let remaining = max_weight.saturating_sub(used);
+
+        remaining.min(Self::settlement_weight_ratio() * max_weight)
+    }
+
+    /// Get the weight of settling in `on_finalize` of current block, with the ending of settling
+    /// only if it fits in the fraction of maximum block weight
+    fn settlement_weight() -> Weight {
+        let mut weight = FINALIZE_WEIGHT;
+        if Self::pending_jackpot_round().is_some() {
+            weight = weight.saturating_add(DRAW_JACKPOT_WEIGHT);
+        }
+        let settling = match GameStatus::get() {
+            Status::Running if Self::timeout() <= T::BlockTime::get() => {
+                weight = weight.saturating_add(BEGIN_SETTLING_WEIGHT);
+                true
+            }
+            Status::Settling => true,
+            _ => false,
+        };
+        let limit = Self::settlement_weight_ratio() * T::MaximumBlockWeight::get();
+        if settling && weight.saturating_add(END_SETTLING_WEIGHT) <= limit {
+            weight = weight.saturating_add(END_SETTLING_WEIGHT);
+        }
+
+        weight
+    }
+
     /// Get current block number
     fn block_number() -> T::BlockNumber {
         <system::Module<T>>::block_number()
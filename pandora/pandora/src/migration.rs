@@ -0,0 +1,305 @@
+//! # Migration
+//!
+//! The storage layout of pandora is versioned by `StorageVersion`, the old layouts are kept here
+//! to decode the stored values, and they are migrated in bounded batches in `on_initialize`
+//!
+//! Version 0 pushed the bonus to the dboxes and the prize to the latest dboxes while settling, so
+//! the migration to version 1 runs in three phases, the cursor is kept until all of them are done:
+//! 1. convert the dboxes, the bonus not pushed yet is credited by the snapshots of the round
+//! 2. open the dboxes in the opening queue, doubled as version 0 would do
+//! 3. record the prize of a round caught in settling and remove the storage of version 0
+//!
+//! The layouts of players and ledger are not changed, the entry of the removed admin account is
+//! dropped from the ledger only.
+
+use super::*;
+use runtime_io::hashing::{blake2_256, twox_128};
+use support::storage::unhashed;
+
+/// The status of dbox in storage version 0
+#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq)]
+pub enum DboxStatusV0 {
+    None,
+    /// The dbox is locked
+    Locked(u64),
+    /// The dbox is created
+    Active,
+    /// The dbox is opening
+    Opening,
+    /// The dbox is opened
+    Opened,
+}
+
+/// The dbox struct in storage version 0, the bonus was pushed to the dbox by settling
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct DboxV0<Hash, Balance, AccountId> {
+    /// The hash of the dbox
+    pub id: Hash,
+    /// The position at which the dbox is created
+    pub create_position: u64,
+    /// The status of dbox
+    pub status: DboxStatusV0,
+    /// The accumulated bonus
+    pub value: Balance,
+    /// The version of dbox
+    pub version: u64,
+    /// The invitor of the dbox
+    pub invitor: Option<AccountId>,
+    /// The position when dbox is requested to open
+    pub open_position: u64,
+    /// The per-dbox bonus
+    pub bonus_per_dbox: Balance,
+    /// The bonus position of this dbox, we use it to keep the pending bonus dbox position
+    pub bonus_position: u64,
+}
+
+type DboxV0Of<T> =
+    DboxV0<<T as system::Trait>::Hash, BalanceOf<T>, <T as system::Trait>::AccountId>;
+
+impl<T: Trait> Module<T> {
+    /// Check if the storage is being migrated
+    pub fn is_migrating() -> bool {
+        Self::storage_version() < STORAGE_VERSION
+    }
+
+    /// Migrate the storage by at most `MigrationBatchSize` items
+    pub(crate) fn migrate_storage() {
+        if Self::storage_version() == 0 {
+            Self::migrate_v0_to_v1(T::MigrationBatchSize::get());
+        }
+    }
+
+    /// Migrate the storage from version 0 to version 1
+    ///
+    /// @limit  the maximum count of dboxes migrated
+    fn migrate_v0_to_v1(limit: u64) {
+        let mut limit = limit;
+        if !Self::migrate_dboxes_v0(&mut limit) {
+            return;
+        }
+        if !Self::open_dboxes_v0(&mut limit) {
+            return;
+        }
+
+        Self::finish_v0_to_v1();
+        MigrationCursor::kill();
+        StorageVersion::put(1);
+        Self::deposit_event(RawEvent::StorageMigrated(1));
+    }
+
+    /// Convert the dboxes of version 0, and count the active dboxes of current round again
+    ///
+    /// The dboxes from `BonusDbox` on still have bonus to be pushed to the previous dboxes, so
+    /// the bonus of them is added to the accumulator of current round, and the snapshot of each
+    /// dbox is the bonus of the dboxes up to itself, which is not pushed to it. The bonus of the
+    /// dbox at `BonusDbox` has been pushed to the dboxes before its bonus position already.
+    ///
+    /// @limit  the count of dboxes which can be migrated, reduced by the migrated ones
+    /// @return true if all dboxes are migrated
+    fn migrate_dboxes_v0(limit: &mut u64) -> bool {
+        let cursor = Self::migration_cursor();
+        let all_dboxes_count = Self::all_dboxes_count();
+        if cursor >= all_dboxes_count {
+            return true;
+        }
+        if cursor == 0 {
+            AllActiveDboxesCount::put(0);
+        }
+
+        let round = Self::round_count();
+        let bonus_dbox = unhashed::get_or_default::<u64>(&value_key_v0(b"BonusDbox"));
+        // The bonus of the draining dbox and its bonus position
+        let (draining_bonus, draining_position) = if cursor < bonus_dbox {
+            match unhashed::get::<DboxV0Of<T>>(&<AllDboxesArray<T>>::hashed_key_for(bonus_dbox)) {
+                Some(old) => (old.bonus_per_dbox, old.bonus_position),
+                None => (Zero::zero(), 0),
+            }
+        } else {
+            (Zero::zero(), 0)
+        };
+
+        let end = cursor.saturating_add(*limit).min(all_dboxes_count);
+        for position in cursor..end {
+            let key = <AllDboxesArray<T>>::hashed_key_for(position);
+            let old = match unhashed::get::<DboxV0Of<T>>(&key) {
+                Some(old) => old,
+                None => continue,
+            };
+            let mut dbox = Self::dbox_from_v0(&old);
+            if dbox.round == round {
+                if position >= bonus_dbox {
+                    <BonusPerDbox<T>>::mutate(round, |n| *n = n.saturating_add(old.bonus_per_dbox));
+                    dbox.bonus_snapshot = Self::bonus_per_dbox(round);
+                } else if position < draining_position {
+                    dbox.bonus_snapshot = draining_bonus;
+                }
+                if dbox.status == DboxStatus::Active {
+                    AllActiveDboxesCount::mutate(|n| *n += 1);
+                }
+            }
+            // The opening dbox is opened with the open position in the next phase
+            if old.status == DboxStatusV0::Opening {
+                unhashed::put(&open_position_key(position), &old.open_position);
+            }
+            <AllDboxesArray<T>>::insert(position, dbox);
+        }
+
+        *limit -= end - cursor;
+        MigrationCursor::put(end);
+        end == all_dboxes_count
+    }
+
+    /// Open the dboxes in the opening queue of version 0
+    ///
+    /// The dbox is paid with the bonus pushed to it until the dbox at its open position, or all
+    /// the bonus if no dbox is created after the open request, and the value is doubled
+    ///
+    /// @limit  the count of dboxes which can be opened, reduced by the opened ones
+    /// @return true if the opening queue is empty
+    fn open_dboxes_v0(limit: &mut u64) -> bool {
+        let count_key = value_key_v0(b"AllOpeningDboxesCount");
+        let mut count = unhashed::get_or_default::<u64>(&count_key);
+        while count > 0 && *limit > 0 {
+            count -= 1;
+            let index_key = map_key_v0(b"AllOpeningDboxesArray", count);
+            let position = unhashed::get_or_default::<u64>(&index_key);
+            let open_position = unhashed::take_or_default::<u64>(&open_position_key(position));
+
+            let mut dbox = Self::dbox_by_index(position);
+            if dbox.status == DboxStatus::Active {
+                let bonus = if open_position < Self::all_dboxes_count() {
+                    Self::dbox_by_index(open_position).bonus_snapshot
+                } else {
+                    Self::bonus_per_dbox(dbox.round)
+                };
+                dbox.value = dbox
+                    .value
+                    .saturating_add(bonus.saturating_sub(dbox.bonus_snapshot));
+                let _ = Self::do_open_dbox(&mut dbox, true);
+                <AllDboxesArray<T>>::insert(position, &dbox);
+                if !Self::is_staled_dbox(&dbox) {
+                    AllActiveDboxesCount::mutate(|n| *n = n.saturating_sub(1));
+                }
+            }
+
+            unhashed::kill(&map_key_v0(b"AllOpeningDboxesMap", position));
+            unhashed::kill(&index_key);
+            unhashed::put(&count_key, &count);
+            *limit -= 1;
+        }
+
+        count == 0
+    }
+
+    /// Record the prize of the round caught in settling, and remove the storage of version 0
+    ///
+    /// The prize is kept for the latest dboxes which are not released yet, and the prize of the
+    /// last player is kept for the last one of them, it was paid after all the others
+    fn finish_v0_to_v1() {
+        let round = Self::round_count();
+        let latest_dboxes_count = Self::latest_dboxes_count();
+        let released_key = value_key_v0(b"ReleasedDboxesCount");
+        if GameStatus::get() == Status::Settling
+            && latest_dboxes_count > 0
+            && !<RoundPrizes<T>>::exists(round)
+        {
+            let released_dboxes_count = unhashed::get_or_default::<u64>(&released_key);
+            let last_dbox_index = Self::last_dbox_index();
+            let prize = if released_dboxes_count < latest_dboxes_count {
+                PrizeOf::<T> {
+                    average_prize: Self::average_prize(),
+                    last_player_prize: <Ledger<T>>::get(Self::last_player_account()),
+                    first_index: last_dbox_index - (latest_dboxes_count - released_dboxes_count),
+                    last_index: last_dbox_index,
+                }
+            } else {
+                PrizeOf::<T> {
+                    average_prize: Zero::zero(),
+                    last_player_prize: <Ledger<T>>::get(Self::last_player_account()),
+                    first_index: last_dbox_index - 1,
+                    last_index: last_dbox_index,
+                }
+            };
+            <RoundPrizes<T>>::insert(round, prize);
+        }
+
+        // The admin account is replaced by the admin origin, it is not a system account any more
+        let admin_key = value_key_v0(b"AdminAccount");
+        if let Some(admin) = unhashed::get::<T::AccountId>(&admin_key) {
+            let accounts = vec![
+                Self::cashier_account(),
+                Self::reserve_account(),
+                Self::pool_account(),
+                Self::last_player_account(),
+                Self::team_account(),
+                Self::operator_account(),
+            ];
+            if !accounts.contains(&admin) {
+                <Ledger<T>>::remove(&admin);
+            }
+        }
+
+        unhashed::kill(&admin_key);
+        unhashed::kill(&released_key);
+        unhashed::kill(&value_key_v0(b"BonusDbox"));
+        unhashed::kill(&value_key_v0(b"AllOpeningDboxesCount"));
+        unhashed::kill(&value_key_v0(b"MaxOps"));
+    }
+
+    /// Convert the dbox of version 0 to the current layout
+    ///
+    /// The pushed bonus is kept in the value, the dboxes of previous rounds are put into round 0,
+    /// so no prize or bonus of the accumulators is pending for them. The snapshot is set by the
+    /// caller, and the opening dbox is active until it is opened by the migration.
+    ///
+    /// @old    the dbox of version 0
+    fn dbox_from_v0(old: &DboxV0Of<T>) -> DboxOf<T> {
+        let round = if old.create_position >= Self::round_start_dbox() {
+            Self::round_count()
+        } else {
+            0
+        };
+        let status = match old.status {
+            DboxStatusV0::None => DboxStatus::None,
+            DboxStatusV0::Locked(n) => DboxStatus::Locked(n),
+            DboxStatusV0::Active | DboxStatusV0::Opening => DboxStatus::Active,
+            DboxStatusV0::Opened => DboxStatus::Opened,
+        };
+
+        DboxOf::<T> {
+            id: old.id,
+            create_position: old.create_position,
+            status: status,
+            value: old.value,
+            version: old.version,
+            invitor: old.invitor.clone(),
+            round: round,
+            bonus_snapshot: Zero::zero(),
+        }
+    }
+}
+
+/// The key of a value removed since version 0
+///
+/// @name   the name of the value
+fn value_key_v0(name: &[u8]) -> [u8; 16] {
+    twox_128(&[&b"PandoraModule "[..], name].concat())
+}
+
+/// The key of an entry of a map removed since version 0
+///
+/// @name   the name of the map
+/// @key    the key of the entry
+fn map_key_v0(name: &[u8], key: u64) -> [u8; 32] {
+    let mut final_key = [&b"PandoraModule "[..], name].concat();
+    key.encode_to(&mut final_key);
+    blake2_256(&final_key)
+}
+
+/// The key of the open position of an opening dbox, only kept while migrating
+///
+/// @position   the create position of the dbox
+fn open_position_key(position: u64) -> [u8; 32] {
+    map_key_v0(b"MigrationOpenPositions", position)
+}
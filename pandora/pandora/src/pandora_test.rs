@@ -3,14 +3,16 @@
 mod tests {
     use crate::*;
 
-    use primitives::{Blake2Hasher, H256};
+    use primitives::{blake2_256, twox_128, Blake2Hasher, H256};
     use runtime_io::TestExternalities;
     use sr_primitives::weights::Weight;
     use sr_primitives::Perbill;
     use sr_primitives::{
         testing::Header,
-        traits::{BlakeTwo256, ConvertInto, IdentityLookup, OnFinalize},
+        traits::{BlakeTwo256, ConvertInto, IdentityLookup, OnFinalize, OnInitialize},
     };
+    use std::cell::RefCell;
+    use support::traits::{LockableCurrency, WithdrawReasons};
     use support::{assert_err, assert_ok, impl_outer_origin, parameter_types};
 
     impl_outer_origin! {
@@ -24,7 +26,7 @@ mod tests {
     pub struct Test;
     parameter_types! {
         pub const BlockHashCount: u64 = 250;
-        pub const MaximumBlockWeight: Weight = 1024;
+        pub const MaximumBlockWeight: Weight = 1_000_000;
         pub const MaximumBlockLength: u32 = 2 * 1024;
         pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
     }
@@ -50,12 +52,27 @@ mod tests {
 
     parameter_types! {
         pub const ExistentialDeposit: u128 = 500;
-        pub const TransferFee: u128 = 0;
         pub const CreationFee: u128 = 0;
         pub const TransactionBaseFee: u128 = 0;
         pub const TransactionByteFee: u128 = 1;
     }
 
+    thread_local! {
+        static TRANSFER_FEE: RefCell<u128> = RefCell::new(0);
+    }
+
+    // Mock transfer fee which is zero unless it is set by the test
+    pub struct TransferFee;
+    impl Get<u128> for TransferFee {
+        fn get() -> u128 {
+            TRANSFER_FEE.with(|f| *f.borrow())
+        }
+    }
+
+    fn set_transfer_fee(fee: u128) {
+        TRANSFER_FEE.with(|f| *f.borrow_mut() = fee);
+    }
+
     impl balances::Trait for Test {
         type Balance = Balance;
         type OnFreeBalanceZero = ();
@@ -70,36 +87,92 @@ mod tests {
 
     parameter_types! {
         pub const ExpirationValue: u32 = 50;
+        pub const BlockTime: u32 = 10;
         pub const MaxLatestValue: u64 = 5;
         pub const MinUnitPrice: Balance = 0; // FIXME:
         pub const MaxUnitPrice: Balance = 3500000000; // FIXME:
+        pub const MaxPriceAge: u64 = 10;
+        pub const TokenUnit: Balance = 1_000;
+        pub const MaxRounds: u64 = 3;
+        pub const MigrationBatchSize: u64 = 2;
         pub const DboxRatio: u32 = 35;
         pub const ReserveRatio: u32 = 35;
         pub const PoolRatio: u32 = 10;
         pub const LastPlayerRatio: u32 = 5;
         pub const TeamRatio: u32 = 5;
         pub const OperatorRatio: u32 = 5;
-        pub const InvitorRatio: u32 = 5;
+        pub const InvitorRatios: Vec<u32> = vec![5, 2, 1];
+        pub const RoyaltyRatio: u32 = 10;
+        pub const SubsidyPeriod: u64 = 10;
+        pub const MaxSubsidies: u32 = 2;
+        pub const Admin: u64 = 10000;
     }
 
     impl Trait for Test {
         type Event = ();
         type Expiration = ExpirationValue;
+        type BlockTime = BlockTime;
         type MaxLatest = MaxLatestValue;
         type MinUnitPrice = MinUnitPrice;
         type MaxUnitPrice = MaxUnitPrice;
+        type PriceProvider = MockPriceProvider;
+        type MaxPriceAge = MaxPriceAge;
+        type TokenUnit = TokenUnit;
+        type Randomness = MockRandomness;
+        type MaxRounds = MaxRounds;
+        type MigrationBatchSize = MigrationBatchSize;
         type DboxRatio = DboxRatio;
         type ReserveRatio = ReserveRatio;
         type PoolRatio = PoolRatio;
         type LastPlayerRatio = LastPlayerRatio;
         type TeamRatio = TeamRatio;
         type OperatorRatio = OperatorRatio;
-        type InvitorRatio = InvitorRatio;
+        type InvitorRatios = InvitorRatios;
+        type RoyaltyRatio = RoyaltyRatio;
+        type SubsidyPeriod = SubsidyPeriod;
+        type MaxSubsidies = MaxSubsidies;
+        type AdminOrigin = system::EnsureSignedBy<Admin, u64>;
         type Currency = Balances;
     }
 
+    thread_local! {
+        static PRICE: RefCell<Option<(Price, u64)>> = RefCell::new(None);
+    }
+
+    // Mock price provider
+    pub struct MockPriceProvider;
+    impl PriceProvider<u64> for MockPriceProvider {
+        fn price() -> Option<(Price, u64)> {
+            PRICE.with(|p| *p.borrow())
+        }
+    }
+
+    fn set_price(price: Price, updated_at: u64) {
+        PRICE.with(|p| *p.borrow_mut() = Some((price, updated_at)));
+    }
+
+    thread_local! {
+        static RANDOM_SEED: RefCell<H256> = RefCell::new(H256::zero());
+    }
+
+    // Mock randomness which is changed block by block, and differs by subject
+    pub struct MockRandomness;
+    impl Randomness<H256> for MockRandomness {
+        fn random(subject: &[u8]) -> H256 {
+            let seed = RANDOM_SEED.with(|r| *r.borrow());
+            BlakeTwo256::hash_of(&(seed, subject))
+        }
+    }
+
+    fn set_random_seed(seed: u8) {
+        let mut bytes = [0u8; 32];
+        bytes[0] = seed;
+        RANDOM_SEED.with(|r| *r.borrow_mut() = H256::from(bytes));
+    }
+
     type Balances = balances::Module<Test>;
     type Pandora = Module<Test>;
+    type System = system::Module<Test>;
 
     // Define previledged acounts
     const ADMIN_ACCOUNT: u64 = 10000;
@@ -124,6 +197,15 @@ mod tests {
     // This function basically just builds a genesis storage key/value store according to
     // our desired mockup.
     fn new_test_ext() -> TestExternalities {
+        new_test_ext_with_game(None, None, None)
+    }
+
+    // Build the genesis storage with the game inited at genesis
+    fn new_test_ext_with_game(
+        dbox_unit_price: Option<u128>,
+        status: Option<Status>,
+        max_active_dboxes_count: Option<u64>,
+    ) -> TestExternalities {
         let mut t = system::GenesisConfig::default()
             .build_storage::<Test>()
             .unwrap();
@@ -132,7 +214,9 @@ mod tests {
             balances: vec![
                 (ADMIN_ACCOUNT, 600_000),
                 (CASHIER_ACCOUNT, 100_000),
+                (RESERVE_ACCOUNT, 1_000),
                 (TEAM_ACCOUNT, 500_000),
+                (OPERATOR_ACCOUNT, 1_000),
                 (ALICE, 100_000),
                 (BOB, 100_000),
                 (DAVE, 100_000),
@@ -149,13 +233,16 @@ mod tests {
         .unwrap();
         // Add config for pandora
         GenesisConfig::<Test> {
-            admin_account: ADMIN_ACCOUNT,
             cashier_account: CASHIER_ACCOUNT,
             reserve_account: RESERVE_ACCOUNT,
             pool_account: POOL_ACCOUNT,
             last_player_account: LAST_PLAYER_ACCOUNT,
             team_account: TEAM_ACCOUNT,
             operator_account: OPERATOR_ACCOUNT,
+            dbox_unit_price: dbox_unit_price,
+            status: status,
+            max_active_dboxes_count: max_active_dboxes_count,
+            settlement_weight_ratio: None,
         }
         .assimilate_storage(&mut t)
         .unwrap();
@@ -174,6 +261,37 @@ mod tests {
         })
     }
 
+    #[test]
+    fn it_works_for_init_at_genesis() {
+        new_test_ext_with_game(Some(100), None, None).execute_with(|| {
+            assert_eq!(Pandora::game_status(), Status::Inited);
+            assert_eq!(Pandora::dbox_unit_price(), 100);
+            assert_eq!(Pandora::max_active_dboxes_count(), 1000);
+            assert_err!(
+                Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100),
+                "Already inited"
+            );
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+        });
+
+        new_test_ext_with_game(Some(100), Some(Status::Running), Some(2)).execute_with(|| {
+            assert_eq!(Pandora::game_status(), Status::Running);
+            assert_eq!(Pandora::round_count(), 1);
+            assert_eq!(Pandora::max_active_dboxes_count(), 2);
+            assert!(Pandora::round_summary(1).is_some());
+
+            assert_ok!(Pandora::create_dbox(Origin::signed(ALICE)));
+            assert_ok!(Pandora::create_dbox(Origin::signed(BOB)));
+            assert_err!(
+                Pandora::create_dbox(Origin::signed(DAVE)),
+                "Exceed max active dboxes limitation"
+            );
+        });
+    }
+
     #[test]
     fn it_works_for_creating_dbox() {
         new_test_ext().execute_with(|| {
@@ -181,7 +299,7 @@ mod tests {
             assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // Create a dbox
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
@@ -217,7 +335,7 @@ mod tests {
             assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // Create dboxes
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
@@ -226,8 +344,8 @@ mod tests {
             <Pandora as OnFinalize<u64>>::on_finalize(1);
 
             let dbox = Pandora::dbox_by_index(0);
-            let (has_pending, double) = Pandora::get_pending_bonus(&dbox);
-            assert_eq!(has_pending, false);
+            let (pending, double) = Pandora::get_pending_bonus(&dbox);
+            assert_eq!(pending, 35 + 35 / 2);
             assert_eq!(double, true);
 
             assert_ok!(Pandora::open_dbox(Origin::signed(RAY), dbox.id));
@@ -256,103 +374,60 @@ mod tests {
     }
 
     #[test]
-    fn it_works_for_opening_dbox_async() {
+    fn it_works_for_claiming_bonus() {
         new_test_ext().execute_with(|| {
             // Init the game
             assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
-            // Tries to open non-existed dbox
+            // Tries to claim non-existed dbox
             assert_err!(
-                Pandora::open_dbox(Origin::signed(RAY), H256::random()),
+                Pandora::claim(Origin::signed(RAY), H256::random()),
                 "Dbox does not exist"
             );
             // Create dboxes
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
-            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
-            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
-
-            let dbox = Pandora::dbox_by_index(0);
-            println!("dbox id={}", dbox.id);
-            let (has_pending, double) = Pandora::get_pending_bonus(&dbox);
-            assert_eq!(has_pending, true);
-            assert_eq!(double, true);
-
-            assert_ok!(Pandora::open_dbox(Origin::signed(RAY), dbox.id));
-            let dbox = Pandora::dbox_by_index(0);
-            assert_eq!(dbox.status, DboxStatus::Opening);
-
-            let player = Pandora::player(RAY);
-            assert_eq!(player.total_bonus, 0);
-
-            for i in 1..6 {
-                let bonus_dbox = Pandora::bonus_dbox();
-                let status = Pandora::game_status();
-                let dbox = Pandora::dbox_by_index(2);
-                println!(
-                    "bonus_dbox = {} status = {} dbox.bonus_position = {}",
-                    bonus_dbox, status as u32, dbox.bonus_position
-                );
-                <Pandora as OnFinalize<u64>>::on_finalize(i);
-            }
-            assert_eq!(Pandora::round_start_dbox(), 3);
-            let dbox = Pandora::dbox_by_index(0);
-            assert_eq!(dbox.status, DboxStatus::Opened);
-
-            let player = Pandora::player(RAY);
-            assert_eq!(player.total_bonus, (35 + 35 / 2) * 2);
-
             let dbox = Pandora::dbox_by_index(0);
-            println!("dbox id={}", dbox.id);
             assert_err!(
-                Pandora::open_dbox(Origin::signed(RAY), dbox.id),
-                "The status of dbox should be active"
+                Pandora::claim(Origin::signed(RAY), dbox.id),
+                "Nothing to claim"
             );
-
-            let dbox = Pandora::dbox_by_index(1);
-            println!("dbox id={}", dbox.id);
-            assert_ok!(Pandora::open_dbox(Origin::signed(RAY), dbox.id));
-
-            let player = Pandora::player(RAY);
-            assert_eq!(player.total_bonus, 104 + 35 / 2);
-            // 3th dbox
-            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
-
-            let dbox = Pandora::dbox_by_index(2);
-            println!("dbox id={}", dbox.id);
-            assert_ok!(Pandora::open_dbox(Origin::signed(RAY), dbox.id));
-
-            let player = Pandora::player(RAY);
-            assert_eq!(player.total_bonus, 121);
-
-            <Pandora as OnFinalize<u64>>::on_finalize(10);
-            assert_eq!(Pandora::all_opening_doxes_count(), 0);
-            let player = Pandora::player(RAY);
-            assert_eq!(player.total_bonus, 121);
-            // 4th dbox
-            assert_ok!(Pandora::create_dbox_with_invitor(
-                Origin::signed(BOB),
-                Some(RAY)
-            ));
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(BOB), None));
+            assert_eq!(Pandora::bonus_per_dbox(1), 35);
             assert_err!(
-                Pandora::create_dbox_with_invitor(Origin::signed(BOB), Some(RAY)),
-                "Invitee should be a new player"
+                Pandora::claim(Origin::signed(BOB), dbox.id),
+                "The owner of the dbox is not the sender"
             );
-            // 5th dbox
-            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(BOB), None));
-
-            let dbox = Pandora::dbox_by_index(4);
-            assert_ok!(Pandora::open_dbox(Origin::signed(BOB), dbox.id));
-            let dbox = Pandora::dbox_by_index(4);
-            assert_eq!(dbox.status, DboxStatus::Opening);
-
-            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(BOB), None));
-            <Pandora as OnFinalize<u64>>::on_finalize(11);
+            // Claim the bonus without opening the dbox
+            assert_ok!(Pandora::claim(Origin::signed(RAY), dbox.id));
+            assert_eq!(Balances::free_balance(&RAY), 99_999_900 + 35);
+            assert_eq!(Pandora::player(RAY).total_bonus, 35);
 
-            let dbox = Pandora::dbox_by_index(4);
+            let dbox = Pandora::dbox_by_index(0);
+            assert_eq!(dbox.status, DboxStatus::Active);
+            assert_eq!(dbox.value, 0);
+            assert_eq!(dbox.bonus_snapshot, 35);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (0, true));
+            assert_err!(
+                Pandora::claim(Origin::signed(RAY), dbox.id),
+                "Nothing to claim"
+            );
+            // The claimed dbox keeps sharing bonus
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(DAVE), None));
+            let dbox = Pandora::dbox_by_index(0);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (35 / 2, true));
+            // Open the dbox to get double bonus
+            assert_ok!(Pandora::open_dbox(Origin::signed(RAY), dbox.id));
+            let dbox = Pandora::dbox_by_index(0);
             assert_eq!(dbox.status, DboxStatus::Opened);
+            assert_eq!(Pandora::player(RAY).total_bonus, 35 + 35 / 2 * 2);
+            assert_eq!(Pandora::all_active_dboxes_count(), 2);
+            // Finalizing blocks only updates the timeout
+            <Pandora as OnFinalize<u64>>::on_finalize(1);
+            assert_eq!(Pandora::timeout(), 40);
+            assert_eq!(Pandora::player(RAY).total_bonus, 35 + 35 / 2 * 2);
         })
     }
 
@@ -363,7 +438,7 @@ mod tests {
             assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // Ray creates a dbox
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
@@ -378,7 +453,6 @@ mod tests {
             // Finalize a block
             <Pandora as OnFinalize<u64>>::on_finalize(1);
             assert_eq!(Pandora::timeout(), 40);
-            assert_eq!(Pandora::bonus_dbox(), 1);
 
             // Alice creates a dbox
             assert_ok!(Pandora::create_dbox_with_invitor(
@@ -390,17 +464,15 @@ mod tests {
 
             let dbox = Pandora::dbox_by_index(1);
             assert_eq!(dbox.create_position, 1);
-            assert_eq!(dbox.bonus_position, 0);
+            assert_eq!(dbox.bonus_snapshot, 35);
 
             assert_eq!(Pandora::timeout(), 50);
             // Finalize a block
             <Pandora as OnFinalize<u64>>::on_finalize(2);
             assert_eq!(Pandora::timeout(), 40);
-            let dbox = Pandora::dbox_by_index(1);
-            assert_eq!(dbox.bonus_position, 1);
 
             let dbox = Pandora::dbox_by_index(0);
-            assert_eq!(dbox.value, 35);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (35, true));
 
             // Ray open the dbox, will get twice of the box value
             assert_ok!(Pandora::open_dbox(Origin::signed(RAY), dbox.id));
@@ -417,12 +489,18 @@ mod tests {
             <Pandora as OnFinalize<u64>>::on_finalize(7);
             assert_eq!(Pandora::balance(&POOL_ACCOUNT), 0);
             assert_eq!(Pandora::average_prize(), 0);
+            // Players claim prizes of the settled round
+            let dbox = Pandora::dbox_by_index(0);
+            assert_ok!(Pandora::claim(Origin::signed(RAY), dbox.id));
             assert_eq!(
                 Balances::free_balance(&RAY),
                 99_999_900 + 70 + 20 / 3 * 2 + 5 * 2
             ); // 2 box operations
-            assert_eq!(Balances::free_balance(&ALICE), 99_900 + 20 / 3); // 1 box operation
-                                                                         // Next round begins
+            assert_err!(
+                Pandora::claim(Origin::signed(RAY), dbox.id),
+                "Nothing to claim"
+            );
+            // Next round begins
             assert_eq!(Pandora::game_status(), Status::Running);
             assert_eq!(Pandora::timeout(), 50);
             assert_eq!(Pandora::round_start_dbox(), 2);
@@ -448,13 +526,14 @@ mod tests {
             // Finalize blocks
             <Pandora as OnFinalize<u64>>::on_finalize(7);
             assert_eq!(Pandora::timeout(), 40);
-            // Alice opens a staled dbox
+            // Alice claims prize of a staled dbox
             let dbox = Pandora::dbox_by_index(1);
-            assert_eq!(dbox.value, 0);
-            assert_eq!(Balances::free_balance(&ALICE), 99_906);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (0, false));
+            assert_ok!(Pandora::claim(Origin::signed(ALICE), dbox.id));
+            assert_eq!(Balances::free_balance(&ALICE), 99_900 + 20 / 3); // 1 box operation
 
             let dbox = Pandora::dbox_by_index(2);
-            assert_eq!(dbox.value, 35 + 35 / 2 + 35 / 3);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (35 + 35 / 2 + 35 / 3, true));
             assert_err!(
                 Pandora::upgrade_dbox(Origin::signed(RAY), dbox.id),
                 "The owner of the dbox is not the sender"
@@ -465,10 +544,10 @@ mod tests {
             );
 
             let dbox = Pandora::dbox_by_index(3);
-            assert_eq!(dbox.value, 35 / 2 + 35 / 3);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (35 / 2 + 35 / 3, true));
 
             let dbox = Pandora::dbox_by_index(4);
-            assert_eq!(dbox.value, 35 / 3);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (35 / 3, true));
 
             // Finalize blocks
             for i in 1..4 {
@@ -487,7 +566,7 @@ mod tests {
             assert_eq!(Pandora::game_status(), Status::Inited);
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // Ray creates a dbox
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
@@ -514,7 +593,7 @@ mod tests {
                 assert_eq!(dbox.status, DboxStatus::Active);
                 assert_ok!(Pandora::open_dbox(Origin::signed(DAVE), dbox.id));
                 let dbox = Pandora::dbox_by_index(all_dboxes_count - 1);
-                assert_eq!(dbox.status, DboxStatus::Opening);
+                assert_eq!(dbox.status, DboxStatus::Opened);
             }
 
             for i in 1..5 {
@@ -523,11 +602,11 @@ mod tests {
 
             assert_eq!(Pandora::timeout(), 10);
             let dbox = Pandora::dbox_by_index(0);
-            assert_eq!(dbox.value, 35 * 3);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (35 * 3, true));
             // Pause game
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Paused as u8
+                Status::Paused
             ));
             assert_err!(
                 Pandora::upgrade_dbox(Origin::signed(RAY), dbox.id),
@@ -536,7 +615,7 @@ mod tests {
             // Run game again
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // Upgrade again
             assert_ok!(Pandora::upgrade_dbox(Origin::signed(RAY), dbox.id));
@@ -554,7 +633,7 @@ mod tests {
             let dbox = Pandora::dbox_by_index(4);
             assert_eq!(dbox.value, 0);
             assert_eq!(dbox.create_position, 4);
-            assert_eq!(dbox.bonus_position, 4);
+            assert_eq!(dbox.round, 1);
             assert_err!(
                 Pandora::upgrade_dbox(Origin::signed(RAY), dbox.id),
                 "Not enough money"
@@ -570,7 +649,7 @@ mod tests {
             assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // Ray creates a dbox
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
@@ -585,7 +664,7 @@ mod tests {
                 assert_eq!(dbox.status, DboxStatus::Active);
                 assert_ok!(Pandora::open_dbox(Origin::signed(DAVE), dbox.id));
                 let dbox = Pandora::dbox_by_index(all_dboxes_count - 1);
-                assert_eq!(dbox.status, DboxStatus::Opening);
+                assert_eq!(dbox.status, DboxStatus::Opened);
             }
 
             for i in 1..6 {
@@ -611,13 +690,13 @@ mod tests {
     }
 
     #[test]
-    fn it_works_for_draining_bonus() {
+    fn it_works_for_accumulating_bonus() {
         new_test_ext().execute_with(|| {
             // Init the game
             assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100_000));
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // RAY creates dboxes
             let count = 1_000;
@@ -626,21 +705,17 @@ mod tests {
             }
             assert_eq!(Balances::free_balance(&RAY), 100_000_000 - 100_000 * 1_000);
             assert_eq!(Pandora::all_dboxes_count(), count);
-            // Each OnFinalize will do 100 operations by default
-            let mut i = 1;
-            loop {
-                <Pandora as OnFinalize<u64>>::on_finalize(i);
-                let bonus_dbox = Pandora::bonus_dbox();
-                let status = Pandora::game_status();
-                println!("bonus_dbox = {} status = {}", bonus_dbox, status as u32);
-                if bonus_dbox == Pandora::all_dboxes_count() {
-                    break;
-                }
-                i += 1;
-            }
+            // The k-th dbox shares its bonus with all the k previous active dboxes
+            let expected: Balance = (1..count).map(|k| 35_000 / k as Balance).sum();
+            assert_eq!(Pandora::bonus_per_dbox(1), expected);
+            // Bonus is available without finalizing any block
+            let dbox = Pandora::dbox_by_index(0);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (expected, true));
+            let dbox = Pandora::dbox_by_index(count - 1);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (0, true));
 
+            <Pandora as OnFinalize<u64>>::on_finalize(1);
             assert_eq!(Pandora::game_status(), Status::Running);
-            assert_eq!(Pandora::bonus_dbox(), 1000);
         })
     }
 
@@ -652,7 +727,7 @@ mod tests {
             assert_eq!(Pandora::game_status(), Status::Inited);
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // Ray creates a dbox
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
@@ -673,6 +748,93 @@ mod tests {
                 Pandora::create_dbox_with_invitor(Origin::signed(BOB), Some(BOB)),
                 "Invitee should be a new player"
             );
+            // The recorded invitor gets commission of later dboxes
+            assert_ok!(Pandora::create_dbox(Origin::signed(BOB)));
+            assert_eq!(Pandora::player(RAY).total_commission, 5 + 5);
+            assert_eq!(Pandora::invitor_of(BOB), Some(RAY));
+            assert_eq!(Pandora::invitor_of(RAY), None);
+            // Nothing is changed if the cashier can not pay the commissions
+            Balances::set_lock(
+                *b"cashlock",
+                &CASHIER_ACCOUNT,
+                u128::max_value(),
+                u64::max_value(),
+                WithdrawReasons::all(),
+            );
+            let reserve = Pandora::balance(&RESERVE_ACCOUNT);
+            let bonus_per_dbox = Pandora::bonus_per_dbox(1);
+            let cashier = Balances::free_balance(&CASHIER_ACCOUNT);
+            assert_err!(
+                Pandora::create_dbox_with_invitor(Origin::signed(DAVE), Some(BOB)),
+                "account liquidity restrictions prevent withdrawal"
+            );
+            assert_eq!(Pandora::balance(&RESERVE_ACCOUNT), reserve);
+            assert_eq!(Pandora::bonus_per_dbox(1), bonus_per_dbox);
+            assert_eq!(Balances::free_balance(&CASHIER_ACCOUNT), cashier);
+            assert_eq!(Balances::free_balance(&DAVE), 100_000);
+            assert_eq!(Pandora::invitor_of(DAVE), None);
+            assert_eq!(Pandora::all_dboxes_count(), 3);
+            // The commissions are paid once the cashier can pay them
+            Balances::remove_lock(*b"cashlock", &CASHIER_ACCOUNT);
+            let ray_balance = Balances::free_balance(&RAY);
+            let bob_balance = Balances::free_balance(&BOB);
+            assert_ok!(Pandora::create_dbox_with_invitor(
+                Origin::signed(DAVE),
+                Some(BOB)
+            ));
+            assert_eq!(Pandora::player(BOB).total_commission, 5);
+            assert_eq!(Balances::free_balance(&BOB), bob_balance + 5);
+            assert_eq!(Pandora::player(RAY).total_commission, 5 + 5 + 2);
+            assert_eq!(Balances::free_balance(&RAY), ray_balance + 2);
+        })
+    }
+
+    #[test]
+    fn it_works_for_referral_tree() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            // Ray <- Bob <- Dave <- Eve <- Ferdie
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            let chain = [RAY, BOB, DAVE, EVE, FERDIE];
+            for i in 1..chain.len() {
+                assert_ok!(Pandora::create_dbox_with_invitor(
+                    Origin::signed(chain[i]),
+                    Some(chain[i - 1])
+                ));
+            }
+            // Commissions follow the tiers of 5, 2 and 1
+            assert_eq!(Pandora::player(EVE).total_commission, 5);
+            assert_eq!(Pandora::player(DAVE).total_commission, 5 + 2);
+            assert_eq!(Pandora::player(BOB).total_commission, 5 + 2 + 1);
+            assert_eq!(Pandora::player(RAY).total_commission, 5 + 2 + 1);
+            assert_eq!(Pandora::referral(RAY).commissions, vec![5, 2, 1]);
+            assert_eq!(Pandora::referral(BOB).commissions, vec![5, 2, 1]);
+            assert_eq!(Pandora::referral(EVE).commissions, vec![5]);
+            // Referral stats
+            let referral = Pandora::referral(RAY);
+            assert_eq!(referral.depth, 0);
+            assert_eq!(referral.invitees, 1);
+            assert_eq!(referral.descendants, 3);
+            let referral = Pandora::referral(BOB);
+            assert_eq!(referral.depth, 1);
+            assert_eq!(referral.descendants, 3);
+            let referral = Pandora::referral(FERDIE);
+            assert_eq!(referral.depth, 4);
+            assert_eq!(referral.invitees, 0);
+            assert_eq!(referral.descendants, 0);
+            // The invitor is recorded only once
+            assert_err!(
+                Pandora::create_dbox_with_invitor(Origin::signed(FERDIE), Some(RAY)),
+                "Invitee should be a new player"
+            );
+            assert_ok!(Pandora::create_dbox(Origin::signed(FERDIE)));
+            assert_eq!(Pandora::invitor_of(FERDIE), Some(EVE));
+            assert_eq!(Pandora::player(EVE).total_commission, 5 + 5);
         })
     }
 
@@ -684,7 +846,7 @@ mod tests {
             assert_eq!(Pandora::game_status(), Status::Inited);
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // create dboxes
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(BOB), None));
@@ -706,6 +868,11 @@ mod tests {
             assert_eq!(Pandora::timeout(), 50);
             assert_eq!(Pandora::round_count(), 2);
             assert_eq!(Pandora::round_start_dbox(), 4);
+            // Players claim prizes
+            for (player, index) in vec![(BOB, 0), (DAVE, 1), (NICOLE, 2), (RAY, 3)] {
+                let dbox = Pandora::dbox_by_index(index);
+                assert_ok!(Pandora::claim(Origin::signed(player), dbox.id));
+            }
 
             let player = Pandora::player(&BOB);
             assert_eq!(player.total_prize, 10);
@@ -729,7 +896,7 @@ mod tests {
             assert_eq!(Pandora::game_status(), Status::Inited);
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // create dboxes above max latest value
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(BOB), None));
@@ -765,6 +932,12 @@ mod tests {
             assert_eq!(Pandora::timeout(), 50);
             assert_eq!(Pandora::round_count(), 2);
             assert_eq!(Pandora::round_start_dbox(), 8);
+            // Players claim prizes
+            let players = vec![BOB, DAVE, EVE, NICOLE, FERDIE, CHARLIE, DJANGO, RAY];
+            for (index, player) in players.into_iter().enumerate() {
+                let dbox = Pandora::dbox_by_index(index as u64);
+                assert_ok!(Pandora::claim(Origin::signed(player), dbox.id));
+            }
 
             let player = Pandora::player(&BOB);
             assert_eq!(player.total_prize, 0);
@@ -794,7 +967,7 @@ mod tests {
             assert_eq!(Pandora::game_status(), Status::Inited);
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // create dboxes above max latest value
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(BOB), None));
@@ -821,6 +994,12 @@ mod tests {
             assert_eq!(Pandora::timeout(), 50);
             assert_eq!(Pandora::round_count(), 2);
             assert_eq!(Pandora::round_start_dbox(), 8);
+            // Players claim prizes
+            let players = vec![BOB, DAVE, EVE, NICOLE, RAY, RAY, RAY, RAY];
+            for (index, player) in players.into_iter().enumerate() {
+                let dbox = Pandora::dbox_by_index(index as u64);
+                assert_ok!(Pandora::claim(Origin::signed(player), dbox.id));
+            }
 
             let player = Pandora::player(&BOB);
             assert_eq!(player.total_prize, 0);
@@ -850,7 +1029,7 @@ mod tests {
             assert_eq!(Pandora::game_status(), Status::Inited);
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
             // create a single dbox
             assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
@@ -865,6 +1044,9 @@ mod tests {
             assert_eq!(Pandora::timeout(), 50);
             assert_eq!(Pandora::round_count(), 2);
             assert_eq!(Pandora::round_start_dbox(), 1);
+            // Prize is claimed by the player
+            let dbox = Pandora::dbox_by_index(0);
+            assert_ok!(Pandora::claim(Origin::signed(RAY), dbox.id));
 
             let player = Pandora::player(&RAY);
             assert_eq!(player.total_prize, 10 + 5);
@@ -878,38 +1060,1450 @@ mod tests {
     #[test]
     fn it_works_for_setting_status() {
         new_test_ext().execute_with(|| {
+            assert_err!(
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Running),
+                "Not inited"
+            );
             // Init the game
             assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
             assert_eq!(Pandora::game_status(), Status::Inited);
+
+            assert_err!(
+                Pandora::set_status(Origin::signed(RAY), Status::Running),
+                "Not authorized"
+            );
+            assert_err!(
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Paused),
+                "Invalid status transition"
+            );
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Running as u8
+                Status::Running
             ));
 
             assert_err!(
-                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Inited as u8),
-                "Invalid new status"
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Inited),
+                "Invalid status transition"
+            );
+            assert_err!(
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Settling),
+                "Invalid status transition"
             );
-
             assert_err!(
-                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Running as u8),
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Running),
                 "New status should be different from current status"
             );
+            // Pause and resume
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Paused
+            ));
+            assert_err!(
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Stopped),
+                "Invalid status transition"
+            );
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Stopped
+            ));
+            assert_err!(
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Running),
+                "Invalid status transition"
+            );
+        })
+    }
+
+    #[test]
+    fn it_works_for_stopping_with_pending_dboxes() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
+
+            assert_err!(
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Stopped),
+                "Pending dboxes exist"
+            );
+            // Stop the game after the round is settled
+            for i in 1..6 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::round_count(), 2);
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Stopped
+            ));
+            assert_eq!(Pandora::game_status(), Status::Stopped);
+        })
+    }
 
+    #[test]
+    fn it_works_for_setting_settlement_weight_ratio() {
+        new_test_ext().execute_with(|| {
             assert_err!(
-                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), 100),
-                "Invalid status value"
+                Pandora::set_settlement_weight_ratio(
+                    Origin::signed(ADMIN_ACCOUNT),
+                    Perbill::from_percent(20)
+                ),
+                "Not inited"
             );
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_eq!(Pandora::settlement_weight_ratio(), Perbill::from_percent(10));
 
             assert_err!(
-                Pandora::set_status(Origin::signed(RAY), 100),
+                Pandora::set_settlement_weight_ratio(Origin::signed(RAY), Perbill::from_percent(20)),
                 "Not authorized"
             );
+            assert_err!(
+                Pandora::set_settlement_weight_ratio(
+                    Origin::signed(ADMIN_ACCOUNT),
+                    Perbill::from_percent(10)
+                ),
+                "New value should be different from current value"
+            );
+            assert_err!(
+                Pandora::set_settlement_weight_ratio(Origin::signed(ADMIN_ACCOUNT), Perbill::zero()),
+                "Invalid range"
+            );
+
+            assert_ok!(Pandora::set_settlement_weight_ratio(
+                Origin::signed(ADMIN_ACCOUNT),
+                Perbill::from_percent(20)
+            ));
+            assert_eq!(Pandora::settlement_weight_ratio(), Perbill::from_percent(20));
+        })
+    }
+
+    #[test]
+    fn it_works_for_deferring_settlement() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            // Leave too little weight for settling
+            assert_ok!(Pandora::set_settlement_weight_ratio(
+                Origin::signed(ADMIN_ACCOUNT),
+                Perbill::from_parts(1)
+            ));
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
+
+            for i in 1..6 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            // The round is closed, but the settling is deferred
+            assert_eq!(Pandora::game_status(), Status::Settling);
+            assert_eq!(Pandora::round_count(), 1);
+            assert_eq!(Pandora::average_prize(), 10);
+            assert_err!(
+                Pandora::create_dbox_with_invitor(Origin::signed(ALICE), None),
+                "Status is not ready"
+            );
+
+            <Pandora as OnFinalize<u64>>::on_finalize(6);
+            assert_eq!(Pandora::game_status(), Status::Settling);
+            // Settling ends once there is enough weight
+            assert_ok!(Pandora::set_settlement_weight_ratio(
+                Origin::signed(ADMIN_ACCOUNT),
+                Perbill::from_percent(10)
+            ));
+            <Pandora as OnFinalize<u64>>::on_finalize(7);
+            assert_eq!(Pandora::game_status(), Status::Running);
+            assert_eq!(Pandora::round_count(), 2);
+            assert_eq!(Pandora::timeout(), 50);
+        })
+    }
+
+    #[test]
+    fn it_works_for_reserving_settlement_weight() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
+            // Only the bookkeeping is reserved while the round is running
+            <Pandora as OnInitialize<u64>>::on_initialize(1);
+            assert_eq!(System::all_extrinsics_weight(), FINALIZE_WEIGHT);
+            for i in 1..5 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::timeout(), 10);
+
+            // The settling is reserved before the extrinsics of the last block of round
+            System::set_block_limits(0, 0);
+            <Pandora as OnInitialize<u64>>::on_initialize(5);
+            let reserved = FINALIZE_WEIGHT + BEGIN_SETTLING_WEIGHT + END_SETTLING_WEIGHT;
+            assert_eq!(System::all_extrinsics_weight(), reserved);
+            // The extrinsics fill the block, and the reserved weight is left for settling
+            System::set_block_limits(reserved + 980_000, 0);
+            <Pandora as OnFinalize<u64>>::on_finalize(5);
+            assert_eq!(Pandora::game_status(), Status::Running);
+            assert_eq!(Pandora::round_count(), 2);
+        })
+    }
 
+    #[test]
+    fn it_works_for_querying_dashboard() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
             assert_ok!(Pandora::set_status(
                 Origin::signed(ADMIN_ACCOUNT),
-                Status::Stopped as u8
+                Status::Running
             ));
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(BOB), None));
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
+            // Dboxes of player
+            let dboxes = Pandora::get_player_dboxes(&RAY);
+            assert_eq!(dboxes.len(), 2);
+            assert_eq!(dboxes[0].create_position, 0);
+            assert_eq!(dboxes[0].status, DboxStatus::Active);
+            assert_eq!(dboxes[0].pending_bonus, 35 + 35 / 2);
+            assert!(dboxes[0].double);
+            assert_eq!(dboxes[0].pending_prize, 0);
+            assert_eq!(dboxes[1].create_position, 2);
+            assert_eq!(dboxes[1].pending_bonus, 0);
+            // Round summary
+            let round = Pandora::get_round_info();
+            assert_eq!(round.round, 1);
+            assert_eq!(round.status, Status::Running);
+            assert_eq!(round.timeout, 50);
+            assert_eq!(round.pool, 10 * 3);
+            assert_eq!(round.latest_dboxes, vec![(RAY, 0), (BOB, 1), (RAY, 2)]);
+            // Dry run of opening
+            let dbox = Pandora::dbox_by_index(0);
+            assert_eq!(Pandora::get_open_payout(dbox.id), Some((35 + 35 / 2) * 2));
+            assert_eq!(Pandora::get_open_payout(H256::random()), None);
+            assert_ok!(Pandora::open_dbox(Origin::signed(RAY), dbox.id));
+            assert_eq!(Pandora::get_open_payout(dbox.id), None);
+            assert_eq!(Pandora::player(RAY).total_bonus, (35 + 35 / 2) * 2);
         })
     }
+
+    #[test]
+    fn it_works_for_transferring_dbox() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            for _i in 0..3 {
+                assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
+            }
+            let dbox = Pandora::dbox_by_index(0);
+            assert_err!(
+                Pandora::transfer_dbox(Origin::signed(BOB), dbox.id, ALICE),
+                "The owner of the dbox is not the sender"
+            );
+            assert_err!(
+                Pandora::transfer_dbox(Origin::signed(RAY), dbox.id, RAY),
+                "Can not transfer dbox to the sender"
+            );
+            assert_err!(
+                Pandora::transfer_dbox(Origin::signed(RAY), dbox.id, POOL_ACCOUNT),
+                "System account is not allowed"
+            );
+            // Transfer the first dbox, the last one is swapped into its position
+            assert_ok!(Pandora::transfer_dbox(Origin::signed(RAY), dbox.id, BOB));
+            assert_eq!(Pandora::owner_of(dbox.id), Some(BOB));
+            assert_eq!(Pandora::owned_dbox_count(RAY), 2);
+            assert_eq!(Pandora::owned_dbox_count(BOB), 1);
+            assert_eq!(Pandora::dbox_of_owner_by_index((BOB, 0)), dbox.id);
+            let last_dbox = Pandora::dbox_by_index(2);
+            assert_eq!(Pandora::dbox_of_owner_by_index((RAY, 0)), last_dbox.id);
+            assert_eq!(
+                Pandora::dbox_of_owner_by_index((RAY, 1)),
+                Pandora::dbox_by_index(1).id
+            );
+            assert_eq!(Pandora::dbox_of_owner_by_index((RAY, 2)), H256::default());
+            // The new owner opens the dbox by index
+            let (pending, double) = Pandora::get_pending_bonus(&dbox);
+            assert_eq!((pending, double), (35 + 35 / 2, true));
+            assert_ok!(Pandora::open_dbox_by_index(Origin::signed(BOB), 0));
+            assert_eq!(Pandora::player(BOB).total_bonus, pending * 2);
+            // The previous owner opens the swapped dbox
+            assert_ok!(Pandora::open_dbox_by_index(Origin::signed(RAY), 0));
+            let last_dbox = Pandora::dbox_by_index(2);
+            assert_eq!(last_dbox.status, DboxStatus::Opened);
+        })
+    }
+
+    #[test]
+    fn it_works_for_trading_dbox() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
+            let dbox = Pandora::dbox_by_index(0);
+
+            assert_err!(
+                Pandora::buy_dbox(Origin::signed(BOB), dbox.id, 1_000),
+                "Dbox is not listed"
+            );
+            assert_err!(
+                Pandora::list_dbox(Origin::signed(BOB), dbox.id, 1_000),
+                "The owner of the dbox is not the sender"
+            );
+            assert_err!(
+                Pandora::list_dbox(Origin::signed(RAY), dbox.id, 0),
+                "Price should not be zero"
+            );
+            assert_ok!(Pandora::list_dbox(Origin::signed(RAY), dbox.id, 1_000));
+            assert_eq!(Pandora::dbox_listing(dbox.id), Some(1_000));
+
+            assert_err!(
+                Pandora::buy_dbox(Origin::signed(RAY), dbox.id, 1_000),
+                "Can not buy dbox from the sender"
+            );
+            assert_err!(
+                Pandora::buy_dbox(Origin::signed(BOB), dbox.id, 999),
+                "Price is higher than expected"
+            );
+            // The royalty is not computed with overflowed price
+            assert_ok!(Pandora::list_dbox(
+                Origin::signed(RAY),
+                dbox.id,
+                u64::max_value()
+            ));
+            assert_err!(
+                Pandora::buy_dbox(Origin::signed(BOB), dbox.id, u64::max_value()),
+                "Overflow calculating the royalty"
+            );
+            assert_eq!(Balances::reserved_balance(&BOB), 0);
+            // Cancel and list again
+            assert_ok!(Pandora::unlist_dbox(Origin::signed(RAY), dbox.id));
+            assert_eq!(Pandora::dbox_listing(dbox.id), None);
+            assert_err!(
+                Pandora::unlist_dbox(Origin::signed(RAY), dbox.id),
+                "Dbox is not listed"
+            );
+            assert_ok!(Pandora::list_dbox(Origin::signed(RAY), dbox.id, 1_050));
+
+            let ray_balance = Balances::free_balance(&RAY);
+            assert_ok!(Pandora::buy_dbox(Origin::signed(BOB), dbox.id, 1_050));
+            assert_eq!(Pandora::owner_of(dbox.id), Some(BOB));
+            assert_eq!(Pandora::dbox_listing(dbox.id), None);
+            assert_eq!(Pandora::owned_dbox_count(RAY), 0);
+            assert_eq!(Pandora::owned_dbox_count(BOB), 1);
+            // Royalty is paid to operator
+            assert_eq!(Balances::free_balance(&BOB), 100_000 - 1_050);
+            assert_eq!(Balances::reserved_balance(&BOB), 0);
+            assert_eq!(Balances::free_balance(&RAY), ray_balance + 945);
+            assert_eq!(Balances::free_balance(&OPERATOR_ACCOUNT), 1_000 + 105);
+            // Transferring removes the listing
+            assert_ok!(Pandora::list_dbox(Origin::signed(BOB), dbox.id, 2_000));
+            assert_ok!(Pandora::transfer_dbox(Origin::signed(BOB), dbox.id, ALICE));
+            assert_eq!(Pandora::dbox_listing(dbox.id), None);
+            // Claiming or opening removes the listing, so the drained dbox is not sold
+            assert_ok!(Pandora::create_dbox_with_invitor(Origin::signed(RAY), None));
+            assert_ok!(Pandora::list_dbox(Origin::signed(ALICE), dbox.id, 1_000));
+            assert_ok!(Pandora::claim(Origin::signed(ALICE), dbox.id));
+            assert_eq!(Pandora::dbox_listing(dbox.id), None);
+            assert_ok!(Pandora::list_dbox(Origin::signed(ALICE), dbox.id, 1_000));
+            assert_ok!(Pandora::open_dbox(Origin::signed(ALICE), dbox.id));
+            assert_eq!(Pandora::dbox_listing(dbox.id), None);
+            // Opened dbox can not be listed
+            assert_err!(
+                Pandora::list_dbox(Origin::signed(ALICE), dbox.id, 1_000),
+                "The status of dbox should be active"
+            );
+        })
+    }
+
+    #[test]
+    fn it_works_for_fiat_unit_price() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_err!(
+                Pandora::set_fiat_unit_price(Origin::signed(RAY), Some(10_000)),
+                "Not authorized"
+            );
+            assert_err!(
+                Pandora::set_fiat_unit_price(Origin::signed(ADMIN_ACCOUNT), Some(0)),
+                "Fiat unit price should not be zero"
+            );
+            assert_ok!(Pandora::set_fiat_unit_price(
+                Origin::signed(ADMIN_ACCOUNT),
+                Some(10_000)
+            ));
+            // The round can not start without price
+            assert_err!(
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Running),
+                "Price is not available"
+            );
+            System::set_block_number(20);
+            set_price(20_000, 5);
+            assert_err!(
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Running),
+                "Price is stale"
+            );
+            // 1 fiat per dbox with 2 fiat per token
+            set_price(20_000, 15);
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_eq!(Pandora::dbox_unit_price(), 500);
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_eq!(Balances::free_balance(&RAY), 100_000_000 - 500);
+            // The round ends with stale price, so the next round is deferred
+            set_price(40_000, 9);
+            for i in 1..6 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::game_status(), Status::Settling);
+            assert_eq!(Pandora::round_count(), 1);
+            // The next round starts with new price
+            set_price(40_000, 20);
+            <Pandora as OnFinalize<u64>>::on_finalize(6);
+            assert_eq!(Pandora::game_status(), Status::Running);
+            assert_eq!(Pandora::round_count(), 2);
+            assert_eq!(Pandora::dbox_unit_price(), 250);
+            // The unit price is clamped
+            set_price(1, 20);
+            assert_ok!(Pandora::set_fiat_unit_price(
+                Origin::signed(ADMIN_ACCOUNT),
+                Some(10_000_000_000)
+            ));
+            for i in 7..12 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::round_count(), 3);
+            assert_eq!(Pandora::dbox_unit_price(), 3500000000);
+            // Fixed unit price is kept when fiat pricing is disabled
+            assert_ok!(Pandora::set_fiat_unit_price(Origin::signed(ADMIN_ACCOUNT), None));
+            set_price(40_000, 20);
+            for i in 12..17 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::round_count(), 4);
+            assert_eq!(Pandora::dbox_unit_price(), 3500000000);
+        })
+    }
+
+    #[test]
+    fn it_works_for_drawing_jackpot() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_err!(
+                Pandora::set_jackpot_ratio(Origin::signed(RAY), 50),
+                "Not authorized"
+            );
+            assert_err!(
+                Pandora::set_jackpot_ratio(Origin::signed(ADMIN_ACCOUNT), 101),
+                "Invalid range"
+            );
+            assert_ok!(Pandora::set_jackpot_ratio(Origin::signed(ADMIN_ACCOUNT), 50));
+            // 5 players create dboxes, the pool has 50
+            let players = [ALICE, BOB, DAVE, EVE, RAY];
+            for player in players.iter() {
+                assert_ok!(Pandora::create_dbox(Origin::signed(*player)));
+            }
+            assert_eq!(Pandora::balance(&POOL_ACCOUNT), 50);
+            // The seed known by the sender of the last extrinsic
+            set_random_seed(1);
+            for i in 1..6 {
+                System::set_block_number(i);
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::round_count(), 2);
+            // Half of the pool is kept for the jackpot, which is not drawn in the same block
+            assert_eq!(Pandora::round_prize(1).average_prize, 25 / 5);
+            let jackpot = Pandora::jackpot(1).unwrap();
+            assert_eq!(jackpot.amount, 25);
+            assert_eq!(jackpot.first_position, 0);
+            assert_eq!(jackpot.last_position, 5);
+            assert_eq!(jackpot.draw_at, 6);
+            assert_eq!(jackpot.lucky_position, None);
+            assert_eq!(Pandora::pending_jackpot_round(), Some(1));
+            // The seed changes in the committed block, and the jackpot is drawn by its own subject
+            set_random_seed(8);
+            let seed = MockRandomness::random(&(JACKPOT_SUBJECT, 1u64).encode());
+            assert_ne!(seed, MockRandomness::random(&(JACKPOT_SUBJECT, 2u64).encode()));
+            let lucky_position = u64::decode(&mut seed.as_ref()).unwrap() % 5;
+            System::set_block_number(6);
+            <Pandora as OnFinalize<u64>>::on_finalize(6);
+            let jackpot = Pandora::jackpot(1).unwrap();
+            assert_eq!(jackpot.lucky_position, Some(lucky_position));
+            assert_eq!(Pandora::pending_jackpot_round(), None);
+            // The seed of later blocks does not change the result
+            set_random_seed(9);
+            System::set_block_number(7);
+            <Pandora as OnFinalize<u64>>::on_finalize(7);
+            assert_eq!(Pandora::jackpot(1).unwrap().lucky_position, Some(lucky_position));
+            // Only the lucky dbox gets the jackpot
+            let unlucky_position = (lucky_position + 1) % 5;
+            let unlucky_player = players[unlucky_position as usize];
+            let dbox = Pandora::dbox_by_index(unlucky_position);
+            let bonus = Pandora::get_pending_bonus(&dbox).0;
+            assert_ok!(Pandora::claim(Origin::signed(unlucky_player), dbox.id));
+            assert_eq!(Pandora::player(unlucky_player).total_bonus, bonus);
+            assert_eq!(Pandora::player(unlucky_player).total_prize, 5);
+
+            let lucky_player = players[lucky_position as usize];
+            let dbox = Pandora::dbox_by_index(lucky_position);
+            assert_ok!(Pandora::claim(Origin::signed(lucky_player), dbox.id));
+            assert_eq!(Pandora::player(lucky_player).total_prize, 5 + 25);
+            assert!(Pandora::jackpot(1).unwrap().claimed);
+            // The jackpot is claimed only once
+            assert_eq!(Pandora::get_pending_jackpot(&dbox), 0);
+        })
+    }
+
+    #[test]
+    fn it_works_for_round_summaries() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            System::set_block_number(1);
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_ok!(Pandora::create_dbox_with_invitor(
+                Origin::signed(BOB),
+                Some(RAY)
+            ));
+            let summary = Pandora::round_summary(1).unwrap();
+            assert_eq!(summary.start_block, 1);
+            assert_eq!(summary.end_block, None);
+            assert_eq!(summary.total_commission, 5);
+            // The round ends
+            System::set_block_number(3);
+            for i in 1..6 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            let summary = Pandora::round_summary(1).unwrap();
+            assert_eq!(summary.end_block, Some(3));
+            assert_eq!(summary.dbox_count, 2);
+            assert_eq!(summary.pool, 20);
+            assert_eq!(summary.last_player, Some(BOB));
+            assert_eq!(summary.total_bonus, 0);
+            assert_eq!(summary.total_prize, 0);
+            // Money claimed later is counted for the round of dbox
+            assert_ok!(Pandora::claim(
+                Origin::signed(RAY),
+                Pandora::dbox_by_index(0).id
+            ));
+            assert_ok!(Pandora::claim(
+                Origin::signed(BOB),
+                Pandora::dbox_by_index(1).id
+            ));
+            let summary = Pandora::round_summary(1).unwrap();
+            assert_eq!(summary.total_bonus, 35);
+            assert_eq!(summary.total_prize, 10 + 10 + 10);
+
+            let summary = Pandora::round_summary(2).unwrap();
+            assert_eq!(summary.start_block, 3);
+            assert_eq!(summary.total_bonus, 0);
+            // Old rounds are pruned
+            for i in 6..21 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::round_count(), 5);
+            assert_eq!(Pandora::round_summary(1), None);
+            assert_eq!(Pandora::round_summary(2), None);
+            let summaries = Pandora::get_round_summaries(100, 100);
+            let rounds: Vec<u64> = summaries.iter().map(|x| x.0).collect();
+            assert_eq!(rounds, vec![5, 4, 3]);
+            assert_eq!(summaries[1].1.dbox_count, 0);
+            assert_eq!(summaries[1].1.last_player, None);
+
+            let summaries = Pandora::get_round_summaries(4, 1);
+            assert_eq!(summaries.len(), 1);
+            assert_eq!(summaries[0].0, 4);
+        })
+    }
+
+    #[test]
+    fn it_works_for_pruning_rounds() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            System::set_block_number(1);
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_ok!(Pandora::create_dbox(Origin::signed(BOB)));
+            // The round ends with unclaimed bonus and prize
+            for i in 1..6 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::round_count(), 2);
+            assert_eq!(Pandora::bonus_per_dbox(1), 35);
+            assert!(<RoundPrizes<Test>>::exists(1));
+            assert!(<LatestDboxes<Test>>::exists(0));
+            assert!(<LatestDboxes<Test>>::exists(1));
+            // Only the summary of round is pruned
+            for i in 6..21 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::round_count(), 5);
+            assert_eq!(Pandora::round_summary(1), None);
+            assert_eq!(Pandora::bonus_per_dbox(1), 35);
+            assert!(<RoundPrizes<Test>>::exists(1));
+            // The dboxes of the pruned round still claim their bonus and prize
+            let balance = Balances::free_balance(RAY);
+            assert_ok!(Pandora::claim(
+                Origin::signed(RAY),
+                Pandora::dbox_by_index(0).id
+            ));
+            assert_eq!(Balances::free_balance(RAY), balance + 35 + 10);
+            let balance = Balances::free_balance(BOB);
+            assert_ok!(Pandora::claim(
+                Origin::signed(BOB),
+                Pandora::dbox_by_index(1).id
+            ));
+            assert_eq!(Balances::free_balance(BOB), balance + 10 + 10);
+            assert!(!<LatestDboxes<Test>>::exists(0));
+            assert!(!<LatestDboxes<Test>>::exists(1));
+            assert_err!(
+                Pandora::claim(Origin::signed(RAY), Pandora::dbox_by_index(0).id),
+                "Nothing to claim"
+            );
+        })
+    }
+
+    #[test]
+    fn it_works_for_opening_dbox_while_paused() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_ok!(Pandora::create_dbox(Origin::signed(BOB)));
+            assert_eq!(Pandora::all_active_dboxes_count(), 2);
+            assert_eq!(Pandora::latest_dboxes_count(), 2);
+            // The opened dbox is not doubled, but it is not active any more
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Paused
+            ));
+            let dbox = Pandora::dbox_by_index(0);
+            assert_eq!(Pandora::get_open_payout(dbox.id), Some(35));
+            assert_ok!(Pandora::open_dbox(Origin::signed(RAY), dbox.id));
+            assert_eq!(Pandora::all_active_dboxes_count(), 1);
+            assert_eq!(Pandora::latest_dboxes_count(), 2);
+            // The bonus of next dbox is shared by the remaining active dbox only
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(DAVE)));
+            let dbox = Pandora::dbox_by_index(1);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (35, true));
+            assert_eq!(Pandora::all_active_dboxes_count(), 2);
+        })
+    }
+
+    #[test]
+    fn it_works_for_forbidding_players() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_ok!(Pandora::create_dbox_with_invitor(
+                Origin::signed(BOB),
+                Some(RAY)
+            ));
+            let dbox_id = Pandora::dbox_by_index(0).id;
+            // Only admin can forbid players
+            assert_err!(
+                Pandora::forbid_player(Origin::signed(BOB), RAY, 7),
+                "Not authorized"
+            );
+            assert_err!(
+                Pandora::forbid_player(Origin::signed(ADMIN_ACCOUNT), CHARLIE, 7),
+                "Player does not exist"
+            );
+            assert_ok!(Pandora::forbid_player(Origin::signed(ADMIN_ACCOUNT), RAY, 7));
+            assert_eq!(Pandora::player(RAY).status, PlayerStatus::Forbidden);
+            assert_eq!(Pandora::forbidden_reason(RAY), Some(7));
+            assert_err!(
+                Pandora::forbid_player(Origin::signed(ADMIN_ACCOUNT), RAY, 8),
+                "Player is not active"
+            );
+            // Forbidden player can not play
+            assert_err!(
+                Pandora::create_dbox(Origin::signed(RAY)),
+                "Player is forbidden"
+            );
+            assert_err!(
+                Pandora::create_dbox_with_invitor(Origin::signed(RAY), None),
+                "Player is forbidden"
+            );
+            assert_err!(
+                Pandora::upgrade_dbox(Origin::signed(RAY), dbox_id),
+                "Player is forbidden"
+            );
+            assert_err!(
+                Pandora::claim(Origin::signed(RAY), dbox_id),
+                "Player is forbidden"
+            );
+            assert_err!(
+                Pandora::transfer_dbox(Origin::signed(RAY), dbox_id, DAVE),
+                "Player is forbidden"
+            );
+            // Forbidden player can not receive dboxes
+            let bob_dbox_id = Pandora::dbox_by_index(1).id;
+            assert_err!(
+                Pandora::transfer_dbox(Origin::signed(BOB), bob_dbox_id, RAY),
+                "Receiver is forbidden"
+            );
+            assert_ok!(Pandora::list_dbox(Origin::signed(BOB), bob_dbox_id, 100));
+            assert_err!(
+                Pandora::buy_dbox(Origin::signed(RAY), bob_dbox_id, 100),
+                "Player is forbidden"
+            );
+            assert_ok!(Pandora::unlist_dbox(Origin::signed(BOB), bob_dbox_id));
+            assert_eq!(Pandora::owner_of(bob_dbox_id), Some(BOB));
+            assert_err!(
+                Pandora::create_dbox_with_invitor(Origin::signed(DAVE), Some(RAY)),
+                "Invitor should be active player"
+            );
+            // Pending bonus is frozen by default
+            assert_err!(
+                Pandora::redirect_forbidden_bonus(Origin::signed(ADMIN_ACCOUNT), dbox_id),
+                "Bonus of forbidden player is frozen"
+            );
+            assert_ok!(Pandora::set_forbidden_bonus_policy(
+                Origin::signed(ADMIN_ACCOUNT),
+                BonusPolicy::Redirect
+            ));
+            let reserve = Pandora::balance(&RESERVE_ACCOUNT);
+            let cashier = Balances::free_balance(CASHIER_ACCOUNT);
+            assert_ok!(Pandora::redirect_forbidden_bonus(
+                Origin::signed(ADMIN_ACCOUNT),
+                dbox_id
+            ));
+            // The money is kept by the cashier for the reserve
+            assert_eq!(Pandora::balance(&RESERVE_ACCOUNT), reserve + 35);
+            assert_eq!(Balances::free_balance(CASHIER_ACCOUNT), cashier);
+            assert_eq!(Balances::free_balance(RESERVE_ACCOUNT), 1_000);
+            assert_eq!(Pandora::player(RAY).total_bonus, 0);
+            // Player can play again after being unforbidden
+            assert_ok!(Pandora::unforbid_player(Origin::signed(ADMIN_ACCOUNT), RAY));
+            assert_eq!(Pandora::player(RAY).status, PlayerStatus::Active);
+            assert_eq!(Pandora::forbidden_reason(RAY), None);
+            assert_err!(
+                Pandora::redirect_forbidden_bonus(Origin::signed(ADMIN_ACCOUNT), dbox_id),
+                "Player is not forbidden"
+            );
+            assert_err!(
+                Pandora::claim(Origin::signed(RAY), dbox_id),
+                "Nothing to claim"
+            );
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+        })
+    }
+
+    #[test]
+    fn it_works_for_rotating_system_accounts() {
+        new_test_ext().execute_with(|| {
+            const NEW_CASHIER: u64 = 10007;
+            const NEW_TEAM: u64 = 10008;
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_ok!(Pandora::create_dbox(Origin::signed(BOB)));
+            // Only admin can rotate system accounts
+            assert_err!(
+                Pandora::set_system_account(Origin::signed(BOB), SystemAccount::Cashier, NEW_CASHIER),
+                "Not authorized"
+            );
+            assert_err!(
+                Pandora::set_system_account(
+                    Origin::signed(ADMIN_ACCOUNT),
+                    SystemAccount::Cashier,
+                    RESERVE_ACCOUNT
+                ),
+                "System account is not allowed"
+            );
+            assert_err!(
+                Pandora::set_system_account(Origin::signed(ADMIN_ACCOUNT), SystemAccount::Cashier, RAY),
+                "Player is not allowed"
+            );
+            assert_err!(
+                Pandora::set_system_account(
+                    Origin::signed(ADMIN_ACCOUNT),
+                    SystemAccount::Cashier,
+                    NEW_CASHIER
+                ),
+                "New account does not exist"
+            );
+            // The free and reserved money of players is moved to the new cashier without fee
+            set_transfer_fee(10);
+            assert_ok!(Balances::transfer(Origin::signed(ALICE), NEW_CASHIER, 1_000));
+            assert_ok!(Balances::reserve(&CASHIER_ACCOUNT, 50));
+            assert_ok!(Pandora::set_system_account(
+                Origin::signed(ADMIN_ACCOUNT),
+                SystemAccount::Cashier,
+                NEW_CASHIER
+            ));
+            assert_eq!(Pandora::cashier_account(), NEW_CASHIER);
+            assert_eq!(Balances::free_balance(NEW_CASHIER), 1_000 + 100_000 + 200);
+            assert_eq!(Balances::total_balance(&CASHIER_ACCOUNT), 0);
+            // The ledger balance is moved to the new account
+            let team_balance = Pandora::balance(&TEAM_ACCOUNT);
+            assert_ok!(Pandora::set_system_account(
+                Origin::signed(ADMIN_ACCOUNT),
+                SystemAccount::Team,
+                NEW_TEAM
+            ));
+            assert_eq!(Pandora::team_account(), NEW_TEAM);
+            assert_eq!(Pandora::balance(&NEW_TEAM), team_balance);
+            assert!(!<Ledger<Test>>::exists(&TEAM_ACCOUNT));
+            // The game goes on with the new cashier
+            assert_ok!(Pandora::create_dbox(Origin::signed(DAVE)));
+            assert_eq!(Balances::free_balance(NEW_CASHIER), 1_000 + 100_000 + 300);
+            assert_ok!(Pandora::claim(
+                Origin::signed(RAY),
+                Pandora::dbox_by_index(0).id
+            ));
+        })
+    }
+
+    #[test]
+    fn it_works_for_migrating_storage_from_v0() {
+        new_test_ext().execute_with(|| {
+            use support::storage::unhashed;
+            let value_key = |name: &str| twox_128(format!("PandoraModule {}", name).as_bytes());
+            let map_key = |name: &str, key: u64| {
+                let mut final_key = format!("PandoraModule {}", name).into_bytes();
+                key.encode_to(&mut final_key);
+                blake2_256(&final_key)
+            };
+            // New chains start with the latest version
+            assert_eq!(Pandora::storage_version(), 1);
+            // Populate a game caught in settling with the dboxes of version 0
+            StorageVersion::put(0);
+            GameStatus::put(Status::Settling);
+            RoundCount::put(2);
+            RoundStartDbox::put(2);
+            AllDboxesCount::put(6);
+            AllActiveDboxesCount::put(3);
+            // The bonus of dbox 4 is pushed to dbox 2 only, and the bonus of dbox 5 is not pushed
+            unhashed::put(&value_key("BonusDbox"), &4u64);
+            let dboxes = [
+                (migration::DboxStatusV0::Opened, 0, 0),
+                (migration::DboxStatusV0::Active, 0, 0),
+                (migration::DboxStatusV0::Active, 5, 2),
+                (migration::DboxStatusV0::Active, 6, 3),
+                (migration::DboxStatusV0::Opening, 8, 3),
+                (migration::DboxStatusV0::Active, 9, 2),
+            ];
+            for (i, (status, bonus_per_dbox, bonus_position)) in dboxes.iter().enumerate() {
+                let old = migration::DboxV0::<H256, u128, u64> {
+                    id: H256::repeat_byte(i as u8),
+                    create_position: i as u64,
+                    status: *status,
+                    value: 10 * i as u128,
+                    version: 0,
+                    invitor: Some(RAY),
+                    open_position: 6,
+                    bonus_per_dbox: *bonus_per_dbox,
+                    bonus_position: *bonus_position,
+                };
+                unhashed::put(&<AllDboxesArray<Test>>::hashed_key_for(i as u64), &old);
+            }
+            <DboxOwner<Test>>::insert(H256::repeat_byte(4), BOB);
+            // Dbox 4 is waiting for the bonus of dbox 5 to be opened
+            unhashed::put(&value_key("AllOpeningDboxesCount"), &1u64);
+            unhashed::put(&map_key("AllOpeningDboxesArray", 0), &4u64);
+            unhashed::put(&map_key("AllOpeningDboxesMap", 4), &0u64);
+            // The prize is released to the first one of the latest dboxes
+            LatestDboxesCount::put(3);
+            LastDboxIndex::put(3);
+            <LatestDboxes<Test>>::insert(0, (ALICE, 2));
+            <LatestDboxes<Test>>::insert(1, (BOB, 3));
+            <LatestDboxes<Test>>::insert(2, (DAVE, 5));
+            <AveragePrize<Test>>::put(50);
+            <Ledger<Test>>::insert(LAST_PLAYER_ACCOUNT, 100);
+            unhashed::put(&value_key("ReleasedDboxesCount"), &1u64);
+            // The orphans of version 0
+            unhashed::put(&value_key("AdminAccount"), &ADMIN_ACCOUNT);
+            unhashed::put(&value_key("MaxOps"), &100u32);
+            <Ledger<Test>>::insert(ADMIN_ACCOUNT, 0);
+            // Dboxes are migrated in batches, and the game is suspended meanwhile
+            <Pandora as OnInitialize<u64>>::on_initialize(1);
+            assert_eq!(Pandora::storage_version(), 0);
+            assert_eq!(Pandora::migration_cursor(), 2);
+            assert_err!(
+                Pandora::create_dbox(Origin::signed(RAY)),
+                "Storage is migrating"
+            );
+            <Pandora as OnFinalize<u64>>::on_finalize(1);
+            assert_eq!(Pandora::game_status(), Status::Settling);
+            <Pandora as OnInitialize<u64>>::on_initialize(2);
+            <Pandora as OnInitialize<u64>>::on_initialize(3);
+            assert_eq!(Pandora::migration_cursor(), 6);
+            // The opening queue is left to the next block
+            assert_eq!(Pandora::storage_version(), 0);
+            assert_eq!(Pandora::dbox_by_index(4).status, DboxStatus::Active);
+            <Pandora as OnInitialize<u64>>::on_initialize(4);
+            assert_eq!(Pandora::storage_version(), 1);
+            assert_eq!(Pandora::migration_cursor(), 0);
+            // Dboxes of previous rounds have nothing pending from accumulators
+            let dbox = Pandora::dbox_by_index(0);
+            assert_eq!(dbox.status, DboxStatus::Opened);
+            assert_eq!(dbox.round, 0);
+            let dbox = Pandora::dbox_by_index(1);
+            assert_eq!(dbox.id, H256::repeat_byte(1));
+            assert_eq!(dbox.status, DboxStatus::Active);
+            assert_eq!(dbox.value, 10);
+            assert_eq!(dbox.round, 0);
+            assert_eq!(dbox.bonus_snapshot, 0);
+            // The bonus not pushed yet is pending for the dboxes of current round
+            assert_eq!(Pandora::bonus_per_dbox(2), 8 + 9);
+            let dbox = Pandora::dbox_by_index(2);
+            assert_eq!(dbox.round, 2);
+            assert_eq!(dbox.invitor, Some(RAY));
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (20 + 9, false));
+            let dbox = Pandora::dbox_by_index(3);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (30 + 8 + 9, false));
+            let dbox = Pandora::dbox_by_index(5);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (50, false));
+            // The opening dbox is paid with the bonus of dbox 5, doubled
+            let dbox = Pandora::dbox_by_index(4);
+            assert_eq!(dbox.status, DboxStatus::Opened);
+            assert_eq!(Balances::free_balance(BOB), 100_000 + (40 + 9) * 2);
+            assert_eq!(Pandora::all_active_dboxes_count(), 3);
+            // The prize of the latest dboxes not released yet is kept for them
+            let prize = Pandora::round_prize(2);
+            assert_eq!(prize.average_prize, 50);
+            assert_eq!(prize.last_player_prize, 100);
+            assert_eq!(prize.first_index, 1);
+            assert_eq!(prize.last_index, 3);
+            // The storage of version 0 is removed
+            assert!(!<Ledger<Test>>::exists(ADMIN_ACCOUNT));
+            for name in &[
+                "BonusDbox",
+                "AllOpeningDboxesCount",
+                "ReleasedDboxesCount",
+                "AdminAccount",
+                "MaxOps",
+            ] {
+                assert!(!unhashed::exists(&value_key(name)));
+            }
+            assert!(!unhashed::exists(&map_key("AllOpeningDboxesArray", 0)));
+            assert!(!unhashed::exists(&map_key("AllOpeningDboxesMap", 4)));
+            // The game goes on
+            <Pandora as OnFinalize<u64>>::on_finalize(4);
+            assert_eq!(Pandora::game_status(), Status::Running);
+            assert_eq!(Pandora::round_count(), 3);
+        })
+    }
+
+    // A xorshift generator, so the simulation is reproducible without extra dependencies
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            let mut x = self.0;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            self.0 = x;
+            x
+        }
+    }
+
+    const PLAYERS: [u64; 9] = [ALICE, BOB, DAVE, EVE, FERDIE, CHARLIE, DJANGO, NICOLE, RAY];
+
+    // Check the invariants of the game economy
+    fn check_invariants() {
+        // Money is neither created nor lost
+        let accounts = [
+            ADMIN_ACCOUNT,
+            CASHIER_ACCOUNT,
+            RESERVE_ACCOUNT,
+            POOL_ACCOUNT,
+            LAST_PLAYER_ACCOUNT,
+            TEAM_ACCOUNT,
+            OPERATOR_ACCOUNT,
+        ];
+        let total: u128 = accounts
+            .iter()
+            .chain(PLAYERS.iter())
+            .map(|account| Balances::total_balance(account))
+            .sum();
+        assert_eq!(total, Balances::total_issuance());
+        // Dboxes are consistent with the indexes
+        let count = Pandora::all_dboxes_count();
+        let owned: u64 = PLAYERS.iter().map(|p| Pandora::owned_dbox_count(p)).sum();
+        assert_eq!(owned, count);
+        let round = Pandora::round_count();
+        let mut active = 0;
+        let mut obligations: u128 = 0;
+        for i in 0..count {
+            let dbox = Pandora::dbox_by_index(i);
+            assert_eq!(dbox.create_position, i);
+            assert_eq!(<AllDboxesIndex<Test>>::get(dbox.id), i);
+            let owner = Pandora::owner_of(dbox.id).expect("Dbox should have an owner");
+            let index = <OwnedDboxesIndex<Test>>::get(dbox.id);
+            assert_eq!(Pandora::dbox_of_owner_by_index((owner, index)), dbox.id);
+            if dbox.status == DboxStatus::Active && dbox.round == round {
+                active += 1;
+            }
+            let (bonus, _) = Pandora::get_pending_bonus(&dbox);
+            let (prize, _) = Pandora::get_pending_prize(&dbox);
+            obligations += bonus + prize + Pandora::get_pending_jackpot(&dbox);
+        }
+        // Active counter is exactly the active dboxes of current round
+        assert_eq!(active, Pandora::all_active_dboxes_count());
+        assert!(Pandora::latest_dboxes_count() <= MaxLatestValue::get());
+        // The ledger is kept by the cashier too, the reserve pays the doubled values, and the pool
+        // and last player are turned into the prizes once the round is settling
+        let mut ledgers = vec![RESERVE_ACCOUNT, TEAM_ACCOUNT, OPERATOR_ACCOUNT];
+        if Pandora::game_status() != Status::Settling {
+            ledgers.extend_from_slice(&[POOL_ACCOUNT, LAST_PLAYER_ACCOUNT]);
+        }
+        obligations += ledgers
+            .iter()
+            .map(|account| Pandora::balance(account))
+            .sum::<u128>();
+        // Cashier can always pay the pending money
+        assert!(Balances::free_balance(CASHIER_ACCOUNT) >= obligations);
+    }
+
+    #[test]
+    fn it_works_for_random_simulation() {
+        new_test_ext().execute_with(|| {
+            let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_jackpot_ratio(Origin::signed(ADMIN_ACCOUNT), 20));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+
+            for n in 1..2_001 {
+                System::set_block_number(n);
+                System::set_block_limits(0, 0);
+                set_random_seed(rng.next() as u8);
+                <Pandora as OnInitialize<u64>>::on_initialize(n);
+                // The result of each action is ignored, failed actions should not break anything
+                for _ in 0..rng.next() % 3 {
+                    let player = PLAYERS[(rng.next() % PLAYERS.len() as u64) as usize];
+                    let count = Pandora::all_dboxes_count();
+                    let dbox_id = if count > 0 {
+                        Some(Pandora::dbox_by_index(rng.next() % count).id)
+                    } else {
+                        None
+                    };
+                    let owner = dbox_id.and_then(Pandora::owner_of).unwrap_or(player);
+                    let origin = Origin::signed(owner);
+                    match (rng.next() % 10, dbox_id) {
+                        (0..=2, _) => {
+                            let _ = Pandora::create_dbox(Origin::signed(player));
+                        }
+                        (3, _) => {
+                            let _ = Pandora::create_dbox_with_invitor(
+                                Origin::signed(player),
+                                Some(owner),
+                            );
+                        }
+                        (4, Some(dbox_id)) => {
+                            let _ = Pandora::open_dbox(origin, dbox_id);
+                        }
+                        (5..=6, Some(dbox_id)) => {
+                            let _ = Pandora::upgrade_dbox(origin, dbox_id);
+                        }
+                        (7..=8, Some(dbox_id)) => {
+                            let _ = Pandora::claim(origin, dbox_id);
+                        }
+                        (9, _) => {
+                            // Pause or resume the game now and then
+                            let status = match Pandora::game_status() {
+                                Status::Running => Status::Paused,
+                                _ => Status::Running,
+                            };
+                            if rng.next() % 10 == 0 {
+                                let _ = Pandora::set_status(
+                                    Origin::signed(ADMIN_ACCOUNT),
+                                    status,
+                                );
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                <Pandora as OnFinalize<u64>>::on_finalize(n);
+                check_invariants();
+            }
+            // The game has gone through many rounds
+            assert!(Pandora::round_count() > 10);
+            assert!(Pandora::all_dboxes_count() > 100);
+
+            // Every dbox claims its pending money, including the dboxes of pruned rounds
+            assert_eq!(Pandora::round_summary(1), None);
+            for i in 0..Pandora::all_dboxes_count() {
+                let dbox = Pandora::dbox_by_index(i);
+                let owner = Pandora::owner_of(dbox.id).unwrap();
+                let (bonus, _) = Pandora::get_pending_bonus(&dbox);
+                let (prize, _) = Pandora::get_pending_prize(&dbox);
+                let amount = bonus + prize + Pandora::get_pending_jackpot(&dbox);
+                if amount == 0 {
+                    assert_err!(
+                        Pandora::claim(Origin::signed(owner), dbox.id),
+                        "Nothing to claim"
+                    );
+                    continue;
+                }
+                let balance = Balances::free_balance(owner);
+                assert_ok!(Pandora::claim(Origin::signed(owner), dbox.id));
+                assert_eq!(Balances::free_balance(owner), balance + amount);
+            }
+            check_invariants();
+        })
+    }
+
+    #[test]
+    fn it_works_for_emergency_shutdown() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_ok!(Pandora::create_dbox(Origin::signed(BOB)));
+            assert_ok!(Pandora::create_dbox(Origin::signed(DAVE)));
+            let ray_dbox = Pandora::dbox_by_index(0).id;
+            let bob_dbox = Pandora::dbox_by_index(1).id;
+            let dave_dbox = Pandora::dbox_by_index(2).id;
+            assert_err!(
+                Pandora::emergency_withdraw(Origin::signed(RAY), ray_dbox),
+                "Status is not ready"
+            );
+            assert_ok!(Pandora::forbid_player(Origin::signed(ADMIN_ACCOUNT), BOB, 7));
+            // Only admin can shut down the game
+            assert_err!(
+                Pandora::set_status(Origin::signed(RAY), Status::EmergencyShutdown),
+                "Not authorized"
+            );
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::EmergencyShutdown
+            ));
+            // Reserve, pool and prize of last player are shared by the active dboxes
+            assert_eq!(Pandora::emergency_share(), (35 * 3 + 10 * 3 + 5 * 3) / 3);
+            assert_eq!(Pandora::balance(&RESERVE_ACCOUNT), 0);
+            assert_eq!(Pandora::balance(&POOL_ACCOUNT), 0);
+            assert_eq!(Pandora::balance(&LAST_PLAYER_ACCOUNT), 0);
+            assert_err!(
+                Pandora::create_dbox(Origin::signed(RAY)),
+                "Status is not ready"
+            );
+            assert_err!(
+                Pandora::claim(Origin::signed(RAY), ray_dbox),
+                "Status is not ready"
+            );
+            // Players withdraw the bonus and the share
+            assert_err!(
+                Pandora::emergency_withdraw(Origin::signed(BOB), ray_dbox),
+                "The owner of the dbox is not the sender"
+            );
+            let balance = Balances::free_balance(RAY);
+            assert_ok!(Pandora::emergency_withdraw(Origin::signed(RAY), ray_dbox));
+            assert_eq!(Balances::free_balance(RAY), balance + 35 + 17 + 50);
+            assert_eq!(Pandora::dbox_by_index(0).status, DboxStatus::Opened);
+            assert_err!(
+                Pandora::emergency_withdraw(Origin::signed(RAY), ray_dbox),
+                "Nothing to claim"
+            );
+            let balance = Balances::free_balance(DAVE);
+            assert_ok!(Pandora::emergency_withdraw(Origin::signed(DAVE), dave_dbox));
+            assert_eq!(Balances::free_balance(DAVE), balance + 50);
+            // Forbidden players withdraw too
+            let balance = Balances::free_balance(BOB);
+            assert_ok!(Pandora::emergency_withdraw(Origin::signed(BOB), bob_dbox));
+            assert_eq!(Balances::free_balance(BOB), balance + 17 + 50);
+            assert_eq!(Pandora::all_active_dboxes_count(), 0);
+            // There is no way back
+            assert_err!(
+                Pandora::set_status(Origin::signed(ADMIN_ACCOUNT), Status::Running),
+                "Invalid status transition"
+            );
+        })
+    }
+
+    #[test]
+    fn it_works_for_emergency_shutdown_without_active_dboxes() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_ok!(Pandora::create_dbox(Origin::signed(BOB)));
+            let ray_dbox = Pandora::dbox_by_index(0).id;
+            let bob_dbox = Pandora::dbox_by_index(1).id;
+            assert_ok!(Pandora::open_dbox(Origin::signed(RAY), ray_dbox));
+            assert_ok!(Pandora::open_dbox(Origin::signed(BOB), bob_dbox));
+            assert_eq!(Pandora::all_active_dboxes_count(), 0);
+            let money = Pandora::balance(&RESERVE_ACCOUNT)
+                + Pandora::balance(&POOL_ACCOUNT)
+                + Pandora::balance(&LAST_PLAYER_ACCOUNT);
+            assert!(money > 0);
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::EmergencyShutdown
+            ));
+            // Nothing is shared, the money is kept in the ledger of reserve
+            assert_eq!(Pandora::emergency_share(), 0);
+            assert_eq!(Pandora::balance(&RESERVE_ACCOUNT), money);
+            assert_eq!(Pandora::balance(&POOL_ACCOUNT), 0);
+            assert_eq!(Pandora::balance(&LAST_PLAYER_ACCOUNT), 0);
+            assert_err!(
+                Pandora::emergency_withdraw(Origin::signed(RAY), ray_dbox),
+                "Nothing to claim"
+            );
+            check_invariants();
+        })
+    }
+
+    #[test]
+    fn it_works_for_emergency_shutdown_while_settling() {
+        new_test_ext().execute_with(|| {
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            // Leave too little weight for ending the settling
+            assert_ok!(Pandora::set_settlement_weight_ratio(
+                Origin::signed(ADMIN_ACCOUNT),
+                Perbill::from_parts(1)
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_ok!(Pandora::create_dbox(Origin::signed(BOB)));
+            let ray_dbox = Pandora::dbox_by_index(0);
+            let bob_dbox = Pandora::dbox_by_index(1);
+            for i in 1..6 {
+                <Pandora as OnFinalize<u64>>::on_finalize(i);
+            }
+            assert_eq!(Pandora::game_status(), Status::Settling);
+            let (ray_prize, _) = Pandora::get_pending_prize(&ray_dbox);
+            let (bob_prize, _) = Pandora::get_pending_prize(&bob_dbox);
+            assert_eq!(ray_prize + bob_prize, 10 * 2 + 5 * 2);
+
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::EmergencyShutdown
+            ));
+            // Only the reserve is shared, the pool and prize of last player are the prizes
+            assert_eq!(Pandora::emergency_share(), 35 * 2 / 2);
+            assert_eq!(Pandora::balance(&RESERVE_ACCOUNT), 0);
+            assert_eq!(Pandora::balance(&POOL_ACCOUNT), 0);
+            assert_eq!(Pandora::balance(&LAST_PLAYER_ACCOUNT), 0);
+            check_invariants();
+            // The round is not settled after shutdown
+            <Pandora as OnFinalize<u64>>::on_finalize(6);
+            assert_eq!(Pandora::game_status(), Status::EmergencyShutdown);
+            assert_eq!(Pandora::round_count(), 1);
+            // Players withdraw the bonus, the share and the prizes
+            let balance = Balances::free_balance(RAY);
+            assert_ok!(Pandora::emergency_withdraw(
+                Origin::signed(RAY),
+                ray_dbox.id
+            ));
+            assert_eq!(Balances::free_balance(RAY), balance + 35 + 35 + ray_prize);
+            let balance = Balances::free_balance(BOB);
+            assert_ok!(Pandora::emergency_withdraw(
+                Origin::signed(BOB),
+                bob_dbox.id
+            ));
+            assert_eq!(Balances::free_balance(BOB), balance + 35 + bob_prize);
+            check_invariants();
+        })
+    }
+
+    #[test]
+    fn it_works_for_paying_fee() {
+        new_test_ext().execute_with(|| {
+            // Fees are not paid before the game is running
+            assert_err!(
+                Pandora::pay_fee(&BOB, &Call::create_dbox(), 10),
+                "Status is not ready"
+            );
+
+            // Init the game
+            assert_ok!(Pandora::init(Origin::signed(ADMIN_ACCOUNT), 100));
+            assert_ok!(Pandora::set_status(
+                Origin::signed(ADMIN_ACCOUNT),
+                Status::Running
+            ));
+            assert_ok!(Pandora::create_dbox(Origin::signed(RAY)));
+            assert_ok!(Pandora::create_dbox(Origin::signed(ALICE)));
+            let ray_dbox = Pandora::dbox_by_index(0).id;
+            assert_eq!(Pandora::get_pending_bonus(&Pandora::dbox_by_index(0)).0, 35);
+
+            // Checking the fee changes nothing
+            let cashier = Balances::free_balance(&CASHIER_ACCOUNT);
+            let operator = Balances::free_balance(&OPERATOR_ACCOUNT);
+            let open = Call::open_dbox(ray_dbox);
+            assert_ok!(Pandora::can_pay_fee(&RAY, &open, 10));
+            assert_eq!(Pandora::dbox_by_index(0).value, 0);
+            assert_eq!(Balances::free_balance(&CASHIER_ACCOUNT), cashier);
+
+            // The fee is paid out of the pending value of dbox
+            assert_ok!(Pandora::pay_fee(&RAY, &open, 10));
+            let dbox = Pandora::dbox_by_index(0);
+            assert_eq!(dbox.value, 25);
+            assert_eq!(Pandora::get_pending_bonus(&dbox), (25, true));
+            assert_eq!(Balances::free_balance(&CASHIER_ACCOUNT), cashier - 10);
+            assert_eq!(Balances::free_balance(&RAY), 99_999_900);
+
+            // The fee is subsidized by the operator if no dbox has enough value
+            assert_ok!(Pandora::pay_fee(&RAY, &open, 30));
+            assert_eq!(Pandora::dbox_by_index(0).value, 25);
+            assert_eq!(Balances::free_balance(&OPERATOR_ACCOUNT), operator - 30);
+            assert_eq!(Pandora::subsidy_of(RAY), (0, 1));
+
+            // The listed dboxes do not pay the fees
+            assert_ok!(Pandora::list_dbox(Origin::signed(RAY), ray_dbox, 200));
+            assert_ok!(Pandora::pay_fee(&RAY, &open, 10));
+            assert_eq!(Pandora::dbox_by_index(0).value, 25);
+            assert_eq!(Pandora::subsidy_of(RAY), (0, 2));
+            assert_ok!(Pandora::unlist_dbox(Origin::signed(RAY), ray_dbox));
+
+            // The moves which would fail are not paid
+            assert_err!(
+                Pandora::pay_fee(&RAY, &Call::upgrade_dbox(ray_dbox), 10),
+                "Not enough money"
+            );
+            assert_err!(
+                Pandora::pay_fee(&BOB, &open, 10),
+                "The owner of the dbox is not the sender"
+            );
+            assert_err!(
+                Pandora::pay_fee(&RAY, &Call::claim(ray_dbox), 10),
+                "Not a game move"
+            );
+
+            // New players are subsidized for their first dbox
+            let operator = Balances::free_balance(&OPERATOR_ACCOUNT);
+            assert_ok!(Pandora::pay_fee(&BOB, &Call::create_dbox(), 10));
+            assert_eq!(Pandora::subsidy_of(BOB), (0, 1));
+            assert_ok!(Pandora::create_dbox(Origin::signed(BOB)));
+            let bob_open = Call::open_dbox(Pandora::dbox_by_index(2).id);
+
+            // Subsidies are limited in each period, even if the moves in one block are all
+            // checked before being paid
+            assert_ok!(Pandora::can_pay_fee(&BOB, &bob_open, 10));
+            assert_ok!(Pandora::can_pay_fee(&BOB, &bob_open, 10));
+            assert_ok!(Pandora::pay_fee(&BOB, &bob_open, 10));
+            assert_err!(
+                Pandora::pay_fee(&BOB, &bob_open, 10),
+                "Too many subsidized fees"
+            );
+            assert_eq!(Balances::free_balance(&OPERATOR_ACCOUNT), operator - 20);
+            assert_eq!(Pandora::subsidy_of(BOB), (0, 2));
+            System::set_block_number(10);
+            assert_ok!(Pandora::pay_fee(&BOB, &bob_open, 10));
+            assert_eq!(Pandora::subsidy_of(BOB), (10, 1));
+
+            // Forbidden players pay the fees by themselves
+            assert_ok!(Pandora::forbid_player(Origin::signed(ADMIN_ACCOUNT), RAY, 7));
+            assert_err!(Pandora::pay_fee(&RAY, &open, 10), "Player is forbidden");
+        })
+    }
+
+    #[test]
+    fn it_works_for_type_metadata() {
+        use support::metadata::{undescribed_types, DecodeDifferent, TypeDefinition};
+
+        let types = Pandora::types();
+        let find = |name: &'static str| {
+            types
+                .iter()
+                .find(|ty| ty.name == DecodeDifferent::Encode(name))
+                .expect("Type is described")
+        };
+
+        // The types of storage and events are described, `Price` is described by price module
+        assert_eq!(
+            undescribed_types(
+                &types,
+                &Pandora::storage_metadata(),
+                Event::<Test>::metadata(),
+                &["Price"]
+            ),
+            Vec::<String>::new()
+        );
+
+        // The status of player is an enum
+        match &find("Player").definition {
+            TypeDefinition::Struct(DecodeDifferent::Encode(fields)) => {
+                assert_eq!(fields[3].name, Some(DecodeDifferent::Encode("status")));
+                assert_eq!(fields[3].ty, DecodeDifferent::Encode("PlayerStatus"));
+            }
+            _ => panic!("Player is not a struct"),
+        }
+        match &find("PlayerStatus").definition {
+            TypeDefinition::Enum(DecodeDifferent::Encode(variants)) => {
+                let names: Vec<_> = variants.iter().map(|v| v.name.clone()).collect();
+                assert_eq!(
+                    names,
+                    vec![
+                        DecodeDifferent::Encode("None"),
+                        DecodeDifferent::Encode("Active"),
+                        DecodeDifferent::Encode("Forbidden"),
+                    ]
+                );
+            }
+            _ => panic!("PlayerStatus is not an enum"),
+        }
+        assert_eq!(
+            find("DboxOf").definition,
+            TypeDefinition::Alias(DecodeDifferent::Encode("Dbox<Hash, Balance, AccountId>"))
+        );
+    }
 }
@@ -0,0 +1,16 @@
+//! Runtime API definition for price module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+
+pub use price::Price;
+
+sr_api::decl_runtime_apis! {
+    pub trait PriceApi<BlockNumber> where
+        BlockNumber: Codec,
+    {
+        /// Get the current price and the block number at which it was last reported
+        fn current_price() -> Option<(Price, BlockNumber)>;
+    }
+}
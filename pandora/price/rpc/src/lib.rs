@@ -0,0 +1,84 @@
+//! RPC interface for the price module.
+
+use client::blockchain::HeaderBackend;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use price_module_rpc_runtime_api::Price as PriceValue;
+use sr_primitives::{
+    generic::BlockId,
+    traits::{Block as BlockT, ProvideRuntimeApi},
+};
+use std::sync::Arc;
+
+pub use self::gen_client::Client as PriceClient;
+pub use price_module_rpc_runtime_api::PriceApi as PriceRuntimeApi;
+
+#[rpc]
+pub trait PriceApi<BlockHash, BlockNumber> {
+    /// Get the current price and the block number at which it was last reported
+    #[rpc(name = "price_currentPrice")]
+    fn current_price(&self, at: Option<BlockHash>) -> Result<Option<(PriceValue, BlockNumber)>>;
+}
+
+/// A struct that implements the [`PriceApi`].
+pub struct Price<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Price<C, B> {
+    /// Create new `Price` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Price {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+    /// The call to runtime failed.
+    RuntimeError,
+}
+
+impl From<Error> for i64 {
+    fn from(e: Error) -> i64 {
+        match e {
+            Error::RuntimeError => 1,
+        }
+    }
+}
+
+/// Convert the error of runtime api to rpc error
+fn runtime_error<E: std::fmt::Debug>(message: &str, e: E) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(Error::RuntimeError.into()),
+        message: message.into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+impl<C, Block, BlockNumber> PriceApi<<Block as BlockT>::Hash, BlockNumber> for Price<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static,
+    C: ProvideRuntimeApi,
+    C: HeaderBackend<Block>,
+    C::Api: PriceRuntimeApi<Block, BlockNumber>,
+    BlockNumber: Codec,
+{
+    fn current_price(
+        &self,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<(PriceValue, BlockNumber)>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        api.current_price(&at)
+            .map_err(|e| runtime_error("Unable to query current price.", e))
+    }
+}
@@ -3,20 +3,49 @@
 use codec::{Decode, Encode};
 use oracle::OracleMixedIn;
 use rstd::prelude::*;
-use sr_primitives::traits::{Bounded, CheckedAdd, CheckedSub, EnsureOrigin, OnFinalize, Zero};
+use sr_primitives::traits::{
+    CheckedAdd, CheckedSub, EnsureOrigin, IdentifyAccount, OnFinalize, SaturatedConversion, Verify,
+    Zero,
+};
+use sr_primitives::transaction_validity::{
+    InvalidTransaction, TransactionPriority, TransactionValidity, ValidTransaction,
+};
+use sr_primitives::weights::SimpleDispatchInfo;
+use sr_primitives::ApplyError;
 use support::traits::{
     ChangeMembers, Currency, Get, LockIdentifier, LockableCurrency, ReservableCurrency,
     WithdrawReasons,
 };
 use support::{
-    decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue,
+    decl_event, decl_module, decl_storage,
+    dispatch::Result,
+    ensure,
+    metadata::{DescribeType, ModuleTypeMetadata, TypeMetadata},
+    unsigned::ValidateUnsigned,
+    Parameter, StorageMap, StorageValue,
 };
-use system::{ensure_root, ensure_signed};
+use system::{ensure_none, ensure_root, ensure_signed};
 
 #[cfg(test)]
 mod price_test;
 
-type Price = u128;
+/// The context of the payload signed for unsigned price reports
+const REPORT_CONTEXT: &[u8] = b"pandora-price-report";
+
+/// The price reported by oracles, scaled by 10_000
+pub type Price = u128;
+
+/// Provide the current price to other modules
+pub trait PriceProvider<BlockNumber> {
+    /// Get the current price and the block number at which it was last reported
+    fn price() -> Option<(Price, BlockNumber)>;
+}
+
+impl<BlockNumber> PriceProvider<BlockNumber> for () {
+    fn price() -> Option<(Price, BlockNumber)> {
+        None
+    }
+}
 
 pub trait Trait: balances::Trait {
     /// Round length
@@ -29,25 +58,54 @@ pub trait Trait: balances::Trait {
     type OracleMixedIn: OracleMixedIn<Self>;
     /// The currency type
     type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+    /// The origin which can manage the module
+    type AdminOrigin: EnsureOrigin<Self::Origin>;
+    /// The signature of unsigned price reports, made by the key of oracle
+    type ReportSignature: Verify<Signer = Self::ReportSigner> + Parameter;
+    /// The signer of unsigned price reports, identified as the account of oracle
+    type ReportSigner: IdentifyAccount<AccountId = Self::AccountId>;
+    /// The priority of unsigned price reports in the transaction pool
+    type UnsignedPriority: Get<TransactionPriority>;
     /// Event
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
     // type ReportOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode)]
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, DescribeType)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct PriceReport<AccountId> {
     reporter: AccountId,
     price: Price,
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode)]
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, DescribeType)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct PendingRequest<Hash, BlockNumber> {
     id: Hash,
     expired_at: BlockNumber,
 }
 
+/// The price report submitted without transaction fee, signed by the oracle
+#[derive(PartialEq, Eq, Clone, Encode, Decode, DescribeType)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct UnsignedReport<AccountId, Hash> {
+    /// The oracle reporting the price
+    pub reporter: AccountId,
+    /// The price
+    pub price: Price,
+    /// The id of the requested job
+    pub id: Hash,
+}
+
+impl<AccountId: Encode, Hash: Encode> UnsignedReport<AccountId, Hash> {
+    /// Get the payload signed by the oracle, bound to the chain by its genesis hash
+    ///
+    /// @genesis_hash   the hash of genesis block
+    pub fn signing_payload(&self, genesis_hash: &Hash) -> Vec<u8> {
+        (REPORT_CONTEXT, genesis_hash, self).encode()
+    }
+}
+
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
 decl_event!(
@@ -59,6 +117,8 @@ decl_event!(
         PriceReported(AccountId, Price),
         /// Price is changed
         PriceChanged(Price),
+        /// Cashier account is changed from the old account to the new one
+        CashierChanged(AccountId, AccountId),
     }
 );
 
@@ -66,12 +126,14 @@ decl_storage! {
     trait Store for Module<T: Trait> as PriceStorage {
         /// Current price
         CurrentPrice get(current_price): Price;
+        /// The block number at which the current price was last updated by new reports
+        LastUpdated get(last_updated): T::BlockNumber;
+        /// True if price is reported in current block, the current price is updated at the end of block
+        HasNewReports: bool;
         /// Price reporters
         PriceReports get(price_reports): Vec<PriceReport<T::AccountId>>;
         /// Job hash array
         PendingRequests get(pending_requests): Vec<PendingRequest<T::Hash, T::BlockNumber>>;
-        /// The admin account
-        AdminAccount get(admin_account) config(): T::AccountId;
         /// The cashier account
         CashierAccount get(cashier_account) config(): T::AccountId;
     }
@@ -84,13 +146,13 @@ decl_module! {
         const RoundLength: T::BlockNumber = T::RoundLength::get();
         const OracleTimeout: T::BlockNumber = T::OracleTimeout::get();
         const MaxOracleCount: u32 = T::MaxOracleCount::get();
+        const UnsignedPriority: TransactionPriority = T::UnsignedPriority::get();
         /// Request price from oracle
         ///
         /// @origin
         /// @oracle the oracle account
         pub fn request_price(origin, oracle: T::AccountId) -> Result {
-            let sender = ensure_signed(origin)?;
-            ensure!(sender == Self::admin_account(), "Not authorized");
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
             // TODO:
             let meta = vec![0;32];
             let timeout = T::OracleTimeout::get(); 
@@ -104,11 +166,31 @@ decl_module! {
             }
         }
 
+        /// Rotate the cashier account which pays for the price requests
+        ///
+        /// The requests are refunded to the cashier who pays for them, so the cashier can only be
+        /// rotated after all pending requests are fulfilled or cancelled
+        ///
+        /// @origin
+        /// @new_account    the new cashier account
+        #[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+        pub fn set_cashier_account(origin, new_account: T::AccountId) -> Result {
+            T::AdminOrigin::try_origin(origin).map_err(|_| "Not authorized")?;
+            let old_account = Self::cashier_account();
+            ensure!(old_account != new_account, "New value should be different from current value");
+            ensure!(Self::pending_requests().is_empty(), "Pending requests are not settled");
+
+            <CashierAccount<T>>::put(&new_account);
+            Self::deposit_event(RawEvent::CashierChanged(old_account, new_account));
+            Ok(())
+        }
+
         /// Report price
         ///
         /// @origin
         /// @price  current price
         /// @id the request id
+        #[weight = SimpleDispatchInfo::FixedNormal(10_000)]
         pub fn report_price(origin, price: Price, id: T::Hash) -> Result {
             // let who = T::ReportOrigin::ensure_origin(origin)?;
             let who = ensure_signed(origin)?;
@@ -120,20 +202,41 @@ decl_module! {
             Ok(())
         }
 
+        /// Report price without transaction fee, validated by `validate_unsigned`
+        ///
+        /// @origin
+        /// @report the price report
+        /// @_signature the signature of `signing_payload` by the oracle, verified when validated
+        #[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+        pub fn report_price_unsigned(
+            origin,
+            report: UnsignedReport<T::AccountId, T::Hash>,
+            _signature: T::ReportSignature
+        ) -> Result {
+            ensure_none(origin)?;
+            T::OracleMixedIn::on_request_fulfilled(&report.reporter, report.id)?;
+
+            Self::add_price(report.reporter, report.price);
+            Self::remove_pending_request(report.id);
+
+            Ok(())
+        }
+
         /// Callback when a block is finalized
         ///
         /// @n  the block number
         fn on_finalize(block_number: T::BlockNumber) {
             let old_price = Self::current_price();
             let mut prices: Vec<Price> = Self::price_reports().iter().map(|x| x.price).collect();
-            // Update price
-            if prices.len() > 0 {
+            // Update price, the price is only refreshed by new reports
+            if HasNewReports::take() && prices.len() > 0 {
                 let median_price = median(&mut prices);
 
                 if old_price != median_price {
                     CurrentPrice::put(median_price);
                     Self::deposit_event(RawEvent::PriceChanged(median_price));
                 }
+                <LastUpdated<T>>::put(block_number);
             }
             // Reset reports if round ends
             if T::BlockNumber::zero() == block_number % T::RoundLength::get() {
@@ -146,6 +249,18 @@ decl_module! {
     }
 }
 
+/// The types of storage and events
+impl<T: Trait> ModuleTypeMetadata for Module<T> {
+    fn types() -> Vec<TypeMetadata> {
+        vec![
+            PriceReport::<T::AccountId>::type_metadata(),
+            PendingRequest::<T::Hash, T::BlockNumber>::type_metadata(),
+            UnsignedReport::<T::AccountId, T::Hash>::type_metadata(),
+            TypeMetadata::alias("Price", "u128"),
+        ]
+    }
+}
+
 /// Helper functions
 impl<T: Trait> Module<T> {
     /// Add pending request
@@ -224,6 +339,7 @@ impl<T: Trait> Module<T> {
         }
 
         <PriceReports<T>>::put(price_reports);
+        HasNewReports::put(true);
         Self::deposit_event(RawEvent::PriceReported(who, price));
 
         Ok(())
@@ -235,6 +351,71 @@ impl<T: Trait> Module<T> {
     }
 }
 
+impl<T: Trait> ValidateUnsigned for Module<T> {
+    type Call = Call<T>;
+
+    /// Validate the unsigned price report again when it is applied, so the report which became
+    /// stale or duplicate in the transaction pool is not included in the block
+    ///
+    /// @call   the call of report
+    fn pre_dispatch(call: &Self::Call) -> rstd::result::Result<(), ApplyError> {
+        Self::validate_unsigned(call)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Validate the unsigned price report, which is provided once per oracle per job
+    ///
+    /// @call   the call of report
+    fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+        let (report, signature) = match call {
+            Call::report_price_unsigned(report, signature) => (report, signature),
+            _ => return InvalidTransaction::Call.into(),
+        };
+
+        if !T::OracleMixedIn::is_valid(&report.reporter) {
+            return InvalidTransaction::BadProof.into();
+        }
+
+        // The request is removed once it is reported or expired
+        let block_number = Self::block_number();
+        let request = match Self::pending_requests()
+            .into_iter()
+            .find(|x| x.id == report.id && x.expired_at > block_number)
+        {
+            Some(request) => request,
+            None => return InvalidTransaction::Stale.into(),
+        };
+        if !T::OracleMixedIn::is_assigned(&report.reporter, report.id) {
+            return InvalidTransaction::BadProof.into();
+        }
+
+        // Check signature at last, which is expensive
+        let genesis_hash = <system::Module<T>>::block_hash(T::BlockNumber::zero());
+        let payload = report.signing_payload(&genesis_hash);
+        if !signature.verify(&payload[..], &report.reporter) {
+            return InvalidTransaction::BadProof.into();
+        }
+
+        Ok(ValidTransaction {
+            priority: T::UnsignedPriority::get(),
+            requires: vec![],
+            provides: vec![(report.reporter.clone(), report.id).encode()],
+            longevity: (request.expired_at - block_number).saturated_into::<u64>(),
+            propagate: true,
+        })
+    }
+}
+
+impl<T: Trait> PriceProvider<T::BlockNumber> for Module<T> {
+    fn price() -> Option<(Price, T::BlockNumber)> {
+        if !CurrentPrice::exists() {
+            return None;
+        }
+        Some((CurrentPrice::get(), Self::last_updated()))
+    }
+}
+
 /// Calculate median value
 ///
 /// @numbers    the numbers
@@ -9,11 +9,12 @@ mod tests {
     use primitives::{Blake2Hasher, H256};
     use rstd::result;
     use runtime_io::TestExternalities;
-    use sr_primitives::weights::Weight;
+    use sr_primitives::weights::{DispatchClass, GetDispatchInfo, Weight};
     use sr_primitives::Perbill;
+    use sr_primitives::transaction_validity::InvalidTransaction;
     use sr_primitives::{
         testing::Header,
-        traits::{BlakeTwo256, ConvertInto, EnsureOrigin, IdentityLookup, OnFinalize},
+        traits::{BlakeTwo256, ConvertInto, EnsureOrigin, IdentityLookup, Lazy, OnFinalize},
     };
     use support::{assert_err, assert_noop, assert_ok, impl_outer_origin, parameter_types};
 
@@ -78,6 +79,8 @@ mod tests {
         pub const RoundLength: u64 = 100;
         pub const OracleTimeout: u64 = 3;
         pub const MaxOracleCount: u32 = 10;
+        pub const Admin: u64 = 10000;
+        pub const UnsignedPriority: u64 = 1 << 20;
     }
 
     impl Trait for Test {
@@ -85,33 +88,61 @@ mod tests {
         type OracleTimeout = OracleTimeout;
         type MaxOracleCount = MaxOracleCount;
         type OracleMixedIn = Self;
+        type AdminOrigin = system::EnsureSignedBy<Admin, u64>;
         type Event = ();
         type Currency = Balances;
+        type ReportSignature = TestSignature;
+        type ReportSigner = TestSigner;
+        type UnsignedPriority = UnsignedPriority;
         // type ReportOrigin = Origin;
     }
 
-    // Mock implementation
-    impl<T: Trait> OracleMixedIn<T> for Test {
+    // Mock signer identified by the account
+    #[derive(Clone, Eq, PartialEq, Encode, Decode, Debug)]
+    pub struct TestSigner(u64);
+    impl IdentifyAccount for TestSigner {
+        type AccountId = u64;
+        fn into_account(self) -> u64 {
+            self.0
+        }
+    }
+
+    // Mock signature made of the signer and the signed message
+    #[derive(Clone, Eq, PartialEq, Encode, Decode, Debug)]
+    pub struct TestSignature(u64, Vec<u8>);
+    impl Verify for TestSignature {
+        type Signer = TestSigner;
+        fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &u64) -> bool {
+            self.0 == *signer && &self.1[..] == msg.get()
+        }
+    }
+
+    // Mock implementation, in which the jobs are assigned to Django and Eve is not an oracle
+    impl OracleMixedIn<Test> for Test {
         /// Create request
         fn create_request(
-            from: &T::AccountId,
+            from: &u64,
             meta: &Vec<u8>,
-            timeout: T::BlockNumber,
-            oracle: &T::AccountId,
-        ) -> result::Result<T::Hash, &'static str> {
-            Ok(T::Hash::default())
+            timeout: u64,
+            oracle: &u64,
+        ) -> result::Result<H256, &'static str> {
+            Ok(H256::default())
         }
         /// Cancel request
-        fn cancel_request(from: &T::AccountId, id: T::Hash) -> Result {
+        fn cancel_request(from: &u64, id: H256) -> Result {
             Ok(())
         }
         /// Called after request is fulfilled
-        fn on_request_fulfilled(oracle: &T::AccountId, id: T::Hash) -> Result {
+        fn on_request_fulfilled(oracle: &u64, id: H256) -> Result {
             Ok(())
         }
         /// Predicate if one oracle is valid.
-        fn is_valid(who: &T::AccountId) -> bool {
-            true
+        fn is_valid(who: &u64) -> bool {
+            *who != EVE
+        }
+        /// Predicate if the job is assigned to the oracle and not fulfilled yet.
+        fn is_assigned(oracle: &u64, id: H256) -> bool {
+            *oracle == DJANGO
         }
     }
 
@@ -161,7 +192,6 @@ mod tests {
         .unwrap();
         // Add config for oracle
         GenesisConfig::<Test> {
-            admin_account: ADMIN_ACCOUNT,
             cashier_account: CASHIER_ACCOUNT,
         }
         .assimilate_storage(&mut t)
@@ -214,4 +244,188 @@ mod tests {
             assert_eq!(Price::price_reports(), []);
         })
     }
+
+    #[test]
+    fn it_works_for_unsigned_report() {
+        new_test_ext().execute_with(|| {
+            System::set_block_number(1);
+            assert_ok!(Price::request_price(Origin::signed(ADMIN_ACCOUNT), DJANGO));
+            let request = Price::pending_requests()[0];
+            let report = |reporter: u64, id: H256| UnsignedReport {
+                reporter: reporter,
+                price: 5000,
+                id: id,
+            };
+            let genesis_hash = System::block_hash(0);
+            let call = |report: UnsignedReport<u64, H256>, signer: u64| {
+                let signature = TestSignature(signer, report.signing_payload(&genesis_hash));
+                Call::report_price_unsigned(report, signature)
+            };
+
+            // Report is provided once per oracle per job, until the job is expired
+            let valid = Price::validate_unsigned(&call(report(DJANGO, request.id), DJANGO)).unwrap();
+            assert_eq!(valid.priority, 1 << 20);
+            assert_eq!(valid.provides, vec![(DJANGO, request.id).encode()]);
+            assert_eq!(valid.longevity, 3);
+
+            // Report signed for another chain or without the context is invalid
+            let other_chain = report(DJANGO, request.id).signing_payload(&H256::random());
+            assert_eq!(
+                Price::validate_unsigned(&Call::report_price_unsigned(
+                    report(DJANGO, request.id),
+                    TestSignature(DJANGO, other_chain)
+                )),
+                InvalidTransaction::BadProof.into()
+            );
+            assert_eq!(
+                Price::validate_unsigned(&Call::report_price_unsigned(
+                    report(DJANGO, request.id),
+                    TestSignature(DJANGO, report(DJANGO, request.id).encode())
+                )),
+                InvalidTransaction::BadProof.into()
+            );
+
+            // Reporter should be the assigned oracle which signs the report
+            assert_eq!(
+                Price::validate_unsigned(&call(report(EVE, request.id), EVE)),
+                InvalidTransaction::BadProof.into()
+            );
+            assert_eq!(
+                Price::validate_unsigned(&call(report(BOB, request.id), BOB)),
+                InvalidTransaction::BadProof.into()
+            );
+            assert_eq!(
+                Price::validate_unsigned(&call(report(DJANGO, request.id), BOB)),
+                InvalidTransaction::BadProof.into()
+            );
+            // Job should be pending
+            assert_eq!(
+                Price::validate_unsigned(&call(report(DJANGO, H256::random()), DJANGO)),
+                InvalidTransaction::Stale.into()
+            );
+            assert_eq!(
+                Price::validate_unsigned(&Call::request_price(DJANGO)),
+                InvalidTransaction::Call.into()
+            );
+
+            // The reports have the same weight, signed or not
+            let info = call(report(DJANGO, request.id), DJANGO).get_dispatch_info();
+            assert_eq!(info.weight, 10_000);
+            assert_eq!(info.class, DispatchClass::Normal);
+            let info = Call::<Test>::report_price(5000, request.id).get_dispatch_info();
+            assert_eq!(info.weight, 10_000);
+            assert_eq!(info.class, DispatchClass::Normal);
+
+            // Only unsigned origin can report
+            let signature = TestSignature(
+                DJANGO,
+                report(DJANGO, request.id).signing_payload(&genesis_hash),
+            );
+            assert_ok!(Price::pre_dispatch(&call(
+                report(DJANGO, request.id),
+                DJANGO
+            )));
+            assert!(Price::report_price_unsigned(
+                Origin::signed(DJANGO),
+                report(DJANGO, request.id),
+                signature.clone()
+            )
+            .is_err());
+            assert_ok!(Price::report_price_unsigned(
+                Origin::system(system::RawOrigin::None),
+                report(DJANGO, request.id),
+                signature
+            ));
+            assert_eq!(Price::pending_requests(), []);
+            <Price as OnFinalize<u64>>::on_finalize(1);
+            assert_eq!(Price::current_price(), 5000);
+
+            // Duplicate report is stale
+            assert_eq!(
+                Price::validate_unsigned(&call(report(DJANGO, request.id), DJANGO)),
+                InvalidTransaction::Stale.into()
+            );
+            assert_eq!(
+                Price::pre_dispatch(&call(report(DJANGO, request.id), DJANGO)),
+                Err(InvalidTransaction::Stale.into())
+            );
+
+            // Expired job is stale
+            assert_ok!(Price::request_price(Origin::signed(ADMIN_ACCOUNT), DJANGO));
+            let request = Price::pending_requests()[0];
+            System::set_block_number(4);
+            assert_eq!(
+                Price::validate_unsigned(&call(report(DJANGO, request.id), DJANGO)),
+                InvalidTransaction::Stale.into()
+            );
+        })
+    }
+
+    #[test]
+    fn it_works_for_providing_price() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(<Price as PriceProvider<u64>>::price(), None);
+
+            System::set_block_number(3);
+            assert_ok!(Price::report_price(Origin::signed(DJANGO), 5000, H256::random()));
+            // The price is updated at the end of block
+            assert_eq!(<Price as PriceProvider<u64>>::price(), None);
+            <Price as OnFinalize<u64>>::on_finalize(3);
+            assert_eq!(<Price as PriceProvider<u64>>::price(), Some((5000, 3)));
+            // Price is not refreshed without new reports
+            System::set_block_number(4);
+            <Price as OnFinalize<u64>>::on_finalize(4);
+            assert_eq!(<Price as PriceProvider<u64>>::price(), Some((5000, 3)));
+            // The same price reported again is refreshed
+            System::set_block_number(5);
+            assert_ok!(Price::report_price(Origin::signed(DJANGO), 5000, H256::random()));
+            assert_eq!(<Price as PriceProvider<u64>>::price(), Some((5000, 3)));
+            <Price as OnFinalize<u64>>::on_finalize(5);
+            assert_eq!(<Price as PriceProvider<u64>>::price(), Some((5000, 5)));
+        })
+    }
+
+    #[test]
+    fn it_works_for_rotating_cashier() {
+        new_test_ext().execute_with(|| {
+            assert_err!(
+                Price::set_cashier_account(Origin::signed(RAY), DAVE),
+                "Not authorized"
+            );
+            assert_err!(
+                Price::set_cashier_account(Origin::signed(ADMIN_ACCOUNT), CASHIER_ACCOUNT),
+                "New value should be different from current value"
+            );
+            // The pending request is refunded to the old cashier before rotating
+            System::set_block_number(1);
+            assert_ok!(Price::request_price(Origin::signed(ADMIN_ACCOUNT), DJANGO));
+            assert_err!(
+                Price::set_cashier_account(Origin::signed(ADMIN_ACCOUNT), DAVE),
+                "Pending requests are not settled"
+            );
+            <Price as OnFinalize<u64>>::on_finalize(4);
+            assert_eq!(Price::pending_requests(), []);
+            assert_ok!(Price::set_cashier_account(Origin::signed(ADMIN_ACCOUNT), DAVE));
+            assert_eq!(Price::cashier_account(), DAVE);
+            // Rotating the cashier is an operational call
+            let call = Call::<Test>::set_cashier_account(CASHIER_ACCOUNT);
+            assert_eq!(call.get_dispatch_info().class, DispatchClass::Operational);
+        })
+    }
+
+    #[test]
+    fn it_works_for_type_metadata() {
+        use support::metadata::{undescribed_types, ModuleTypeMetadata};
+
+        // The types of storage and events are described
+        assert_eq!(
+            undescribed_types(
+                &Price::types(),
+                &Price::storage_metadata(),
+                Event::<Test>::metadata(),
+                &[]
+            ),
+            Vec::<String>::new()
+        );
+    }
 }
@@ -0,0 +1,178 @@
+//! The exchange adapters fetching the price of the trading pair.
+
+use futures::{future, Future, Stream};
+use hyper::{client::HttpConnector, Body, Client, Request};
+use hyper_rustls::HttpsConnector;
+use pandora_runtime::price::Price;
+use serde_json::Value;
+
+/// The decimals of the price reported by oracles
+pub const PRICE_DECIMALS: usize = 4;
+
+/// The future of fetched price
+pub type PriceFuture = Box<dyn Future<Item = Price, Error = String> + Send>;
+
+/// The adapter of an exchange which provides the price of the trading pair
+pub trait Exchange: Send + Sync {
+    /// The name of exchange
+    fn name(&self) -> &'static str;
+
+    /// Fetch the latest price, scaled by 10^PRICE_DECIMALS
+    fn fetch_price(&self) -> PriceFuture;
+}
+
+/// Parse the decimal price returned by exchanges, e.g. `8123.45`
+///
+/// The digits beyond the precision of oracle price are truncated.
+///
+/// @value  the decimal string
+pub fn parse_price(value: &str) -> Result<Price, String> {
+    let invalid = || format!("Invalid price: {}", value);
+    let mut parts = value.trim().splitn(2, '.');
+    let integer = parts.next().unwrap_or_default();
+    let fraction = parts.next().unwrap_or_default();
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    if !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let mut digits = String::from(integer);
+    digits.extend(fraction.chars().chain(std::iter::repeat('0')).take(PRICE_DECIMALS));
+    digits.parse::<Price>().map_err(|_| invalid())
+}
+
+/// The exchange which queries a JSON ticker over HTTPS
+pub struct HttpExchange {
+    name: &'static str,
+    url: String,
+    field: &'static str,
+    client: Client<HttpsConnector<HttpConnector>, Body>,
+}
+
+impl HttpExchange {
+    /// Create the exchange
+    ///
+    /// @name   the name of exchange
+    /// @url    the url of ticker
+    /// @field  the field of price in the ticker
+    pub fn new(name: &'static str, url: String, field: &'static str) -> Self {
+        HttpExchange {
+            name: name,
+            url: url,
+            field: field,
+            client: Client::builder().build(HttpsConnector::new(1)),
+        }
+    }
+
+    /// The ticker of Binance
+    ///
+    /// @symbol the trading pair, e.g. `BTCUSDT`
+    pub fn binance(symbol: &str) -> Self {
+        Self::new(
+            "binance",
+            format!("https://api.binance.com/api/v3/ticker/price?symbol={}", symbol),
+            "price",
+        )
+    }
+
+    /// The ticker of Coinbase Pro
+    ///
+    /// @symbol the trading pair, e.g. `BTC-USD`
+    pub fn coinbase(symbol: &str) -> Self {
+        Self::new(
+            "coinbase",
+            format!("https://api.pro.coinbase.com/products/{}/ticker", symbol),
+            "price",
+        )
+    }
+
+    /// The ticker of Bitstamp
+    ///
+    /// @symbol the trading pair, e.g. `btcusd`
+    pub fn bitstamp(symbol: &str) -> Self {
+        Self::new(
+            "bitstamp",
+            format!("https://www.bitstamp.net/api/v2/ticker/{}/", symbol),
+            "last",
+        )
+    }
+}
+
+impl Exchange for HttpExchange {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn fetch_price(&self) -> PriceFuture {
+        let request = Request::get(self.url.as_str())
+            // Coinbase rejects the requests without user agent
+            .header("User-Agent", "pandora-reporter")
+            .body(Body::empty());
+        let request = match request {
+            Ok(request) => request,
+            Err(e) => return Box::new(future::err(e.to_string())),
+        };
+
+        let name = self.name;
+        let field = self.field;
+        Box::new(
+            self.client
+                .request(request)
+                .and_then(|response| response.into_body().concat2())
+                .map_err(move |e| format!("Error requesting {}: {}", name, e))
+                .and_then(move |body| {
+                    let ticker: Value = serde_json::from_slice(&body)
+                        .map_err(|e| format!("Error parsing ticker of {}: {}", name, e))?;
+                    match ticker.get(field).and_then(Value::as_str) {
+                        Some(price) => parse_price(price),
+                        None => Err(format!("No price in ticker of {}: {}", name, ticker)),
+                    }
+                }),
+        )
+    }
+}
+
+/// The exchange returning a fixed price, which is used to test against a dev chain
+pub struct MockExchange {
+    price: Price,
+}
+
+impl MockExchange {
+    /// Create the exchange
+    ///
+    /// @price  the price scaled by 10^PRICE_DECIMALS
+    pub fn new(price: Price) -> Self {
+        MockExchange { price: price }
+    }
+}
+
+impl Exchange for MockExchange {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn fetch_price(&self) -> PriceFuture {
+        Box::new(future::ok(self.price))
+    }
+}
+
+/// Create the exchange by name
+///
+/// @name           the name of exchange, one of binance, coinbase, bitstamp and mock
+/// @symbol         the trading pair in the format of exchange, BTC/USD by default
+/// @mock_price     the price returned by the mock exchange
+pub fn from_name(
+    name: &str,
+    symbol: Option<&str>,
+    mock_price: &str,
+) -> Result<Box<dyn Exchange>, String> {
+    Ok(match name {
+        "binance" => Box::new(HttpExchange::binance(symbol.unwrap_or("BTCUSDT"))),
+        "coinbase" => Box::new(HttpExchange::coinbase(symbol.unwrap_or("BTC-USD"))),
+        "bitstamp" => Box::new(HttpExchange::bitstamp(symbol.unwrap_or("btcusd"))),
+        "mock" => Box::new(MockExchange::new(parse_price(mock_price)?)),
+        _ => return Err(format!("Unknown exchange: {}", name)),
+    })
+}
@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+    use crate::exchange::*;
+    use futures::Future;
+
+    #[test]
+    fn it_works_for_parsing_price() {
+        assert_eq!(parse_price("8123.45"), Ok(81_234_500));
+        assert_eq!(parse_price("8123"), Ok(81_230_000));
+        assert_eq!(parse_price("0.00012"), Ok(1));
+        assert_eq!(parse_price(".5"), Ok(5_000));
+        // The digits beyond the precision are truncated
+        assert_eq!(parse_price("8123.456789"), Ok(81_234_567));
+        assert_eq!(parse_price(" 1.5\n"), Ok(15_000));
+    }
+
+    #[test]
+    fn it_fails_for_parsing_invalid_price() {
+        assert!(parse_price("").is_err());
+        assert!(parse_price(".").is_err());
+        assert!(parse_price("-1").is_err());
+        assert!(parse_price("1.2.3").is_err());
+        assert!(parse_price("1e5").is_err());
+    }
+
+    #[test]
+    fn it_works_for_mock_exchange() {
+        let exchange = from_name("mock", None, "9000.5").unwrap();
+        assert_eq!(exchange.name(), "mock");
+        assert_eq!(exchange.fetch_price().wait(), Ok(90_005_000));
+
+        assert_eq!(from_name("binance", None, "0").unwrap().name(), "binance");
+        assert!(from_name("unknown", None, "0").is_err());
+        assert!(from_name("mock", None, "abc").is_err());
+    }
+}
@@ -0,0 +1,44 @@
+//! Loading the oracle key from a keystore directory.
+
+use primitives::{
+    crypto::{KeyTypeId, Protected, Ss58Codec},
+    sr25519, Pair,
+};
+use std::path::PathBuf;
+
+/// The key type of oracle keys, which are inserted by `author_insertKey` or `subkey insert`
+pub const ORACLE_KEY_TYPE: KeyTypeId = KeyTypeId(*b"orcl");
+
+/// Load the oracle key from the keystore
+///
+/// @path       the path of keystore directory
+/// @password   the password of keystore
+/// @public     the SS58 address of oracle, required if there are several oracle keys
+pub fn load_key(
+    path: PathBuf,
+    password: Option<String>,
+    public: Option<&str>,
+) -> Result<sr25519::Pair, String> {
+    let keystore = keystore::Store::open(path, password.map(Protected::from))
+        .map_err(|e| format!("Error opening keystore: {}", e))?;
+    let keystore = keystore.read();
+
+    let public = match public {
+        Some(public) => sr25519::Public::from_ss58check(public)
+            .map_err(|e| format!("Invalid oracle address {}: {:?}", public, e))?,
+        None => {
+            let mut keys = keystore
+                .public_keys_by_type::<sr25519::Public>(ORACLE_KEY_TYPE)
+                .map_err(|e| format!("Error listing oracle keys: {}", e))?;
+            match keys.len() {
+                1 => keys.remove(0),
+                0 => return Err("No oracle key in keystore".into()),
+                _ => return Err("Several oracle keys in keystore, specify one by --oracle".into()),
+            }
+        }
+    };
+
+    keystore
+        .key_pair_by_type::<sr25519::Pair>(&public, ORACLE_KEY_TYPE)
+        .map_err(|e| format!("Error loading oracle key {}: {}", public.to_ss58check(), e))
+}
@@ -0,0 +1,88 @@
+//! # Pandora Reporter
+//!
+//! The daemon of an oracle, which reports the price for each job assigned to the oracle. The
+//! oracle key is loaded from a keystore directory, e.g. inserted by `author_insertKey` with the
+//! key type `orcl`, and the prices are fetched from the exchange chosen by `--exchange`.
+//!
+//! Run with `cargo run -p pandora-reporter -- --keystore-path <PATH> --exchange mock` to report
+//! a fixed price against a dev chain.
+
+mod exchange;
+mod keys;
+mod reporter;
+
+#[cfg(test)]
+mod exchange_test;
+#[cfg(test)]
+mod reporter_test;
+
+use log::error;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// The options of reporter
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "pandora-reporter",
+    about = "Report prices for the jobs assigned to a Pandora oracle"
+)]
+struct Opt {
+    /// The WebSocket url of node
+    #[structopt(long = "url", default_value = "ws://127.0.0.1:9944")]
+    url: String,
+
+    /// The keystore directory containing the oracle key
+    #[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+    keystore_path: PathBuf,
+
+    /// The password of keystore
+    #[structopt(long = "password")]
+    password: Option<String>,
+
+    /// The SS58 address of oracle, required if there are several oracle keys in keystore
+    #[structopt(long = "oracle")]
+    oracle: Option<String>,
+
+    /// The exchange providing the prices, one of binance, coinbase, bitstamp and mock
+    #[structopt(long = "exchange", default_value = "binance")]
+    exchange: String,
+
+    /// The trading pair in the format of exchange, BTC/USD by default
+    #[structopt(long = "symbol")]
+    symbol: Option<String>,
+
+    /// The price reported by the mock exchange
+    #[structopt(long = "mock-price", default_value = "8000")]
+    mock_price: String,
+
+    /// Report prices by unsigned extrinsics, so the oracle pays no transaction fee
+    #[structopt(long = "unsigned")]
+    unsigned: bool,
+}
+
+fn run(opt: Opt) -> Result<(), String> {
+    let pair = keys::load_key(
+        opt.keystore_path,
+        opt.password,
+        opt.oracle.as_ref().map(String::as_str),
+    )?;
+    let exchange = exchange::from_name(
+        &opt.exchange,
+        opt.symbol.as_ref().map(String::as_str),
+        &opt.mock_price,
+    )?;
+
+    let mut runtime =
+        tokio::runtime::Runtime::new().map_err(|e| format!("Error starting runtime: {}", e))?;
+    runtime.block_on(reporter::run(&opt.url, pair, exchange, opt.unsigned))?;
+    Err("Subscription of events is closed".into())
+}
+
+fn main() {
+    env_logger::init();
+
+    if let Err(e) = run(Opt::from_args()) {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+}
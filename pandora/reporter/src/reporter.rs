@@ -0,0 +1,295 @@
+//! The daemon reporting prices for the jobs assigned to the oracle.
+//!
+//! The reporter subscribes to the events of each block, and for every `JobCreated` event
+//! assigned to the oracle, it fetches the price from the exchange and submits `report_price`.
+//! The nonce is tracked locally and fetched again from the node whenever a submission fails.
+//! A job is remembered once its report is submitted, until the job is past the maximum timeout
+//! of oracle jobs, so a failed report is retried if the job is seen again.
+//! In the unsigned mode, the report is signed with the genesis hash as the payload of
+//! `report_price_unsigned`, which needs neither the nonce nor the fee.
+
+use crate::exchange::Exchange;
+use codec::{Decode, Encode};
+use futures::{future, stream, Future, Stream};
+use jsonrpc_core_client::{transports::ws, RpcChannel};
+use log::{error, info, warn};
+use pandora_runtime::{
+    fee, oracle, price, AccountId, BlockNumber, Call, Event, Hash, Header, Index, MaxTimeout,
+    SignedBlock, SignedExtra, UncheckedExtrinsic,
+};
+use parking_lot::Mutex;
+use primitives::{
+    storage::{StorageChangeSet, StorageKey},
+    twox_128, sr25519, Bytes, Pair,
+};
+use rpc_primitives::number::NumberOrHex;
+use sr_primitives::generic::{Era, SignedPayload};
+use std::{collections::HashMap, sync::Arc};
+use substrate_rpc::{author::AuthorClient, chain::ChainClient, state::StateClient};
+use support::traits::Get;
+use system::EventRecord;
+use system_rpc::SystemClient;
+
+/// The future of reporter
+pub type ReporterFuture<T> = Box<dyn Future<Item = T, Error = String> + Send>;
+
+/// Decode the events from the storage changes of `System Events`
+///
+/// @change_set the storage changes of block
+pub fn decode_events(change_set: &StorageChangeSet<Hash>) -> Vec<Event> {
+    change_set
+        .changes
+        .iter()
+        .filter_map(|(_, data)| data.as_ref())
+        .filter_map(|data| {
+            Vec::<EventRecord<Event, Hash>>::decode(&mut &data.0[..])
+                .map_err(|e| warn!("Error decoding events of {}: {:?}", change_set.block, e))
+                .ok()
+        })
+        .flatten()
+        .map(|record| record.event)
+        .collect()
+}
+
+/// Get the jobs assigned to the oracle, with the blocks at which they are created
+///
+/// @events     the events of block
+/// @account    the account of oracle
+pub fn assigned_jobs(events: &[Event], account: &AccountId) -> Vec<(Hash, BlockNumber)> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Event::oracle(oracle::RawEvent::JobCreated(_, who, created_at, id))
+                if who == account =>
+            {
+                Some((*id, *created_at))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Forget the reported jobs which are past the maximum timeout, they are cancelled by the chain
+/// and can not be seen again
+///
+/// @reported   the reported jobs with the blocks at which they are created
+/// @now        the block of the latest job seen
+pub fn prune_reported(reported: &mut HashMap<Hash, BlockNumber>, now: BlockNumber) {
+    let timeout: BlockNumber = MaxTimeout::get();
+    reported.retain(|_, created_at| created_at.saturating_add(timeout) >= now);
+}
+
+/// The reporter of an oracle
+pub struct Reporter {
+    pair: sr25519::Pair,
+    account: AccountId,
+    exchange: Box<dyn Exchange>,
+    author: AuthorClient<Hash, Hash>,
+    system: SystemClient<AccountId, Index>,
+    genesis_hash: Hash,
+    spec_version: u32,
+    /// Report by unsigned extrinsics
+    unsigned: bool,
+    /// The next nonce, None if it should be fetched from the node
+    nonce: Mutex<Option<Index>>,
+    /// The jobs whose reports are submitted, with the blocks at which they are created, since
+    /// the first change set of subscription may be seen twice
+    reported: Mutex<HashMap<Hash, BlockNumber>>,
+}
+
+impl Reporter {
+    /// Sign the `report_price` call as an immortal extrinsic
+    ///
+    /// @price  the price
+    /// @job    the job id
+    /// @nonce  the nonce of oracle
+    pub fn sign(&self, price: price::Price, job: Hash, nonce: Index) -> UncheckedExtrinsic {
+        let call = Call::Price(price::Call::report_price(price, job));
+        let extra: SignedExtra = (
+            system::CheckVersion::new(),
+            system::CheckGenesis::new(),
+            system::CheckEra::from(Era::Immortal),
+            system::CheckNonce::from(nonce),
+            system::CheckWeight::new(),
+            fee::ChargeGameFee::from(0),
+        );
+        let additional_signed = (
+            self.spec_version,
+            self.genesis_hash,
+            self.genesis_hash,
+            (),
+            (),
+            (),
+        );
+        let payload = SignedPayload::from_raw(call, extra, additional_signed);
+        let signature = payload.using_encoded(|p| self.pair.sign(p));
+        let (call, extra, _) = payload.deconstruct();
+
+        UncheckedExtrinsic::new_signed(call, self.account.clone().into(), signature.into(), extra)
+    }
+
+    /// Sign the report as the payload of an unsigned `report_price_unsigned` extrinsic
+    ///
+    /// @price  the price
+    /// @job    the job id
+    pub fn sign_unsigned(&self, price: price::Price, job: Hash) -> UncheckedExtrinsic {
+        let report = price::UnsignedReport {
+            reporter: self.account.clone(),
+            price: price,
+            id: job,
+        };
+        let signature = self.pair.sign(&report.signing_payload(&self.genesis_hash));
+        let call = Call::Price(price::Call::report_price_unsigned(report, signature.into()));
+
+        UncheckedExtrinsic::new_unsigned(call)
+    }
+
+    /// Get the next nonce of oracle, from the node if it is unknown
+    fn next_nonce(&self) -> ReporterFuture<Index> {
+        match *self.nonce.lock() {
+            Some(nonce) => Box::new(future::ok(nonce)),
+            None => Box::new(
+                self.system
+                    .nonce(self.account.clone())
+                    .map_err(|e| format!("Error fetching nonce: {:?}", e)),
+            ),
+        }
+    }
+
+    /// Fetch the price and report it for the job, the jobs are reported one by one
+    ///
+    /// @job    the job id
+    /// @created_at the block at which the job is created
+    fn report(self: Arc<Self>, job: Hash, created_at: BlockNumber) -> ReporterFuture<()> {
+        if self.reported.lock().contains_key(&job) {
+            return Box::new(future::ok(()));
+        }
+
+        let reporter = self.clone();
+        let fetched = self
+            .exchange
+            .fetch_price()
+            .map_err(move |e| format!("Error fetching price for job {}: {}", job, e));
+        let nonce: ReporterFuture<Option<Index>> = if self.unsigned {
+            Box::new(future::ok(None))
+        } else {
+            Box::new(self.next_nonce().map(Some))
+        };
+        let submitted = fetched
+            .join(nonce)
+            .and_then(move |(price, nonce)| {
+                let extrinsic = match nonce {
+                    Some(nonce) => reporter.sign(price, job, nonce),
+                    None => reporter.sign_unsigned(price, job),
+                };
+                reporter
+                    .author
+                    .submit_extrinsic(Bytes(extrinsic.encode()))
+                    .then(move |result| match result {
+                        Ok(hash) => {
+                            if let Some(nonce) = nonce {
+                                *reporter.nonce.lock() = Some(nonce + 1);
+                            }
+                            reporter.reported.lock().insert(job, created_at);
+                            info!(
+                                "Reported price {} for job {} by extrinsic {}",
+                                price, job, hash
+                            );
+                            Ok(())
+                        }
+                        Err(e) => {
+                            // The nonce may be stale, fetch it again for the next job
+                            *reporter.nonce.lock() = None;
+                            Err(format!("Error submitting report for job {}: {:?}", job, e))
+                        }
+                    })
+            });
+
+        Box::new(submitted.or_else(|e| {
+            error!("{}", e);
+            Ok::<(), String>(())
+        }))
+    }
+
+    /// Report the jobs created in the block
+    ///
+    /// @change_set the storage changes of `System Events`
+    fn handle(self: Arc<Self>, change_set: StorageChangeSet<Hash>) -> ReporterFuture<()> {
+        let jobs = assigned_jobs(&decode_events(&change_set), &self.account);
+        if let Some(now) = jobs.iter().map(|(_, created_at)| *created_at).max() {
+            info!("{} jobs assigned in block {}", jobs.len(), change_set.block);
+            prune_reported(&mut self.reported.lock(), now);
+        }
+        Box::new(
+            stream::iter_ok::<_, String>(jobs)
+                .for_each(move |(job, created_at)| self.clone().report(job, created_at)),
+        )
+    }
+}
+
+/// Connect to the node and report the prices until the subscription ends
+///
+/// @url        the WebSocket url of node
+/// @pair       the key of oracle
+/// @exchange   the exchange providing the prices
+/// @unsigned   true if the prices are reported by unsigned extrinsics
+pub fn run(
+    url: &str,
+    pair: sr25519::Pair,
+    exchange: Box<dyn Exchange>,
+    unsigned: bool,
+) -> ReporterFuture<()> {
+    let url = match url::Url::parse(url) {
+        Ok(url) => url,
+        Err(e) => return Box::new(future::err(format!("Invalid url {}: {}", url, e))),
+    };
+
+    let connected = ws::connect::<RpcChannel>(&url)
+        .map_err(|e| format!("Error connecting to node: {:?}", e));
+    Box::new(connected.and_then(move |channel| {
+        let state = StateClient::<Hash>::from(channel.clone());
+        let chain = ChainClient::<BlockNumber, Hash, Header, SignedBlock>::from(channel.clone());
+        let author = AuthorClient::<Hash, Hash>::from(channel.clone());
+        let system = SystemClient::<AccountId, Index>::from(channel);
+
+        chain
+            .block_hash(Some(NumberOrHex::Number(0)))
+            .join(state.runtime_version(None))
+            .map_err(|e| format!("Error querying chain: {:?}", e))
+            .and_then(|(genesis_hash, version)| {
+                genesis_hash
+                    .map(|hash| (hash, version.spec_version))
+                    .ok_or_else(|| "No genesis block".to_string())
+            })
+            .and_then(move |(genesis_hash, spec_version)| {
+                let account: AccountId = pair.public().into();
+                info!(
+                    "Reporting prices from {} as oracle {}",
+                    exchange.name(),
+                    account
+                );
+                let reporter = Arc::new(Reporter {
+                    pair: pair,
+                    account: account,
+                    exchange: exchange,
+                    author: author,
+                    system: system,
+                    genesis_hash: genesis_hash,
+                    spec_version: spec_version,
+                    unsigned: unsigned,
+                    nonce: Mutex::new(None),
+                    reported: Mutex::new(HashMap::new()),
+                });
+
+                let events_key = StorageKey(twox_128(b"System Events").to_vec());
+                state
+                    .subscribe_storage(Some(vec![events_key]))
+                    .map_err(|e| format!("Error subscribing events: {:?}", e))
+                    .and_then(move |changes| {
+                        changes
+                            .map_err(|e| format!("Error receiving events: {:?}", e))
+                            .for_each(move |change_set| reporter.clone().handle(change_set))
+                    })
+            })
+    }))
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use crate::reporter::*;
+    use codec::Encode;
+    use pandora_runtime::{oracle, price, AccountId, Event, Hash, MaxTimeout};
+    use primitives::storage::{StorageChangeSet, StorageData, StorageKey};
+    use std::collections::HashMap;
+    use support::traits::Get;
+    use system::{EventRecord, Phase};
+
+    fn account(seed: u8) -> AccountId {
+        [seed; 32].into()
+    }
+
+    fn job_created(oracle: u8, id: Hash) -> Event {
+        Event::oracle(oracle::RawEvent::JobCreated(account(0), account(oracle), 1, id))
+    }
+
+    #[test]
+    fn it_works_for_assigned_jobs() {
+        let events = vec![
+            job_created(1, Hash::repeat_byte(1)),
+            Event::price(price::RawEvent::PriceChanged(10_000)),
+            job_created(2, Hash::repeat_byte(2)),
+            job_created(1, Hash::repeat_byte(3)),
+        ];
+
+        assert_eq!(
+            assigned_jobs(&events, &account(1)),
+            [(Hash::repeat_byte(1), 1), (Hash::repeat_byte(3), 1)]
+        );
+        assert_eq!(
+            assigned_jobs(&events, &account(2)),
+            [(Hash::repeat_byte(2), 1)]
+        );
+        assert!(assigned_jobs(&events, &account(3)).is_empty());
+    }
+
+    #[test]
+    fn it_works_for_pruning_reported_jobs() {
+        let mut reported = HashMap::new();
+        reported.insert(Hash::repeat_byte(1), 1);
+        reported.insert(Hash::repeat_byte(2), 5);
+        // The jobs are kept until they are past the maximum timeout
+        prune_reported(&mut reported, 1 + MaxTimeout::get());
+        assert_eq!(reported.len(), 2);
+        prune_reported(&mut reported, 2 + MaxTimeout::get());
+        assert_eq!(reported.len(), 1);
+        assert!(reported.contains_key(&Hash::repeat_byte(2)));
+    }
+
+    #[test]
+    fn it_works_for_decoding_events() {
+        let records = vec![EventRecord {
+            phase: Phase::ApplyExtrinsic(1),
+            event: job_created(1, Hash::repeat_byte(1)),
+            topics: vec![],
+        }];
+        let change_set = StorageChangeSet {
+            block: Hash::repeat_byte(9),
+            changes: vec![
+                (StorageKey(vec![1]), Some(StorageData(records.encode()))),
+                (StorageKey(vec![2]), None),
+                (StorageKey(vec![3]), Some(StorageData(vec![0xff]))),
+            ],
+        };
+
+        assert_eq!(
+            decode_events(&change_set),
+            [job_created(1, Hash::repeat_byte(1))]
+        );
+    }
+}
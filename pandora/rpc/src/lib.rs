@@ -0,0 +1,49 @@
+//! A collection of pandora-specific RPC methods.
+//!
+//! The standard system and transaction payment RPCs are composed with the RPCs of the game,
+//! oracle and price modules, so the full and light services register the same extensions.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+
+use pandora_runtime::{
+    opaque::Block, AccountId, Balance, BlockNumber, Hash, Index, UncheckedExtrinsic,
+};
+use sr_primitives::traits::ProvideRuntimeApi;
+use transaction_pool::txpool::{ChainApi, Pool};
+
+/// Instantiate all RPC extensions.
+pub fn create<C, P, M>(client: Arc<C>, pool: Arc<Pool<P>>) -> jsonrpc_core::IoHandler<M>
+where
+    C: ProvideRuntimeApi,
+    C: client::blockchain::HeaderBackend<Block>,
+    C: Send + Sync + 'static,
+    C::Api: srml_system_rpc::AccountNonceApi<Block, AccountId, Index>,
+    C::Api: srml_transaction_payment_rpc::TransactionPaymentRuntimeApi<
+        Block,
+        Balance,
+        UncheckedExtrinsic,
+    >,
+    C::Api: pandora_module_rpc::PandoraRuntimeApi<Block, AccountId, Hash, Balance, BlockNumber>,
+    C::Api: oracle_module_rpc::OracleRuntimeApi<Block, AccountId, Balance>,
+    C::Api: price_module_rpc::PriceRuntimeApi<Block, BlockNumber>,
+    P: ChainApi + Sync + Send + 'static,
+    M: jsonrpc_core::Metadata + Default,
+{
+    use oracle_module_rpc::{Oracle, OracleApi};
+    use pandora_module_rpc::{Pandora, PandoraApi};
+    use price_module_rpc::{Price, PriceApi};
+    use srml_system_rpc::{System, SystemApi};
+    use srml_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+
+    let mut io = jsonrpc_core::IoHandler::default();
+    io.extend_with(SystemApi::to_delegate(System::new(client.clone(), pool)));
+    io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(
+        client.clone(),
+    )));
+    io.extend_with(PandoraApi::to_delegate(Pandora::new(client.clone())));
+    io.extend_with(OracleApi::to_delegate(Oracle::new(client.clone())));
+    io.extend_with(PriceApi::to_delegate(Price::new(client)));
+    io
+}
@@ -0,0 +1,148 @@
+//! The transaction fee policy of the game.
+//!
+//! The fee of a game move is paid by the game when the move has no tip and can succeed: out of
+//! the pending value of an active and unlisted dbox of the player, or subsidized by the operator
+//! within the rate limit of the player, see `pandora::Module::pay_fee`. Otherwise the fee is
+//! charged from the signer as usual.
+//!
+//! The payer is decided again when the move is dispatched, so a move validated as paid by the
+//! game is charged from the signer if the game can no longer pay, e.g. the subsidies of the
+//! player are used up by the earlier moves in the same block. The move is invalid only if the
+//! signer can not pay either.
+
+use crate::{
+    AccountId, Balance, Call, MaximumBlockWeight, Pandora, Runtime, TransactionBaseFee,
+    TransactionByteFee, TransactionPayment,
+};
+use codec::{Decode, Encode};
+use sr_primitives::traits::{Convert, SaturatedConversion, SignedExtension, Zero};
+use sr_primitives::transaction_validity::{
+    TransactionPriority, TransactionValidity, TransactionValidityError, ValidTransaction,
+};
+use sr_primitives::weights::DispatchInfo;
+use sr_primitives::ApplyError;
+use support::traits::{Contains, Get};
+use transaction_payment::ChargeTransactionPayment;
+
+/// The game moves of which the fees are paid by the game
+pub struct GameMoves;
+
+impl Contains<Call> for GameMoves {
+    fn contains(call: &Call) -> bool {
+        match call {
+            Call::Pandora(pandora::Call::create_dbox(..))
+            | Call::Pandora(pandora::Call::create_dbox_with_invitor(..))
+            | Call::Pandora(pandora::Call::upgrade_dbox(..))
+            | Call::Pandora(pandora::Call::upgrade_dbox_by_index(..))
+            | Call::Pandora(pandora::Call::open_dbox(..))
+            | Call::Pandora(pandora::Call::open_dbox_by_index(..)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Charge the transaction fee like `ChargeTransactionPayment`, with the same encoding, except
+/// that the fees of `GameMoves` are paid by the game if possible
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeGameFee(#[codec(compact)] Balance);
+
+impl ChargeGameFee {
+    /// Create the extension with the tip
+    ///
+    /// @tip    the tip paid by the signer
+    pub fn from(tip: Balance) -> Self {
+        ChargeGameFee(tip)
+    }
+
+    /// Compute the fee like `ChargeTransactionPayment` without tip, from the length of the
+    /// extrinsic and its weight capped at the maximum block weight, adjusted by the multiplier
+    ///
+    /// @info   the dispatch info of the call
+    /// @len    the length of the extrinsic
+    fn compute_fee(info: DispatchInfo, len: usize) -> Balance {
+        let len_fee = if info.pay_length_fee() {
+            let per_byte = TransactionByteFee::get();
+            TransactionBaseFee::get().saturating_add(per_byte.saturating_mul(len as Balance))
+        } else {
+            Zero::zero()
+        };
+        let weight = info.weight.min(MaximumBlockWeight::get());
+        let weight_fee = <Runtime as transaction_payment::Trait>::WeightToFee::convert(weight);
+
+        TransactionPayment::next_fee_multiplier()
+            .saturated_multiply_accumulate(len_fee.saturating_add(weight_fee))
+    }
+
+    /// Get the game move of which the fee may be paid by the game, the tip is always paid by
+    /// the signer
+    ///
+    /// @call   the call
+    fn game_move<'a>(&self, call: &'a Call) -> Option<&'a pandora::Call<Runtime>> {
+        match call {
+            Call::Pandora(game_move) if self.0.is_zero() && GameMoves::contains(call) => {
+                Some(game_move)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl rstd::fmt::Debug for ChargeGameFee {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut rstd::fmt::Formatter) -> rstd::fmt::Result {
+        write!(f, "ChargeGameFee<{:?}>", self.0)
+    }
+    #[cfg(not(feature = "std"))]
+    fn fmt(&self, _: &mut rstd::fmt::Formatter) -> rstd::fmt::Result {
+        Ok(())
+    }
+}
+
+impl SignedExtension for ChargeGameFee {
+    type AccountId = AccountId;
+    type Call = Call;
+    type AdditionalSigned = ();
+    type Pre = ();
+
+    fn additional_signed(&self) -> rstd::result::Result<(), TransactionValidityError> {
+        Ok(())
+    }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: DispatchInfo,
+        len: usize,
+    ) -> TransactionValidity {
+        // Only check the payment here, the fee is paid in `pre_dispatch`
+        if let Some(game_move) = self.game_move(call) {
+            let fee = Self::compute_fee(info, len);
+            if Pandora::can_pay_fee(who, game_move, fee).is_ok() {
+                let mut r = ValidTransaction::default();
+                r.priority = fee.saturated_into::<TransactionPriority>();
+                return Ok(r);
+            }
+        }
+
+        ChargeTransactionPayment::<Runtime>::from(self.0).validate(who, call, info, len)
+    }
+
+    fn pre_dispatch(
+        self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: DispatchInfo,
+        len: usize,
+    ) -> rstd::result::Result<(), ApplyError> {
+        if let Some(game_move) = self.game_move(call) {
+            if Pandora::pay_fee(who, game_move, Self::compute_fee(info, len)).is_ok() {
+                return Ok(());
+            }
+        }
+
+        // Fall back to the signer, which fails with `InvalidTransaction::Payment` if the signer
+        // can not pay the fee either
+        ChargeTransactionPayment::<Runtime>::from(self.0).pre_dispatch(who, call, info, len)
+    }
+}
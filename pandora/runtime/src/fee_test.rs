@@ -0,0 +1,115 @@
+/// tests for the transaction fee policy
+#[cfg(test)]
+mod tests {
+    use crate::fee::ChargeGameFee;
+    use crate::*;
+
+    use pandora::Status;
+    use runtime_io::TestExternalities;
+    use sr_primitives::traits::SignedExtension;
+    use sr_primitives::transaction_validity::InvalidTransaction;
+    use sr_primitives::weights::GetDispatchInfo;
+    use sr_primitives::ApplyError;
+    use support::traits::{Currency, Get};
+
+    /// The free balance of each endowed account
+    const ENDOWMENT: Balance = 1 << 60;
+    /// The length of the extrinsics
+    const LEN: usize = 100;
+
+    /// Get a test account
+    ///
+    /// @seed   the byte repeated in the account id
+    fn account(seed: u8) -> AccountId {
+        AccountId::from([seed; 32])
+    }
+
+    fn assert_ok(result: Result<(), ApplyError>) {
+        assert_eq!(result, Ok(()));
+    }
+
+    fn new_test_ext() -> TestExternalities {
+        GenesisConfig {
+            system: Some(SystemConfig {
+                code: vec![],
+                changes_trie_config: Default::default(),
+            }),
+            aura: None,
+            grandpa: None,
+            indices: None,
+            balances: Some(BalancesConfig {
+                balances: vec![
+                    (account(0), ENDOWMENT),
+                    (account(5), ENDOWMENT),
+                    (account(10), ENDOWMENT),
+                ],
+                vesting: vec![],
+            }),
+            sudo: None,
+            pandora: Some(PandoraConfig {
+                cashier_account: account(0),
+                reserve_account: account(1),
+                pool_account: account(2),
+                last_player_account: account(3),
+                team_account: account(4),
+                operator_account: account(5),
+                dbox_unit_price: Some(1_000),
+                status: Some(Status::Running),
+                max_active_dboxes_count: None,
+                settlement_weight_ratio: None,
+            }),
+            oracle: None,
+            price: None,
+        }
+        .build_storage()
+        .expect("Genesis config is valid")
+        .into()
+    }
+
+    #[test]
+    fn it_works_for_charging_game_fee() {
+        new_test_ext().execute_with(|| {
+            System::set_block_number(1);
+            let player = account(10);
+            let operator = account(5);
+            let call = Call::Pandora(pandora::Call::create_dbox());
+            let info = call.get_dispatch_info();
+            let charge = || ChargeGameFee::from(0);
+
+            // The fees of a new player are subsidized
+            let operator_balance = Balances::free_balance(&operator);
+            assert!(charge().validate(&player, &call, info, LEN).is_ok());
+            assert_eq!(Balances::free_balance(&operator), operator_balance);
+            assert_ok(charge().pre_dispatch(&player, &call, info, LEN));
+            let fee = operator_balance - Balances::free_balance(&operator);
+            assert!(fee > 0);
+            assert_eq!(Balances::free_balance(&player), ENDOWMENT);
+
+            let max_subsidies = <Runtime as pandora::Trait>::MaxSubsidies::get();
+            for _ in 2..max_subsidies {
+                assert_ok(charge().pre_dispatch(&player, &call, info, LEN));
+            }
+            assert_eq!(Pandora::subsidy_of(&player), (1, max_subsidies - 1));
+
+            // Two moves validated in one block exceed the subsidies, the latter is charged from
+            // the signer
+            assert!(charge().validate(&player, &call, info, LEN).is_ok());
+            assert!(charge().validate(&player, &call, info, LEN).is_ok());
+            let operator_balance = Balances::free_balance(&operator);
+            assert_ok(charge().pre_dispatch(&player, &call, info, LEN));
+            assert_eq!(Balances::free_balance(&operator), operator_balance - fee);
+            assert_eq!(Balances::free_balance(&player), ENDOWMENT);
+            assert_ok(charge().pre_dispatch(&player, &call, info, LEN));
+            assert_eq!(Balances::free_balance(&operator), operator_balance - fee);
+            assert_eq!(Balances::free_balance(&player), ENDOWMENT - fee);
+            assert_eq!(Pandora::subsidy_of(&player), (1, max_subsidies));
+
+            // The move is invalid if neither the game nor the signer can pay
+            let poor = account(11);
+            assert_eq!(
+                charge().pre_dispatch(&poor, &call, info, LEN),
+                Err(ApplyError::Validity(InvalidTransaction::Payment.into()))
+            );
+        })
+    }
+}
@@ -18,8 +18,10 @@ use sr_primitives::traits::{
     BlakeTwo256, Block as BlockT, ConvertInto, IdentifyAccount, NumberFor, StaticLookup, Verify,
 };
 use sr_primitives::weights::Weight;
+use transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
 use sr_primitives::{
-    create_runtime_str, generic, impl_opaque_keys, transaction_validity::TransactionValidity,
+    create_runtime_str, generic, impl_opaque_keys,
+    transaction_validity::{TransactionPriority, TransactionValidity},
     ApplyResult, MultiSignature,
 };
 #[cfg(feature = "std")]
@@ -60,6 +62,9 @@ pub type Hash = primitives::H256;
 /// Digest item type.
 pub type DigestItem = generic::DigestItem<Hash>;
 
+pub mod fee;
+mod fee_test;
+
 pub use oracle;
 pub use pandora;
 pub use price;
@@ -93,7 +98,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("node-pandora"),
     impl_name: create_runtime_str!("node-pandora"),
     authoring_version: 1,
-    spec_version: 1,
+    spec_version: 3,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
 };
@@ -232,32 +237,52 @@ impl sudo::Trait for Runtime {
 parameter_types! {
     pub const ExpirationValue: u32 = 60; // 1 minute
     // pub const ExpirationValue: u32 = 12 * 3600; // 12 hours
+    pub const BlockTime: u32 = (MILLISECS_PER_BLOCK / 1000) as u32;
     pub const MaxLatestValue: u64 = 100;
     pub const MinUnitPrice: Balance = 0;
-    pub const MaxUnitPrice: Balance = 10_000_000_000;
+    // 1_000 tokens, so the fiat-derived price is only clamped for extreme oracle prices
+    pub const MaxUnitPrice: Balance = 1_000 * 1_000_000_000_000;
+    pub const MaxPriceAge: BlockNumber = 10 * MINUTES;
+    pub const TokenUnit: Balance = 1_000_000_000_000;
+    pub const MaxRounds: u64 = 1_000;
+    pub const MigrationBatchSize: u64 = 100;
     pub const DboxRatio: u32 = 35;
     pub const ReserveRatio: u32 = 35;
     pub const PoolRatio: u32 = 10;
     pub const LastPlayerRatio: u32 = 5;
     pub const TeamRatio: u32 = 5;
     pub const OperatorRatio: u32 = 5;
-    pub const InvitorRatio: u32 = 5;
+    pub const InvitorRatios: Vec<u32> = vec![3, 1, 1];
+    pub const RoyaltyRatio: u32 = 5;
+    pub const SubsidyPeriod: BlockNumber = HOURS;
+    pub const MaxSubsidies: u32 = 10;
 }
 
 /// Used for the module pandora in `./pandora.rs`
 impl pandora::Trait for Runtime {
     type Event = Event;
     type Expiration = ExpirationValue;
+    type BlockTime = BlockTime;
     type MaxLatest = MaxLatestValue;
     type MinUnitPrice = MinUnitPrice;
     type MaxUnitPrice = MaxUnitPrice;
+    type PriceProvider = Price;
+    type MaxPriceAge = MaxPriceAge;
+    type TokenUnit = TokenUnit;
+    type Randomness = RandomnessCollectiveFlip;
+    type MaxRounds = MaxRounds;
+    type MigrationBatchSize = MigrationBatchSize;
     type DboxRatio = DboxRatio;
     type ReserveRatio = ReserveRatio;
     type PoolRatio = PoolRatio;
     type LastPlayerRatio = LastPlayerRatio;
     type TeamRatio = TeamRatio;
     type OperatorRatio = OperatorRatio;
-    type InvitorRatio = InvitorRatio;
+    type InvitorRatios = InvitorRatios;
+    type RoyaltyRatio = RoyaltyRatio;
+    type SubsidyPeriod = SubsidyPeriod;
+    type MaxSubsidies = MaxSubsidies;
+    type AdminOrigin = system::EnsureRoot<AccountId>;
     type Currency = Balances;
 }
 
@@ -279,6 +304,7 @@ impl oracle::Trait for Runtime {
     type MinStaking = MinStaking;
 
     // type MaliciousSlashOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, OracleCollective>;
+    type AdminOrigin = system::EnsureRoot<AccountId>;
     type Count = Count;
     type ReportInteval = ReportInterval;
     type ElectionEra = ElectionEra;
@@ -293,6 +319,7 @@ parameter_types! {
     pub const RoundLength: BlockNumber = 100;
     pub const OracleTimeout: BlockNumber = 30; // TODO: use small value
     pub const MaxOracleCount: u32 = 10;
+    pub const UnsignedPriority: TransactionPriority = 1 << 20;
 }
 
 impl price::Trait for Runtime {
@@ -300,8 +327,12 @@ impl price::Trait for Runtime {
     type OracleTimeout = OracleTimeout;
     type MaxOracleCount = MaxOracleCount;
     type OracleMixedIn = Oracle;
+    type AdminOrigin = system::EnsureRoot<AccountId>;
     type Event = Event;
     type Currency = Balances;
+    type ReportSignature = Signature;
+    type ReportSigner = <Signature as Verify>::Signer;
+    type UnsignedPriority = UnsignedPriority;
 }
 
 construct_runtime!(
@@ -320,11 +351,18 @@ construct_runtime!(
 		Sudo: sudo,
         Pandora: pandora::{Module, Call, Storage, Event<T>, Config<T>},
 		Oracle: oracle::{Module, Call, Storage, Event<T>, Config<T>},
-		Price: price::{Module, Call, Storage, Event<T>, Config<T>},
+		Price: price::{Module, Call, Storage, Event<T>, Config<T>, ValidateUnsigned},
 		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
 	}
 );
 
+support::impl_type_registry!(
+    for Runtime with modules
+        pandora::Module as Pandora,
+        oracle::Module as Oracle,
+        price::Module as Price,
+);
+
 /// The address format for describing accounts.
 pub type Address = <Indices as StaticLookup>::Source;
 /// Block header type as expected by this runtime.
@@ -342,7 +380,7 @@ pub type SignedExtra = (
     system::CheckEra<Runtime>,
     system::CheckNonce<Runtime>,
     system::CheckWeight<Runtime>,
-    transaction_payment::ChargeTransactionPayment<Runtime>,
+    fee::ChargeGameFee,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -431,4 +469,65 @@ impl_runtime_apis! {
             Grandpa::grandpa_authorities()
         }
     }
+
+    impl pandora_rpc_runtime_api::PandoraApi<Block, AccountId, Hash, Balance, BlockNumber> for Runtime {
+        fn player_dboxes(who: AccountId) -> Vec<pandora::DboxInfo<Hash, Balance>> {
+            Pandora::get_player_dboxes(&who)
+        }
+
+        fn round_info() -> pandora::RoundInfo<AccountId, Balance> {
+            Pandora::get_round_info()
+        }
+
+        fn player_info(who: AccountId) -> pandora::Player<Balance> {
+            Pandora::player(who)
+        }
+
+        fn open_payout(dbox_id: Hash) -> Option<Balance> {
+            Pandora::get_open_payout(dbox_id)
+        }
+
+        fn round_summaries(
+            from: u64,
+            count: u64,
+        ) -> Vec<(u64, pandora::RoundSummary<AccountId, Balance, BlockNumber>)> {
+            Pandora::get_round_summaries(from, count)
+        }
+    }
+
+    impl oracle_rpc_runtime_api::OracleApi<Block, AccountId, Balance> for Runtime {
+        fn oracles() -> Vec<AccountId> {
+            Oracle::oracles()
+        }
+
+        fn candidates() -> Vec<AccountId> {
+            Oracle::candidates()
+        }
+
+        fn staked(who: AccountId) -> Balance {
+            Oracle::get_staked(&who)
+        }
+    }
+
+    impl price_rpc_runtime_api::PriceApi<Block, BlockNumber> for Runtime {
+        fn current_price() -> Option<(price::Price, BlockNumber)> {
+            <Price as price::PriceProvider<BlockNumber>>::price()
+        }
+    }
+
+    impl system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
+        fn account_nonce(account: AccountId) -> Index {
+            System::account_nonce(account)
+        }
+    }
+
+    impl transaction_payment_rpc_runtime_api::TransactionPaymentApi<
+        Block,
+        Balance,
+        UncheckedExtrinsic,
+    > for Runtime {
+        fn query_info(uxt: UncheckedExtrinsic, len: u32) -> RuntimeDispatchInfo<Balance> {
+            TransactionPayment::query_info(uxt, len)
+        }
+    }
 }
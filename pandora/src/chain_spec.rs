@@ -1,16 +1,26 @@
 use aura_primitives::sr25519::AuthorityId as AuraId;
 use grandpa_primitives::AuthorityId as GrandpaId;
 use pandora_runtime::{
-    AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, IndicesConfig,
-    OracleConfig, PandoraConfig, PriceConfig, Signature, SudoConfig, SystemConfig, WASM_BINARY,
+    pandora::Status, AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, IndicesConfig,
+    Balance, OracleConfig, PandoraConfig, PriceConfig, Signature, SudoConfig, SystemConfig,
+    WASM_BINARY,
 };
-use primitives::{sr25519, Pair, Public};
+use primitives::{crypto::Ss58Codec, ed25519, sr25519, Pair, Public};
+use serde::Deserialize;
 use sr_primitives::traits::{IdentifyAccount, Verify};
+use std::{fs, path::Path};
 use substrate_service;
 
 // Note this is the URL for the telemetry server
 //const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// The unit price of dbox of the game running at genesis of dev chains
+pub const DEV_DBOX_UNIT_PRICE: Balance = 1_000_000_000;
+/// The maximum active dboxes of the game running at genesis of dev chains
+const DEV_MAX_ACTIVE_DBOXES_COUNT: u64 = 1_000;
+/// The funds staked by each oracle elected at genesis of dev chains
+const DEV_ORACLE_STAKE: Balance = 10_000_000_000;
+
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
 pub type ChainSpec = substrate_service::ChainSpec<GenesisConfig>;
 
@@ -23,8 +33,27 @@ pub enum Alternative {
     Development,
     /// Whatever the current runtime is, with simple Alice/Bob auths.
     LocalTestnet,
+    /// Whatever the current runtime is, with the non-dev keys in `res/staging.json`.
+    StagingTestnet,
 }
 
+/// The seeds of the well-known dev keys
+const DEV_SEEDS: [&str; 8] = [
+    "Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie", "Nicole", "Ray",
+];
+
+/// The derivations of the well-known dev keys
+const DEV_DERIVATIONS: [&str; 8] = [
+    "",
+    "//stash",
+    "//Cashier",
+    "//Reserve",
+    "//Pool",
+    "//LastPlayer",
+    "//Team",
+    "//Operator",
+];
+
 /// Helper function to generate a crypto pair from seed
 pub fn get_from_seed<TPublic: Public>(seed: &str) -> <TPublic::Pair as Pair>::Public {
     TPublic::Pair::from_string(&format!("//{}", seed), None)
@@ -58,6 +87,7 @@ impl Alternative {
                     testnet_genesis(
                         vec![get_authority_keys_from_seed("Alice")],
                         get_account_id_from_seed::<sr25519::Public>("Alice"),
+                        SystemAccounts::dev(),
                         vec![
                             get_account_id_from_seed::<sr25519::Public>("Alice"),
                             get_account_id_from_seed::<sr25519::Public>("Bob"),
@@ -71,6 +101,7 @@ impl Alternative {
                             get_account_id_from_seed::<sr25519::Public>("Ray"),
                         ],
                         true,
+                        true,
                     )
                 },
                 vec![],
@@ -89,6 +120,7 @@ impl Alternative {
                             get_authority_keys_from_seed("Bob"),
                         ],
                         get_account_id_from_seed::<sr25519::Public>("Alice"),
+                        SystemAccounts::dev(),
                         vec![
                             get_account_id_from_seed::<sr25519::Public>("Alice"),
                             get_account_id_from_seed::<sr25519::Public>("Bob"),
@@ -105,6 +137,7 @@ impl Alternative {
                             get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
                         ],
                         true,
+                        true,
                     )
                 },
                 vec![],
@@ -113,6 +146,12 @@ impl Alternative {
                 None,
                 None,
             ),
+            // The staging keys are checked like the keys of `generate-spec`
+            Alternative::StagingTestnet => {
+                GenesisKeys::from_json_bytes(include_bytes!("../res/staging.json"))
+                    .and_then(GenesisKeys::build_spec)
+                    .map_err(|e| format!("Invalid staging keys in res/staging.json: {}", e))?
+            }
         })
     }
 
@@ -120,17 +159,230 @@ impl Alternative {
         match s {
             "dev" => Some(Alternative::Development),
             "" | "local" => Some(Alternative::LocalTestnet),
+            "staging" => Some(Alternative::StagingTestnet),
             _ => None,
         }
     }
 }
 
+/// The system accounts of pandora
+#[derive(Clone, Debug)]
+pub struct SystemAccounts {
+    /// The cashier account, which is shared by oracle and price
+    pub cashier: AccountId,
+    /// The reserve account
+    pub reserve: AccountId,
+    /// The pool account
+    pub pool: AccountId,
+    /// The last player account
+    pub last_player: AccountId,
+    /// The team account
+    pub team: AccountId,
+    /// The operator account
+    pub operator: AccountId,
+}
+
+impl SystemAccounts {
+    /// The system accounts derived from the dev seed of Alice
+    pub fn dev() -> Self {
+        SystemAccounts {
+            cashier: get_account_id_from_seed::<sr25519::Public>("Alice//Cashier"),
+            reserve: get_account_id_from_seed::<sr25519::Public>("Alice//Reserve"),
+            pool: get_account_id_from_seed::<sr25519::Public>("Alice//Pool"),
+            last_player: get_account_id_from_seed::<sr25519::Public>("Alice//LastPlayer"),
+            team: get_account_id_from_seed::<sr25519::Public>("Alice//Team"),
+            operator: get_account_id_from_seed::<sr25519::Public>("Alice//Operator"),
+        }
+    }
+}
+
+/// The keys of an authority, in SS58 or hex of public key
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorityKeys {
+    /// The sr25519 key of aura
+    pub aura: String,
+    /// The ed25519 key of grandpa
+    pub grandpa: String,
+}
+
+/// The keys of a chain, in SS58 or hex of public key, which the chain spec is generated from
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisKeys {
+    /// The name of chain
+    pub name: String,
+    /// The id of chain
+    pub id: String,
+    /// The initial authorities
+    pub authorities: Vec<AuthorityKeys>,
+    /// The sudo account, which is the admin of pandora, oracle and price
+    pub sudo: String,
+    /// The cashier account
+    pub cashier: String,
+    /// The reserve account
+    pub reserve: String,
+    /// The pool account
+    pub pool: String,
+    /// The last player account
+    pub last_player: String,
+    /// The team account
+    pub team: String,
+    /// The operator account
+    pub operator: String,
+    /// The endowed accounts besides the sudo account
+    #[serde(default)]
+    pub endowed: Vec<String>,
+    /// The boot nodes
+    #[serde(default)]
+    pub boot_nodes: Vec<String>,
+}
+
+impl GenesisKeys {
+    /// Parse the keys from JSON
+    ///
+    /// @json   the JSON bytes
+    pub fn from_json_bytes(json: &[u8]) -> Result<Self, String> {
+        serde_json::from_slice(json).map_err(|e| format!("Error parsing genesis keys: {}", e))
+    }
+
+    /// Load the keys from the JSON file
+    ///
+    /// @path   the path of file
+    pub fn from_json_file(path: &Path) -> Result<Self, String> {
+        let json = fs::read(path)
+            .map_err(|e| format!("Error reading genesis keys {}: {}", path.display(), e))?;
+        Self::from_json_bytes(&json)
+    }
+
+    /// Build the chain spec, none of the keys is allowed to be a well-known dev key
+    pub fn build_spec(self) -> Result<ChainSpec, String> {
+        if self.authorities.is_empty() {
+            return Err("At least one authority is required".into());
+        }
+        let dev_keys = dev_public_keys();
+        let account = |field: &str, value: &str| -> Result<AccountId, String> {
+            parse_public(field, value, &dev_keys).map(AccountId::from)
+        };
+
+        let mut initial_authorities: Vec<(AuraId, GrandpaId)> = vec![];
+        for (i, keys) in self.authorities.iter().enumerate() {
+            let aura = parse_public(&format!("authorities[{}].aura", i), &keys.aura, &dev_keys)?;
+            let grandpa =
+                parse_public(&format!("authorities[{}].grandpa", i), &keys.grandpa, &dev_keys)?;
+            initial_authorities.push((
+                sr25519::Public(aura).into(),
+                ed25519::Public(grandpa).into(),
+            ));
+        }
+        let root_key = account("sudo", &self.sudo)?;
+        let system_accounts = SystemAccounts {
+            cashier: account("cashier", &self.cashier)?,
+            reserve: account("reserve", &self.reserve)?,
+            pool: account("pool", &self.pool)?,
+            last_player: account("last_player", &self.last_player)?,
+            team: account("team", &self.team)?,
+            operator: account("operator", &self.operator)?,
+        };
+        let mut endowed_accounts = vec![root_key.clone()];
+        for (i, value) in self.endowed.iter().enumerate() {
+            endowed_accounts.push(account(&format!("endowed[{}]", i), value)?);
+        }
+
+        Ok(ChainSpec::from_genesis(
+            &self.name,
+            &self.id,
+            move || {
+                testnet_genesis(
+                    initial_authorities.clone(),
+                    root_key.clone(),
+                    system_accounts.clone(),
+                    endowed_accounts.clone(),
+                    false,
+                    false,
+                )
+            },
+            self.boot_nodes,
+            None,
+            None,
+            None,
+            None,
+        ))
+    }
+}
+
+/// The raw public keys of the well-known dev seeds, in both sr25519 and ed25519
+fn dev_public_keys() -> Vec<[u8; 32]> {
+    let mut keys = vec![];
+    for seed in DEV_SEEDS.iter() {
+        for derivation in DEV_DERIVATIONS.iter() {
+            let seed = format!("{}{}", seed, derivation);
+            keys.push(get_from_seed::<sr25519::Public>(&seed).0);
+            keys.push(get_from_seed::<ed25519::Public>(&seed).0);
+        }
+    }
+    keys
+}
+
+/// Parse the public key in SS58 or hex, which should not be a well-known dev key
+///
+/// @field      the name of field, for error message
+/// @value      the SS58 address or the hex of public key
+/// @dev_keys   the well-known dev keys
+fn parse_public(field: &str, value: &str, dev_keys: &[[u8; 32]]) -> Result<[u8; 32], String> {
+    let raw = if value.starts_with("0x") {
+        decode_hex(&value[2..])
+    } else {
+        AccountId::from_ss58check(value)
+            .ok()
+            .map(|account| *account.as_ref())
+    }
+    .ok_or_else(|| format!("Invalid key of {}: {:?}", field, value))?;
+
+    if dev_keys.contains(&raw) {
+        return Err(format!("The key of {} is a well-known dev key: {}", field, value));
+    }
+    Ok(raw)
+}
+
+/// Decode the hex of 32 bytes
+///
+/// @hex    the hex without prefix
+fn decode_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut raw = [0u8; 32];
+    for (i, byte) in raw.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(raw)
+}
+
 fn testnet_genesis(
     initial_authorities: Vec<(AuraId, GrandpaId)>,
     root_key: AccountId,
+    system_accounts: SystemAccounts,
     endowed_accounts: Vec<AccountId>,
+    bootstrap_game: bool,
     _enable_println: bool,
 ) -> GenesisConfig {
+    // Dev chains start with a running game and the oracles elected from the dev accounts
+    let (dbox_unit_price, status, max_active_dboxes_count, oracles) = if bootstrap_game {
+        (
+            Some(DEV_DBOX_UNIT_PRICE),
+            Some(Status::Running),
+            Some(DEV_MAX_ACTIVE_DBOXES_COUNT),
+            vec![
+                (get_account_id_from_seed::<sr25519::Public>("Alice"), DEV_ORACLE_STAKE),
+                (get_account_id_from_seed::<sr25519::Public>("Bob"), DEV_ORACLE_STAKE),
+                (get_account_id_from_seed::<sr25519::Public>("Charlie"), DEV_ORACLE_STAKE),
+            ],
+        )
+    } else {
+        (None, None, None, vec![])
+    };
+
     GenesisConfig {
         system: Some(SystemConfig {
             code: WASM_BINARY.to_vec(),
@@ -158,20 +410,24 @@ fn testnet_genesis(
                 .collect(),
         }),
         pandora: Some(PandoraConfig {
-            admin_account: get_account_id_from_seed::<sr25519::Public>("Alice"),
-            cashier_account: get_account_id_from_seed::<sr25519::Public>("Alice//Cashier"),
-            reserve_account: get_account_id_from_seed::<sr25519::Public>("Alice//Reserve"),
-            pool_account: get_account_id_from_seed::<sr25519::Public>("Alice//Pool"),
-            last_player_account: get_account_id_from_seed::<sr25519::Public>("Alice//LastPlayer"),
-            team_account: get_account_id_from_seed::<sr25519::Public>("Alice//Team"),
-            operator_account: get_account_id_from_seed::<sr25519::Public>("Alice//Operator"),
+            cashier_account: system_accounts.cashier.clone(),
+            reserve_account: system_accounts.reserve,
+            pool_account: system_accounts.pool,
+            last_player_account: system_accounts.last_player,
+            team_account: system_accounts.team,
+            operator_account: system_accounts.operator,
+            dbox_unit_price: dbox_unit_price,
+            status: status,
+            max_active_dboxes_count: max_active_dboxes_count,
+            settlement_weight_ratio: None,
         }),
         oracle: Some(OracleConfig {
-            cashier_account: get_account_id_from_seed::<sr25519::Public>("Alice//Cashier"),
+            cashier_account: system_accounts.cashier.clone(),
+            oracles: oracles,
+            candidates: vec![],
         }),
         price: Some(PriceConfig {
-            admin_account: get_account_id_from_seed::<sr25519::Public>("Alice"),
-            cashier_account: get_account_id_from_seed::<sr25519::Public>("Alice//Cashier"),
+            cashier_account: system_accounts.cashier,
         }),
     }
 }
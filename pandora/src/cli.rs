@@ -1,13 +1,144 @@
 use crate::chain_spec;
+use crate::factory_impl::FactoryState;
+use crate::game::{self, GameCmd};
 use crate::service;
 use aura_primitives::sr25519::AuthorityPair as AuraPair;
 use futures::{future, sync::oneshot, Future};
 use log::info;
 use std::cell::RefCell;
-use substrate_cli::{display_role, informant, parse_and_prepare, NoCustom, ParseAndPrepare};
+use std::path::PathBuf;
+use structopt::{clap::App, StructOpt};
+use substrate_cli::{
+    display_role, informant, parse_and_prepare, AugmentClap, ExecutionStrategyParam,
+    GetLogFilter, NoCustom, ParseAndPrepare, SharedParams,
+};
 pub use substrate_cli::{error, IntoExit, VersionInfo};
+use substrate_client::ExecutionStrategies;
 use substrate_service::{AbstractService, Configuration, Roles as ServiceRoles};
 use tokio::runtime::Runtime;
+use transaction_factory::{Mode, RuntimeAdapter};
+
+/// Custom subcommands.
+#[derive(Clone, Debug, StructOpt)]
+pub enum CustomSubcommands {
+    /// The custom game subcommand for bootstrapping a game from a definition file.
+    #[structopt(
+        name = "game",
+        about = "Prints the signed extrinsics which init and start the game defined by the config."
+    )]
+    Game(GameCmd),
+    /// The custom subcommand for generating a chain spec from the genesis keys.
+    #[structopt(
+        name = "generate-spec",
+        about = "Generates the chain spec from the genesis keys, rejecting the well-known dev keys."
+    )]
+    GenerateSpec(GenerateSpecCmd),
+    /// The custom factory subcommand for manufacturing transactions.
+    #[structopt(
+        name = "factory",
+        about = "Manufactures transfers or game extrinsics into blocks. \
+        Only supported for development or local testnet."
+    )]
+    Factory(FactoryCmd),
+    /// The custom subcommand for dumping the types of pandora modules.
+    #[structopt(
+        name = "type-registry",
+        about = "Prints the types used by the storage and events of pandora modules in JSON."
+    )]
+    TypeRegistry(TypeRegistryCmd),
+}
+
+/// The `generate-spec` command used to build a chain spec from the genesis keys.
+#[derive(Debug, StructOpt, Clone)]
+pub struct GenerateSpecCmd {
+    /// The genesis keys in JSON format, see `res/staging.json`
+    #[structopt(long = "keys", value_name = "PATH", parse(from_os_str))]
+    pub keys: PathBuf,
+
+    /// Print the spec in raw storage format
+    #[structopt(long = "raw")]
+    pub raw: bool,
+}
+
+impl AugmentClap for GenerateSpecCmd {
+    fn augment_clap<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        GenerateSpecCmd::augment_clap(app)
+    }
+}
+
+/// The `type-registry` command used to dump the type registry of runtime.
+#[derive(Debug, StructOpt, Clone)]
+pub struct TypeRegistryCmd {
+    /// Print the JSON in a human readable format
+    #[structopt(long = "pretty")]
+    pub pretty: bool,
+}
+
+impl AugmentClap for TypeRegistryCmd {
+    fn augment_clap<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        TypeRegistryCmd::augment_clap(app)
+    }
+}
+
+/// The `factory` command used to generate blocks of transactions.
+/// Please note: this command currently only works on an empty database!
+#[derive(Debug, StructOpt, Clone)]
+pub struct FactoryCmd {
+    /// How often to repeat. In mode `MasterToNToM` this is the number of rounds of transfers,
+    /// in mode `Game` the number of blocks played after the players are funded.
+    #[structopt(long = "rounds", default_value = "1")]
+    pub rounds: u64,
+
+    /// MasterToN: Manufacture `num` transfers from the master account
+    ///            to `num` randomly created accounts, one each.
+    ///
+    /// MasterTo1: Manufacture `num` transfers from the master account
+    ///            to exactly one other randomly created account.
+    ///
+    /// MasterToNToM: Manufacture `num` transfers from the master account
+    ///               to `num` randomly created accounts, which transfer
+    ///               to other randomly created accounts `rounds` times.
+    ///
+    /// Game: Fund `num` randomly created players from the master account,
+    ///       then fill `rounds` blocks with a mix of `create_dbox_with_invitor`,
+    ///       `upgrade_dbox` and `open_dbox` of `per-block` players each.
+    #[structopt(long = "mode", default_value = "MasterToN")]
+    pub mode: Mode,
+
+    /// Number of transfers to generate, or the number of players in mode `Game`.
+    #[structopt(long = "num", default_value = "8")]
+    pub num: u64,
+
+    /// Number of players acting in each block of mode `Game`.
+    #[structopt(long = "per-block", default_value = "8")]
+    pub per_block: u32,
+
+    #[allow(missing_docs)]
+    #[structopt(flatten)]
+    pub shared_params: SharedParams,
+
+    /// The means of execution used when calling into the runtime while importing blocks.
+    #[structopt(
+        long = "execution",
+        value_name = "STRATEGY",
+        possible_values = &ExecutionStrategyParam::variants(),
+        case_insensitive = true,
+        default_value = "NativeElseWasm"
+    )]
+    pub execution: ExecutionStrategyParam,
+}
+
+impl AugmentClap for FactoryCmd {
+    fn augment_clap<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        FactoryCmd::augment_clap(app)
+    }
+}
+
+impl GetLogFilter for CustomSubcommands {
+    fn get_log_filter(&self) -> Option<String> {
+        None
+    }
+}
 
 /// Parse command line arguments into service configuration.
 pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
@@ -17,7 +148,7 @@ where
     E: IntoExit,
 {
     type Config<T> = Configuration<(), T>;
-    match parse_and_prepare::<NoCustom, NoCustom, _>(&version, "substrate-node", args) {
+    match parse_and_prepare::<CustomSubcommands, NoCustom, _>(&version, "substrate-node", args) {
         ParseAndPrepare::Run(cmd) => cmd.run(
             load_spec,
             exit,
@@ -52,16 +183,93 @@ where
         ParseAndPrepare::RevertChain(cmd) => {
             cmd.run_with_builder(|config: Config<_>| Ok(new_full_start!(config).0), load_spec)
         }
-        ParseAndPrepare::CustomCommand(_) => Ok(()),
+        ParseAndPrepare::CustomCommand(CustomSubcommands::Game(cli_args)) => {
+            // The genesis hash is only needed for signing
+            let genesis_hash = if cli_args.dry_run {
+                None
+            } else {
+                let config: Config<_> = substrate_cli::create_config_with_db_path(
+                    load_spec,
+                    &cli_args.shared_params,
+                    &version,
+                )?;
+                let builder = new_full_start!(config).0;
+                Some(builder.client().info().chain.genesis_hash)
+            };
+
+            game::run(&cli_args, genesis_hash).map_err(Into::into)
+        }
+        ParseAndPrepare::CustomCommand(CustomSubcommands::GenerateSpec(cli_args)) => {
+            let json = chain_spec::GenesisKeys::from_json_file(&cli_args.keys)?
+                .build_spec()?
+                .to_json(cli_args.raw)?;
+            println!("{}", json);
+            Ok(())
+        }
+        ParseAndPrepare::CustomCommand(CustomSubcommands::Factory(cli_args)) => {
+            let mut config: Config<_> = substrate_cli::create_config_with_db_path(
+                load_spec,
+                &cli_args.shared_params,
+                &version,
+            )?;
+            config.execution_strategies = ExecutionStrategies {
+                importing: cli_args.execution.into(),
+                block_construction: cli_args.execution.into(),
+                other: cli_args.execution.into(),
+                ..Default::default()
+            };
+
+            // The game is only running at genesis of dev chains
+            match config.chain_spec.id() {
+                "dev" | "local_testnet" => {}
+                _ => {
+                    let msg = "Factory is only supported for development and local testnet.";
+                    return Err(error::Error::Input(msg.into()));
+                }
+            }
+
+            let factory_state =
+                FactoryState::new(cli_args.mode.clone(), cli_args.num, cli_args.rounds);
+
+            let service_builder = new_full_start!(config).0;
+            let client = service_builder.client();
+            let select_chain = service_builder
+                .select_chain()
+                .expect("The select_chain is always initialized by new_full_start!; qed");
+            match cli_args.mode {
+                Mode::Game => transaction_factory::game_factory(
+                    factory_state,
+                    cli_args.per_block,
+                    client,
+                    select_chain,
+                ),
+                _ => transaction_factory::factory(factory_state, client, select_chain),
+            }
+            .map_err(|e| format!("Error in transaction factory: {}", e))?;
+
+            Ok(())
+        }
+        ParseAndPrepare::CustomCommand(CustomSubcommands::TypeRegistry(cli_args)) => {
+            let registry = pandora_runtime::Runtime::type_registry();
+            let json = if cli_args.pretty {
+                serde_json::to_string_pretty(&registry)
+            } else {
+                serde_json::to_string(&registry)
+            }
+            .map_err(|e| format!("Error serializing type registry: {}", e))?;
+            println!("{}", json);
+            Ok(())
+        }
     }?;
 
     Ok(())
 }
 
+/// Load the chain spec by id, any other id is taken as the path of spec JSON file
 fn load_spec(id: &str) -> Result<Option<chain_spec::ChainSpec>, String> {
     Ok(match chain_spec::Alternative::from(id) {
         Some(spec) => Some(spec.load()?),
-        None => None,
+        None => Some(chain_spec::ChainSpec::from_json_file(PathBuf::from(id))?),
     })
 }
 
@@ -0,0 +1,213 @@
+//! Implementation of the transaction factory traits for the pandora runtime, which enables
+//! manufacturing blocks of transfers or of the dbox game on a dev chain.
+//!
+//! The extrinsics are immortal, and the nonces start from zero, so the factory only works on
+//! an empty database.
+
+use crate::chain_spec::DEV_DBOX_UNIT_PRICE;
+use crate::game::sign;
+use inherents::InherentData;
+use pandora_runtime::{
+    pandora, AccountId, Balance, BalancesCall, Block, BlockNumber, Call, ExistentialDeposit,
+    Hash, Index, SLOT_DURATION,
+};
+use primitives::{sr25519, Pair};
+use sr_primitives::traits::Block as BlockT;
+use transaction_factory::{GameCall, GameRuntimeAdapter, Mode, RuntimeAdapter};
+
+/// The number of dboxes each player is funded for
+const PLAYER_DBOXES: Balance = 100;
+
+/// The state of the transaction factory
+pub struct FactoryState {
+    block_no: BlockNumber,
+    mode: Mode,
+    start_number: BlockNumber,
+    rounds: BlockNumber,
+    round: BlockNumber,
+    block_in_round: BlockNumber,
+    num: BlockNumber,
+}
+
+impl FactoryState {
+    /// The key pair of a generated account
+    ///
+    /// @seed   the seed of account
+    fn pair(seed: BlockNumber) -> sr25519::Pair {
+        sr25519::Pair::from_string(&format!("//Player{}", seed), None)
+            .expect("static values are valid; qed")
+    }
+}
+
+impl RuntimeAdapter for FactoryState {
+    type AccountId = AccountId;
+    type Balance = Balance;
+    type Block = Block;
+    type Index = Index;
+    type Number = BlockNumber;
+    type Phase = u64;
+    type Secret = sr25519::Pair;
+
+    fn new(mode: Mode, num: u64, rounds: u64) -> Self {
+        FactoryState {
+            block_no: 0,
+            mode: mode,
+            start_number: 0,
+            rounds: rounds as BlockNumber,
+            round: 0,
+            block_in_round: 0,
+            num: num as BlockNumber,
+        }
+    }
+
+    fn block_no(&self) -> Self::Number {
+        self.block_no
+    }
+
+    fn block_in_round(&self) -> Self::Number {
+        self.block_in_round
+    }
+
+    fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    fn num(&self) -> Self::Number {
+        self.num
+    }
+
+    fn rounds(&self) -> Self::Number {
+        self.rounds
+    }
+
+    fn round(&self) -> Self::Number {
+        self.round
+    }
+
+    fn start_number(&self) -> Self::Number {
+        self.start_number
+    }
+
+    fn set_block_in_round(&mut self, val: Self::Number) {
+        self.block_in_round = val;
+    }
+
+    fn set_block_no(&mut self, val: Self::Number) {
+        self.block_no = val;
+    }
+
+    fn set_round(&mut self, val: Self::Number) {
+        self.round = val;
+    }
+
+    fn transfer_extrinsic(
+        &self,
+        sender: &Self::AccountId,
+        key: &Self::Secret,
+        destination: &Self::AccountId,
+        amount: &Self::Balance,
+        version: u32,
+        genesis_hash: &Hash,
+        prior_block_hash: &Hash,
+    ) -> <Self::Block as BlockT>::Extrinsic {
+        let index = self.extract_index(sender, prior_block_hash);
+        self.transfer_extrinsic_with_nonce(
+            sender,
+            key,
+            index,
+            destination,
+            amount,
+            version,
+            genesis_hash,
+            prior_block_hash,
+        )
+    }
+
+    fn inherent_extrinsics(&self) -> InherentData {
+        // Aura requires a new slot for each block
+        let timestamp = (self.block_no as u64 + 1) * SLOT_DURATION;
+
+        let mut inherent = InherentData::new();
+        inherent
+            .put_data(timestamp::INHERENT_IDENTIFIER, &timestamp)
+            .expect("Failed putting timestamp inherent");
+        inherent
+    }
+
+    fn minimum_balance() -> Self::Balance {
+        ExistentialDeposit::get()
+    }
+
+    fn master_account_id() -> Self::AccountId {
+        Self::master_account_secret().public().into()
+    }
+
+    fn master_account_secret() -> Self::Secret {
+        sr25519::Pair::from_string("//Alice", None).expect("static values are valid; qed")
+    }
+
+    fn extract_index(&self, _account_id: &Self::AccountId, _block_hash: &Hash) -> Self::Index {
+        // The database is empty, so the index is the number of the previous transfers
+        // from the master account, or zero for the accounts of the second round
+        match (&self.mode, self.round) {
+            (Mode::MasterToNToM, round) if round > 0 => 0,
+            _ => self.block_no,
+        }
+    }
+
+    fn extract_phase(&self, _block_hash: Hash) -> Self::Phase {
+        // The extrinsics are immortal
+        0
+    }
+
+    fn gen_random_account_id(seed: &Self::Number) -> Self::AccountId {
+        Self::pair(*seed).public().into()
+    }
+
+    fn gen_random_account_secret(seed: &Self::Number) -> Self::Secret {
+        Self::pair(*seed)
+    }
+}
+
+impl GameRuntimeAdapter for FactoryState {
+    fn player_funds() -> Self::Balance {
+        DEV_DBOX_UNIT_PRICE * PLAYER_DBOXES
+    }
+
+    fn block_duration() -> u64 {
+        SLOT_DURATION
+    }
+
+    fn transfer_extrinsic_with_nonce(
+        &self,
+        _sender: &Self::AccountId,
+        key: &Self::Secret,
+        nonce: u32,
+        destination: &Self::AccountId,
+        amount: &Self::Balance,
+        _version: u32,
+        genesis_hash: &Hash,
+        _prior_block_hash: &Hash,
+    ) -> <Self::Block as BlockT>::Extrinsic {
+        let call = Call::Balances(BalancesCall::transfer(destination.clone().into(), *amount));
+        sign(call, key, nonce, *genesis_hash)
+    }
+
+    fn game_extrinsic(
+        &self,
+        _sender: &Self::AccountId,
+        key: &Self::Secret,
+        nonce: u32,
+        call: GameCall<Self::AccountId>,
+        _version: u32,
+        genesis_hash: &Hash,
+        _prior_block_hash: &Hash,
+    ) -> <Self::Block as BlockT>::Extrinsic {
+        let call = match call {
+            GameCall::CreateDbox(invitor) => pandora::Call::create_dbox_with_invitor(invitor),
+            GameCall::UpgradeDbox(index) => pandora::Call::upgrade_dbox_by_index(index),
+            GameCall::OpenDbox(index) => pandora::Call::open_dbox_by_index(index),
+        };
+        sign(Call::Pandora(call), key, nonce, *genesis_hash)
+    }
+}
@@ -0,0 +1,183 @@
+//! The `game` subcommand, which turns a game definition into the extrinsics bootstrapping the game.
+//!
+//! The admin calls of pandora are gated by root, so each call is wrapped into `sudo` and signed
+//! by the sudo key. The extrinsics are printed as hex, one per line, ready for
+//! `author_submitExtrinsic`.
+
+use codec::Encode;
+use pandora_runtime::{
+    fee,
+    pandora::{self, Status},
+    AccountId, Call, Hash, Perbill, Runtime, SignedExtra, UncheckedExtrinsic, VERSION,
+};
+use primitives::{hexdisplay::HexDisplay, sr25519, Pair};
+use serde::Deserialize;
+use sr_primitives::generic::{Era, SignedPayload};
+use std::{fs, path::PathBuf};
+use structopt::{clap::App, StructOpt};
+use substrate_cli::{AugmentClap, SharedParams};
+
+/// The `game` command used to bootstrap a game from a definition file.
+#[derive(Debug, StructOpt, Clone)]
+pub struct GameCmd {
+    /// The game definition in TOML or JSON format, by the extension of the file
+    #[structopt(long = "config", value_name = "PATH", parse(from_os_str))]
+    pub config: PathBuf,
+
+    /// The secret URI of the sudo key which signs the extrinsics
+    #[structopt(long = "suri", default_value = "//Alice")]
+    pub suri: String,
+
+    /// The nonce of the sudo account for the first extrinsic
+    #[structopt(long = "nonce", default_value = "0")]
+    pub nonce: u32,
+
+    /// Print the calls only, without signing them
+    #[structopt(long = "dry-run")]
+    pub dry_run: bool,
+
+    #[allow(missing_docs)]
+    #[structopt(flatten)]
+    pub shared_params: SharedParams,
+}
+
+impl AugmentClap for GameCmd {
+    fn augment_clap<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        GameCmd::augment_clap(app)
+    }
+}
+
+/// The game definition, the optional settings are left as the defaults of `init`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameDefinition {
+    /// The unit price of dbox
+    pub unit_price: u64,
+    /// The fiat price of dbox in the precision of oracle price
+    pub fiat_unit_price: Option<u64>,
+    /// The percentage of prize pool drawn to a lucky dbox of the round
+    pub jackpot_ratio: Option<u32>,
+    /// The percentage of block weight used by settlement
+    pub settlement_weight_ratio: Option<u32>,
+    /// The maximum active dboxes, applied from the next round
+    pub max_active_dboxes_count: Option<u64>,
+    /// Start the game after it is inited
+    #[serde(default = "default_start")]
+    pub start: bool,
+}
+
+fn default_start() -> bool {
+    true
+}
+
+impl GameDefinition {
+    /// Load the definition from the TOML or JSON file
+    ///
+    /// @path   the path of file
+    pub fn load(path: &PathBuf) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Error reading game definition {}: {}", path.display(), e))?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&content).map_err(|e| e.to_string()),
+            _ => toml::from_str(&content).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("Error parsing game definition {}: {}", path.display(), e))
+    }
+
+    /// The admin calls of pandora, in the order of dispatching
+    pub fn calls(&self) -> Vec<pandora::Call<Runtime>> {
+        let mut calls = vec![pandora::Call::init(self.unit_price.into())];
+
+        if let Some(price) = self.fiat_unit_price {
+            calls.push(pandora::Call::set_fiat_unit_price(Some(price.into())));
+        }
+        if let Some(ratio) = self.jackpot_ratio {
+            calls.push(pandora::Call::set_jackpot_ratio(ratio));
+        }
+        if let Some(ratio) = self.settlement_weight_ratio {
+            calls.push(pandora::Call::set_settlement_weight_ratio(
+                Perbill::from_percent(ratio),
+            ));
+        }
+        if let Some(count) = self.max_active_dboxes_count {
+            calls.push(pandora::Call::preset_max_active_dboxes_count(count));
+        }
+        if self.start {
+            calls.push(pandora::Call::set_status(Status::Running));
+        }
+
+        calls
+    }
+}
+
+/// Wrap the call into `sudo`
+///
+/// @call   the admin call of pandora
+fn wrap_sudo(call: pandora::Call<Runtime>) -> Call {
+    Call::Sudo(sudo::Call::sudo(Box::new(Call::Pandora(call))))
+}
+
+/// Sign the call as an immortal extrinsic
+///
+/// @call           the call
+/// @signer         the key pair of signer
+/// @nonce          the nonce of signer
+/// @genesis_hash   the genesis hash of chain
+pub fn sign(
+    call: Call,
+    signer: &sr25519::Pair,
+    nonce: u32,
+    genesis_hash: Hash,
+) -> UncheckedExtrinsic {
+    let extra: SignedExtra = (
+        system::CheckVersion::new(),
+        system::CheckGenesis::new(),
+        system::CheckEra::from(Era::Immortal),
+        system::CheckNonce::from(nonce),
+        system::CheckWeight::new(),
+        fee::ChargeGameFee::from(0),
+    );
+    let additional_signed = (
+        VERSION.spec_version,
+        genesis_hash,
+        genesis_hash,
+        (),
+        (),
+        (),
+    );
+    let payload = SignedPayload::from_raw(call, extra, additional_signed);
+    let signature = payload.using_encoded(|p| signer.sign(p));
+    let (call, extra, _) = payload.deconstruct();
+    let account: AccountId = signer.public().into();
+
+    UncheckedExtrinsic::new_signed(call, account.into(), signature.into(), extra)
+}
+
+/// Print the extrinsics bootstrapping the game
+///
+/// @cmd            the command
+/// @genesis_hash   the genesis hash of chain, None for dry run
+pub fn run(cmd: &GameCmd, genesis_hash: Option<Hash>) -> Result<(), String> {
+    let definition = GameDefinition::load(&cmd.config)?;
+    let calls = definition.calls();
+
+    let genesis_hash = match genesis_hash {
+        Some(hash) => hash,
+        None => {
+            for call in calls.iter() {
+                println!("{:?}", call);
+            }
+            return Ok(());
+        }
+    };
+
+    let signer = sr25519::Pair::from_string(&cmd.suri, None)
+        .map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+    for (nonce, call) in (cmd.nonce..).zip(calls.into_iter()) {
+        let extrinsic = sign(wrap_sudo(call), &signer, nonce, genesis_hash);
+        println!("0x{}", HexDisplay::from(&extrinsic.encode()));
+    }
+
+    Ok(())
+}
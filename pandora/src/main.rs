@@ -4,6 +4,8 @@
 #![warn(unused_extern_crates)]
 
 mod chain_spec;
+mod factory_impl;
+mod game;
 #[macro_use]
 mod service;
 mod cli;
@@ -33,6 +33,7 @@ construct_simple_protocol! {
 /// be able to perform chain operations.
 macro_rules! new_full_start {
     ($config:expr) => {{
+        type RpcExtension = jsonrpc_core::IoHandler<substrate_rpc::Metadata>;
         let mut import_setup = None;
         let inherent_data_providers = inherents::InherentDataProviders::new();
 
@@ -75,6 +76,9 @@ macro_rules! new_full_start {
             import_setup = Some((grandpa_block_import, grandpa_link));
 
             Ok(import_queue)
+        })?
+        .with_rpc_extensions(|client, pool, _backend| -> RpcExtension {
+            pandora_rpc::create(client, pool)
         })?;
 
         (builder, import_setup, inherent_data_providers)
@@ -196,6 +200,7 @@ pub fn new_full<C: Send + Default + 'static>(
 pub fn new_light<C: Send + Default + 'static>(
     config: Configuration<C, GenesisConfig>,
 ) -> Result<impl AbstractService, ServiceError> {
+    type RpcExtension = jsonrpc_core::IoHandler<substrate_rpc::Metadata>;
     let inherent_data_providers = InherentDataProviders::new();
 
     ServiceBuilder::new_light::<Block, RuntimeApi, Executor>(config)?
@@ -240,5 +245,8 @@ pub fn new_light<C: Send + Default + 'static>(
         .with_finality_proof_provider(|client, backend| {
             Ok(Arc::new(GrandpaFinalityProofProvider::new(backend, client)) as _)
         })?
+        .with_rpc_extensions(|client, pool, _backend| -> RpcExtension {
+            pandora_rpc::create(client, pool)
+        })?
         .build()
 }
@@ -0,0 +1,151 @@
+//! The test chain, which builds and imports blocks of signed extrinsics into a `TestClient`.
+
+use crate::client::{
+    consensus::BlockOrigin, Block, Client, ClientExt, TestClientBuilder, TestClientBuilderExt,
+};
+use codec::{Decode, Encode};
+use keyring::AccountKeyring;
+use pandora_runtime::{
+    BlockNumber, Call, Event, Hash, Index, TimestampCall, UncheckedExtrinsic, SLOT_DURATION,
+};
+use primitives::{storage::StorageKey, twox_128};
+use sr_primitives::{
+    generic::BlockId,
+    traits::{Block as BlockT, Header as HeaderT},
+    OpaqueExtrinsic,
+};
+use std::collections::HashMap;
+use system::EventRecord;
+
+/// The chain backed by the test client of `pandora-runtime`
+pub struct TestChain {
+    client: Client,
+    genesis_hash: Hash,
+    nonces: HashMap<AccountKeyring, Index>,
+}
+
+impl Default for TestChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestChain {
+    /// Create the chain from the genesis of tests
+    pub fn new() -> Self {
+        let client = TestClientBuilder::new().build();
+        let genesis_hash = client.genesis_hash();
+
+        TestChain {
+            client: client,
+            genesis_hash: genesis_hash,
+            nonces: HashMap::new(),
+        }
+    }
+
+    /// The test client
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// The hash of the genesis block
+    pub fn genesis_hash(&self) -> Hash {
+        self.genesis_hash
+    }
+
+    /// The number of the best block
+    pub fn best_number(&self) -> BlockNumber {
+        self.client.info().chain.best_number
+    }
+
+    /// The id of the best block
+    pub fn best_id(&self) -> BlockId<Block> {
+        BlockId::Hash(self.client.info().chain.best_hash)
+    }
+
+    /// Sign the call with the next nonce of signer
+    ///
+    /// @signer the keyring of signer
+    /// @call   the call
+    pub fn sign(&mut self, signer: AccountKeyring, call: Call) -> UncheckedExtrinsic {
+        let nonce = self.nonces.entry(signer).or_insert(0);
+        let extrinsic = crate::keyring::sign(call, signer, *nonce, self.genesis_hash);
+        *nonce += 1;
+        extrinsic
+    }
+
+    /// Wrap the call into `sudo` and sign it with the sudo key
+    ///
+    /// @call   the call requiring root
+    pub fn sudo(&mut self, call: Call) -> UncheckedExtrinsic {
+        self.sign(
+            AccountKeyring::Alice,
+            Call::Sudo(sudo::Call::sudo(Box::new(call))),
+        )
+    }
+
+    /// Build a block on top of the best block and import it
+    ///
+    /// The timestamp inherent is pushed ahead of the extrinsics, and every extrinsic should be
+    /// applied, while the result of dispatch is left to the events.
+    ///
+    /// @extrinsics the extrinsics of block
+    pub fn import_block(&mut self, extrinsics: Vec<UncheckedExtrinsic>) -> Hash {
+        let number = self.best_number() + 1;
+        let timestamp = UncheckedExtrinsic::new_unsigned(Call::Timestamp(TimestampCall::set(
+            number as u64 * SLOT_DURATION,
+        )));
+
+        let mut builder = self
+            .client
+            .new_block(Default::default())
+            .expect("Block builder is created");
+        for extrinsic in Some(timestamp).into_iter().chain(extrinsics) {
+            let opaque: OpaqueExtrinsic = Decode::decode(&mut &extrinsic.encode()[..])
+                .expect("Extrinsic is encoded with its length");
+            builder.push(opaque).expect("Extrinsic is applied");
+        }
+        let block = builder.bake().expect("Block is baked");
+        let hash = block.header().hash();
+        self.client
+            .import(BlockOrigin::Own, block)
+            .expect("Block is imported");
+
+        hash
+    }
+
+    /// Import empty blocks until the best block is at the number
+    ///
+    /// @number the block number
+    pub fn import_blocks_until(&mut self, number: BlockNumber) {
+        while self.best_number() < number {
+            self.import_block(vec![]);
+        }
+    }
+
+    /// Read the storage value of the best block
+    ///
+    /// @key    the unhashed key of storage value, e.g. `System Events`
+    pub fn storage_value<T: Decode>(&self, key: &[u8]) -> Option<T> {
+        self.client
+            .storage(&self.best_id(), &StorageKey(twox_128(key).to_vec()))
+            .expect("Storage is readable")
+            .map(|data| Decode::decode(&mut &data.0[..]).expect("Storage value is decodable"))
+    }
+
+    /// The events deposited in the best block
+    pub fn events(&self) -> Vec<Event> {
+        self.storage_value::<Vec<EventRecord<Event, Hash>>>(b"System Events")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|record| record.event)
+            .collect()
+    }
+
+    /// Check if the event is deposited in the best block
+    ///
+    /// @event  the event
+    pub fn has_event(&self, event: Event) -> bool {
+        self.events().contains(&event)
+    }
+}
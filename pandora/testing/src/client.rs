@@ -0,0 +1,65 @@
+//! Utilities to build a `TestClient` for `pandora-runtime`.
+
+use sr_primitives::BuildStorage;
+use substrate_executor::native_executor_instance;
+
+/// Re-export test-client utilities.
+pub use test_client::*;
+
+native_executor_instance!(
+    pub LocalExecutor,
+    pandora_runtime::api::dispatch,
+    pandora_runtime::native_version,
+);
+
+/// Call executor for `pandora-runtime` `TestClient`.
+pub type Executor = substrate_executor::NativeExecutor<LocalExecutor>;
+
+/// The block type of node.
+pub type Block = pandora_runtime::opaque::Block;
+
+/// Default backend type.
+pub type Backend = test_client::Backend<Block>;
+
+/// Test client type.
+pub type Client = client::Client<
+    Backend,
+    client::LocalCallExecutor<Backend, Executor>,
+    Block,
+    pandora_runtime::RuntimeApi,
+>;
+
+/// Genesis configuration parameters for `TestClient`.
+#[derive(Default)]
+pub struct GenesisParameters;
+
+impl test_client::GenesisInit for GenesisParameters {
+    fn genesis_storage(&self) -> (StorageOverlay, ChildrenStorageOverlay) {
+        crate::genesis::config(None).build_storage().unwrap()
+    }
+}
+
+/// A `pandora-runtime` extensions to `TestClientBuilder`.
+pub trait TestClientBuilderExt: Sized {
+    /// Create test client builder.
+    fn new() -> Self;
+
+    /// Build the test client.
+    fn build(self) -> Client;
+}
+
+impl TestClientBuilderExt
+    for test_client::TestClientBuilder<
+        client::LocalCallExecutor<Backend, Executor>,
+        Backend,
+        GenesisParameters,
+    >
+{
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn build(self) -> Client {
+        self.build_with_native_executor(None).0
+    }
+}
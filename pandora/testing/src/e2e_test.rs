@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod tests {
+    use crate::chain::TestChain;
+    use crate::keyring::*;
+    use keyring::AccountKeyring;
+    use oracle_rpc_runtime_api::OracleApi;
+    use pandora_rpc_runtime_api::PandoraApi;
+    use pandora_runtime::{
+        oracle, pandora, pandora::Status, price, Balance, Call, ElectionEra, Event, Hash,
+        MaxUnitPrice, MinStaking, TokenUnit, UncheckedExtrinsic,
+    };
+    use price_rpc_runtime_api::PriceApi;
+    use sr_primitives::traits::ProvideRuntimeApi;
+    use support::traits::Get;
+
+    const UNIT_PRICE: Balance = 1_000_000_000;
+
+    /// Init and start the game in the next block
+    fn start_game(chain: &mut TestChain) {
+        let init = chain.sudo(Call::Pandora(pandora::Call::init(UNIT_PRICE)));
+        let start = chain.sudo(Call::Pandora(pandora::Call::set_status(Status::Running)));
+        chain.import_block(vec![init, start]);
+    }
+
+    /// Bond Dave, Eve and Ferdie in the next block and elect them at the next election
+    fn elect_oracles(chain: &mut TestChain) {
+        let bonds = vec![
+            (AccountKeyring::Dave, MinStaking::get()),
+            (AccountKeyring::Eve, MinStaking::get() * 2),
+            (AccountKeyring::Ferdie, MinStaking::get() * 3),
+        ]
+        .into_iter()
+        .map(|(who, amount)| chain.sign(who, Call::Oracle(oracle::Call::bond(amount))))
+        .collect();
+        chain.import_block(bonds);
+
+        let election = ElectionEra::get();
+        let next_election = (chain.best_number() / election + 1) * election;
+        chain.import_blocks_until(next_election);
+    }
+
+    /// Request the price from Dave in the next block
+    fn request_price(chain: &mut TestChain) -> Hash {
+        let request = chain.sudo(Call::Price(price::Call::request_price(dave())));
+        chain.import_block(vec![request]);
+        chain
+            .events()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::oracle(oracle::RawEvent::JobCreated(_, who, _, id)) if who == dave() => {
+                    Some(id)
+                }
+                _ => None,
+            })
+            .next()
+            .expect("Job is created")
+    }
+
+    #[test]
+    fn it_works_for_game() {
+        let mut chain = TestChain::new();
+        start_game(&mut chain);
+        assert!(chain.has_event(Event::sudo(sudo::RawEvent::Sudid(true))));
+        assert!(chain.has_event(Event::pandora(pandora::RawEvent::GameInited(1))));
+        assert!(chain.has_event(Event::pandora(pandora::RawEvent::GameRunning(1))));
+        assert_eq!(
+            chain.storage_value::<Status>(b"PandoraModule GameStatus"),
+            Some(Status::Running)
+        );
+
+        let create = chain.sign(AccountKeyring::Bob, Call::Pandora(pandora::Call::create_dbox()));
+        chain.import_block(vec![create]);
+        let dbox_id = chain
+            .events()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::pandora(pandora::RawEvent::DboxCreated(id, owner)) if owner == bob() => {
+                    Some(id)
+                }
+                _ => None,
+            })
+            .next()
+            .expect("Dbox is created");
+
+        let dboxes = chain
+            .client()
+            .runtime_api()
+            .player_dboxes(&chain.best_id(), bob())
+            .unwrap();
+        assert_eq!(dboxes.len(), 1);
+        assert_eq!(dboxes[0].id, dbox_id);
+
+        let open = chain.sign(
+            AccountKeyring::Bob,
+            Call::Pandora(pandora::Call::open_dbox(dbox_id)),
+        );
+        chain.import_block(vec![open]);
+        assert!(chain.has_event(Event::pandora(pandora::RawEvent::DboxOpened(dbox_id))));
+    }
+
+    #[test]
+    fn it_fails_for_game_without_root() {
+        let mut chain = TestChain::new();
+        let init = chain.sign(
+            AccountKeyring::Bob,
+            Call::Pandora(pandora::Call::init(UNIT_PRICE)),
+        );
+        chain.import_block(vec![init]);
+        assert!(!chain.has_event(Event::pandora(pandora::RawEvent::GameInited(1))));
+        assert_eq!(chain.storage_value::<Status>(b"PandoraModule GameStatus"), None);
+    }
+
+    #[test]
+    fn it_works_for_oracle_election() {
+        let mut chain = TestChain::new();
+        elect_oracles(&mut chain);
+        assert!(chain.has_event(Event::oracle(oracle::RawEvent::OracleElected(
+            ElectionEra::get()
+        ))));
+
+        let api = chain.client().runtime_api();
+        let at = chain.best_id();
+        assert_eq!(api.oracles(&at).unwrap(), [ferdie(), eve(), dave()]);
+        assert!(api.candidates(&at).unwrap().is_empty());
+        assert_eq!(api.staked(&at, eve()).unwrap(), MinStaking::get() * 2);
+    }
+
+    #[test]
+    fn it_works_for_price() {
+        let mut chain = TestChain::new();
+        elect_oracles(&mut chain);
+
+        let job_id = request_price(&mut chain);
+
+        let report = chain.sign(
+            AccountKeyring::Dave,
+            Call::Price(price::Call::report_price(12_345, job_id)),
+        );
+        let reported_at = chain.best_number() + 1;
+        chain.import_block(vec![report]);
+        assert!(chain.has_event(Event::oracle(oracle::RawEvent::JobFulfilled(
+            dave(),
+            reported_at,
+            job_id
+        ))));
+        assert!(chain.has_event(Event::price(price::RawEvent::PriceChanged(12_345))));
+
+        let current_price = chain
+            .client()
+            .runtime_api()
+            .current_price(&chain.best_id())
+            .unwrap();
+        assert_eq!(current_price, Some((12_345, reported_at)));
+    }
+
+    #[test]
+    fn it_works_for_fiat_unit_price() {
+        let mut chain = TestChain::new();
+        elect_oracles(&mut chain);
+        let job_id = request_price(&mut chain);
+        // 0.8 fiat per token
+        let report = chain.sign(
+            AccountKeyring::Dave,
+            Call::Price(price::Call::report_price(8_000, job_id)),
+        );
+        chain.import_block(vec![report]);
+        // 1 fiat per dbox
+        let init = chain.sudo(Call::Pandora(pandora::Call::init(UNIT_PRICE)));
+        let fiat = chain.sudo(Call::Pandora(pandora::Call::set_fiat_unit_price(Some(10_000))));
+        let start = chain.sudo(Call::Pandora(pandora::Call::set_status(Status::Running)));
+        chain.import_block(vec![init, fiat, start]);
+        assert!(chain.has_event(Event::pandora(pandora::RawEvent::GameRunning(
+            chain.best_number()
+        ))));
+
+        let unit_price = TokenUnit::get() * 10_000 / 8_000;
+        assert!(unit_price < MaxUnitPrice::get());
+        assert!(chain.has_event(Event::pandora(pandora::RawEvent::UnitPriceChanged(
+            unit_price
+        ))));
+        assert_eq!(
+            chain.storage_value::<Balance>(b"PandoraModule DboxUnitPrice"),
+            Some(unit_price)
+        );
+    }
+
+    #[test]
+    fn it_works_for_unsigned_price() {
+        let mut chain = TestChain::new();
+        elect_oracles(&mut chain);
+        let job_id = request_price(&mut chain);
+
+        let report = price::UnsignedReport {
+            reporter: dave(),
+            price: 12_345,
+            id: job_id,
+        };
+        let signature = AccountKeyring::Dave.sign(&report.signing_payload(&chain.genesis_hash()));
+        let report = UncheckedExtrinsic::new_unsigned(Call::Price(
+            price::Call::report_price_unsigned(report, signature.into()),
+        ));
+        let reported_at = chain.best_number() + 1;
+        chain.import_block(vec![report]);
+        assert!(chain.has_event(Event::oracle(oracle::RawEvent::JobFulfilled(
+            dave(),
+            reported_at,
+            job_id
+        ))));
+        assert!(chain.has_event(Event::price(price::RawEvent::PriceChanged(12_345))));
+    }
+}
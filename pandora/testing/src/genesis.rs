@@ -0,0 +1,74 @@
+//! Genesis Configuration.
+
+use crate::keyring::*;
+use keyring::{Ed25519Keyring, Sr25519Keyring};
+use pandora_runtime::{
+    AuraConfig, Balance, BalancesConfig, GenesisConfig, GrandpaConfig, IndicesConfig,
+    OracleConfig, PandoraConfig, PriceConfig, SudoConfig, SystemConfig, WASM_BINARY,
+};
+
+/// The free balance of each endowed account
+pub const ENDOWMENT: Balance = 1 << 60;
+
+/// Create genesis runtime configuration for tests.
+///
+/// The game is not inited and no oracle is elected, which are left to the extrinsics of tests.
+///
+/// @code   the runtime code, the WASM binary of `pandora-runtime` by default
+pub fn config(code: Option<&[u8]>) -> GenesisConfig {
+    let endowed_accounts = vec![
+        alice(),
+        bob(),
+        charlie(),
+        dave(),
+        eve(),
+        ferdie(),
+        system_account(0),
+        system_account(6),
+    ];
+
+    GenesisConfig {
+        system: Some(SystemConfig {
+            code: code.map(|x| x.to_vec()).unwrap_or_else(|| WASM_BINARY.to_vec()),
+            changes_trie_config: Default::default(),
+        }),
+        indices: Some(IndicesConfig {
+            ids: endowed_accounts.clone(),
+        }),
+        balances: Some(BalancesConfig {
+            balances: endowed_accounts
+                .into_iter()
+                .map(|k| (k, ENDOWMENT))
+                .collect(),
+            vesting: vec![],
+        }),
+        sudo: Some(SudoConfig { key: alice() }),
+        aura: Some(AuraConfig {
+            authorities: vec![Sr25519Keyring::Alice.public().into()],
+        }),
+        grandpa: Some(GrandpaConfig {
+            authorities: vec![(Ed25519Keyring::Alice.public().into(), 1)],
+        }),
+        pandora: Some(PandoraConfig {
+            cashier_account: system_account(0),
+            reserve_account: system_account(1),
+            pool_account: system_account(2),
+            last_player_account: system_account(3),
+            team_account: system_account(4),
+            operator_account: system_account(5),
+            dbox_unit_price: None,
+            status: None,
+            max_active_dboxes_count: None,
+            settlement_weight_ratio: None,
+        }),
+        oracle: Some(OracleConfig {
+            cashier_account: system_account(0),
+            oracles: vec![],
+            candidates: vec![],
+        }),
+        // The price requests are paid by another account than the oracle cashier
+        price: Some(PriceConfig {
+            cashier_account: system_account(6),
+        }),
+    }
+}
@@ -0,0 +1,87 @@
+//! Test accounts and the signing of extrinsics.
+
+use codec::Encode;
+use keyring::AccountKeyring;
+use pandora_runtime::{
+    fee, AccountId, Call, Hash, Index, SignedExtra, UncheckedExtrinsic, VERSION,
+};
+use sr_primitives::generic::{Era, SignedPayload};
+
+/// Alice's account id, who is also the sudo key.
+pub fn alice() -> AccountId {
+    AccountKeyring::Alice.into()
+}
+
+/// Bob's account id.
+pub fn bob() -> AccountId {
+    AccountKeyring::Bob.into()
+}
+
+/// Charlie's account id.
+pub fn charlie() -> AccountId {
+    AccountKeyring::Charlie.into()
+}
+
+/// Dave's account id.
+pub fn dave() -> AccountId {
+    AccountKeyring::Dave.into()
+}
+
+/// Eve's account id.
+pub fn eve() -> AccountId {
+    AccountKeyring::Eve.into()
+}
+
+/// Ferdie's account id.
+pub fn ferdie() -> AccountId {
+    AccountKeyring::Ferdie.into()
+}
+
+/// Get the system account of the pandora, oracle and price modules
+///
+/// @index  the index of system account
+pub fn system_account(index: u8) -> AccountId {
+    [0xff - index; 32].into()
+}
+
+/// Returns transaction extra.
+///
+/// @nonce  the nonce of signer
+pub fn signed_extra(nonce: Index) -> SignedExtra {
+    (
+        system::CheckVersion::new(),
+        system::CheckGenesis::new(),
+        system::CheckEra::from(Era::Immortal),
+        system::CheckNonce::from(nonce),
+        system::CheckWeight::new(),
+        fee::ChargeGameFee::from(0),
+    )
+}
+
+/// Sign the call as an immortal extrinsic
+///
+/// @call           the call
+/// @signer         the keyring of signer
+/// @nonce          the nonce of signer
+/// @genesis_hash   the genesis hash of chain
+pub fn sign(
+    call: Call,
+    signer: AccountKeyring,
+    nonce: Index,
+    genesis_hash: Hash,
+) -> UncheckedExtrinsic {
+    let additional_signed = (
+        VERSION.spec_version,
+        genesis_hash,
+        genesis_hash,
+        (),
+        (),
+        (),
+    );
+    let payload = SignedPayload::from_raw(call, signed_extra(nonce), additional_signed);
+    let signature = payload.using_encoded(|p| signer.sign(p));
+    let (call, extra, _) = payload.deconstruct();
+    let account: AccountId = signer.into();
+
+    UncheckedExtrinsic::new_signed(call, account.into(), signature.into(), extra)
+}
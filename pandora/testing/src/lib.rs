@@ -0,0 +1,14 @@
+//! A set of testing utilities for the Pandora node.
+//!
+//! Unlike the mock runtimes of the modules, the chain is backed by the real `pandora_runtime`, so
+//! the blocks imported here go through the runtime wiring and parameters of the node.
+
+#![warn(missing_docs)]
+
+pub mod chain;
+pub mod client;
+pub mod genesis;
+pub mod keyring;
+
+#[cfg(test)]
+mod e2e_test;
@@ -60,9 +60,7 @@
 //! # pub type AllModules = u64;
 //! # pub enum Runtime {};
 //! # use sr_primitives::transaction_validity::{TransactionValidity, UnknownTransaction};
-//!	# #[allow(deprecated)]
 //! # use sr_primitives::traits::ValidateUnsigned;
-//!	# #[allow(deprecated)]
 //! # impl ValidateUnsigned for Runtime {
 //! # 	type Call = ();
 //! #
@@ -78,7 +76,6 @@
 
 use codec::{Codec, Encode};
 use rstd::{marker::PhantomData, prelude::*};
-#[allow(deprecated)]
 use sr_primitives::traits::ValidateUnsigned;
 use sr_primitives::{
     generic::Digest,
@@ -106,7 +103,6 @@ pub struct Executive<System, Block, Context, UnsignedValidator, AllModules>(
     PhantomData<(System, Block, Context, UnsignedValidator, AllModules)>,
 );
 
-#[allow(deprecated)] // Allow ValidateUnsigned, remove the attribute when the trait is removed.
 impl<
         System: system::Trait,
         Block: traits::Block<Header = System::Header, Hash = System::Hash>,
@@ -129,7 +125,6 @@ where
     }
 }
 
-#[allow(deprecated)] // Allow ValidateUnsigned, remove the attribute when the trait is removed.
 impl<
         System: system::Trait,
         Block: traits::Block<Header = System::Header, Hash = System::Hash>,
@@ -429,7 +424,6 @@ mod tests {
         type FeeMultiplierUpdate = ();
     }
 
-    #[allow(deprecated)]
     impl ValidateUnsigned for Runtime {
         type Call = Call;
 
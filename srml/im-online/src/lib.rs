@@ -576,7 +576,6 @@ impl<T: Trait> session::OneSessionHandler<T::AccountId> for Module<T> {
     }
 }
 
-#[allow(deprecated)]
 impl<T: Trait> support::unsigned::ValidateUnsigned for Module<T> {
     type Call = Call<T>;
 
@@ -104,7 +104,6 @@ fn heartbeat(
     authority_index: u32,
     id: UintAuthorityId,
 ) -> dispatch::Result {
-    #[allow(deprecated)]
     use support::unsigned::ValidateUnsigned;
 
     let heartbeat = Heartbeat {
@@ -118,7 +117,6 @@ fn heartbeat(
     };
     let signature = id.sign(&heartbeat.encode()).unwrap();
 
-    #[allow(deprecated)] // Allow ValidateUnsigned
     ImOnline::pre_dispatch(&crate::Call::heartbeat(
         heartbeat.clone(),
         signature.clone(),
@@ -250,6 +250,88 @@ impl ModuleErrorMetadata for &'static str {
     }
 }
 
+/// All the metadata about a field of a struct or an enum variant.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct FieldMetadata {
+    /// The name of field, `None` for the fields of a tuple struct or variant.
+    pub name: Option<DecodeDifferentStr>,
+    pub ty: DecodeDifferentStr,
+    pub documentation: DecodeDifferentArray<&'static str, StringBuf>,
+}
+
+/// All the metadata about an enum variant.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct VariantMetadata {
+    pub name: DecodeDifferentStr,
+    /// The index of variant in the encoding, which is the order of declaration.
+    pub index: u8,
+    pub fields: DecodeDifferentArray<FieldMetadata>,
+    pub documentation: DecodeDifferentArray<&'static str, StringBuf>,
+}
+
+/// The definition of a type.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub enum TypeDefinition {
+    /// A struct, encoded as its fields in order.
+    Struct(DecodeDifferentArray<FieldMetadata>),
+    /// An enum, encoded as the index of variant followed by its fields.
+    Enum(DecodeDifferentArray<VariantMetadata>),
+    /// Another name of a type, e.g. `DboxOf` for `Dbox<Hash, Balance, AccountId>`.
+    Alias(DecodeDifferentStr),
+}
+
+/// All the metadata about a type used by the storage or events of a module.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct TypeMetadata {
+    pub name: DecodeDifferentStr,
+    /// The names of generic parameters, which are used by the types of fields.
+    pub parameters: DecodeDifferentArray<&'static str, StringBuf>,
+    pub definition: TypeDefinition,
+    pub documentation: DecodeDifferentArray<&'static str, StringBuf>,
+}
+
+impl TypeMetadata {
+    /// Creates the metadata of a type alias.
+    pub fn alias(name: &'static str, ty: &'static str) -> Self {
+        TypeMetadata {
+            name: DecodeDifferent::Encode(name),
+            parameters: DecodeDifferent::Encode(&[]),
+            definition: TypeDefinition::Alias(DecodeDifferent::Encode(ty)),
+            documentation: DecodeDifferent::Encode(&[]),
+        }
+    }
+}
+
+/// The metadata of a type, usually implemented by `#[derive(DescribeType)]`.
+pub trait DescribeType {
+    fn type_metadata() -> TypeMetadata;
+}
+
+/// All the types described by a module.
+pub trait ModuleTypeMetadata {
+    fn types() -> Vec<TypeMetadata>;
+}
+
+/// All the metadata about the types of a module.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct ModuleTypesMetadata {
+    pub name: DecodeDifferentStr,
+    pub types: DecodeDifferent<FnEncode<Vec<TypeMetadata>>, Vec<TypeMetadata>>,
+}
+
+/// The registry of the types used by the storage and events of runtime modules,
+/// which lets clients decode them without a hand-maintained type map.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct TypeRegistry {
+    pub modules: DecodeDifferentArray<ModuleTypesMetadata>,
+}
+
 /// A technical trait to store lazy initiated vec value as static dyn pointer.
 pub trait DefaultByte: Send + Sync {
     fn default_byte(&self) -> Vec<u8>;
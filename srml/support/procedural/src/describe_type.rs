@@ -0,0 +1,230 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Implementation of `#[derive(DescribeType)]`, describing the fields and variants of a type
+//! for the type registry.
+
+use srml_support_procedural_tools::{
+	clean_type_string, generate_crate_access, generate_hidden_includes,
+};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+	spanned::Spanned, Attribute, Data, DeriveInput, Error, Expr, Fields, GenericParam, Lit, Meta,
+	NestedMeta, Result, Variant,
+};
+
+/// The `#[codec(..)]` attributes of a field or a variant that change its SCALE encoding.
+#[derive(Default)]
+struct CodecAttrs {
+	/// `#[codec(skip)]`, the item is not encoded.
+	skip: bool,
+	/// `#[codec(compact)]`, the field is encoded as `Compact<T>`.
+	compact: bool,
+	/// `#[codec(index = "N")]`, the index of the variant.
+	index: Option<u8>,
+}
+
+/// Parse an integer literal, or a string literal holding an integer, as a variant index.
+fn parse_index(lit: &Lit) -> Result<u8> {
+	let index = match lit {
+		Lit::Int(int) => int.base10_parse::<u8>().ok(),
+		Lit::Str(s) => s.value().parse::<u8>().ok(),
+		_ => None,
+	};
+	index.ok_or_else(|| Error::new_spanned(lit, "The index of a variant must be an integer in 0..=255"))
+}
+
+/// The `#[codec(..)]` attributes of an item. Attributes changing the encoding in a way that
+/// cannot be described are rejected.
+fn codec_attrs(attrs: &[Attribute]) -> Result<CodecAttrs> {
+	let mut codec = CodecAttrs::default();
+	for attr in attrs.iter().filter(|attr| attr.path.is_ident("codec")) {
+		let list = match attr.parse_meta()? {
+			Meta::List(list) => list,
+			meta => return Err(Error::new_spanned(meta, "Expected `#[codec(..)]`")),
+		};
+		for nested in list.nested.iter() {
+			match nested {
+				NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => codec.skip = true,
+				NestedMeta::Meta(Meta::Path(path)) if path.is_ident("compact") => {
+					codec.compact = true
+				},
+				NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("index") => {
+					codec.index = Some(parse_index(&meta.lit)?)
+				},
+				_ => {
+					return Err(Error::new_spanned(
+						nested,
+						"This codec attribute is not supported by `DescribeType`",
+					))
+				},
+			}
+		}
+	}
+	Ok(codec)
+}
+
+/// The index of a variant in the SCALE encoding: `#[codec(index = "N")]`, else the explicit
+/// discriminant, else the order of declaration.
+fn variant_index(variant: &Variant, position: usize) -> Result<u8> {
+	let codec = codec_attrs(&variant.attrs)?;
+	if codec.skip {
+		return Err(Error::new_spanned(variant, "Skipped variants are not supported by `DescribeType`"));
+	}
+	if codec.compact {
+		return Err(Error::new_spanned(variant, "`#[codec(compact)]` only applies to fields"));
+	}
+	if let Some(index) = codec.index {
+		return Ok(index);
+	}
+	match &variant.discriminant {
+		Some((_, Expr::Lit(expr))) => parse_index(&expr.lit),
+		Some((_, expr)) => Err(Error::new_spanned(expr, "The discriminant must be an integer literal")),
+		None if position < 256 => Ok(position as u8),
+		None => Err(Error::new(variant.span(), "Only 256 variants are encodable")),
+	}
+}
+
+/// The doc comments of an item, as a list of string literals.
+fn docs(attrs: &[Attribute]) -> TokenStream {
+	let mut docs = TokenStream::new();
+	for attr in attrs.iter().filter_map(|v| v.parse_meta().ok()) {
+		if let syn::Meta::NameValue(meta) = attr {
+			if meta.path.is_ident("doc") {
+				let lit = meta.lit;
+				docs.extend(quote!(#lit,));
+			}
+		}
+	}
+	docs
+}
+
+fn fields_metadata(scrate: &TokenStream, fields: &Fields) -> Result<TokenStream> {
+	let mut metadata = Vec::new();
+	for field in fields.iter() {
+		let codec = codec_attrs(&field.attrs)?;
+		if codec.skip {
+			continue;
+		}
+		if codec.index.is_some() {
+			return Err(Error::new_spanned(field, "`#[codec(index)]` only applies to variants"));
+		}
+
+		let name = match &field.ident {
+			Some(ident) => {
+				let name = ident.to_string();
+				quote!( Some(#scrate::metadata::DecodeDifferent::Encode(#name)) )
+			},
+			None => quote!( None ),
+		};
+		let ty = &field.ty;
+		let mut ty = clean_type_string(&quote!(#ty).to_string());
+		if codec.compact {
+			ty = format!("Compact<{}>", ty);
+		}
+		let docs = docs(&field.attrs);
+
+		metadata.push(quote! {
+			#scrate::metadata::FieldMetadata {
+				name: #name,
+				ty: #scrate::metadata::DecodeDifferent::Encode(#ty),
+				documentation: #scrate::metadata::DecodeDifferent::Encode(&[ #docs ]),
+			}
+		});
+	}
+
+	Ok(quote!( #scrate::metadata::DecodeDifferent::Encode(&[ #( #metadata, )* ]) ))
+}
+
+/// The `TypeDefinition` of a struct or an enum.
+fn definition(scrate: &TokenStream, data: &Data) -> Result<TokenStream> {
+	match data {
+		Data::Struct(data) => {
+			let fields = fields_metadata(scrate, &data.fields)?;
+			Ok(quote!( #scrate::metadata::TypeDefinition::Struct(#fields) ))
+		},
+		Data::Enum(data) => {
+			let mut variants = Vec::new();
+			let mut indices = Vec::new();
+			for (position, variant) in data.variants.iter().enumerate() {
+				let name = variant.ident.to_string();
+				let index = variant_index(variant, position)?;
+				if indices.contains(&index) {
+					return Err(Error::new_spanned(variant, "Duplicate index of variant"));
+				}
+				indices.push(index);
+				let fields = fields_metadata(scrate, &variant.fields)?;
+				let docs = docs(&variant.attrs);
+
+				variants.push(quote! {
+					#scrate::metadata::VariantMetadata {
+						name: #scrate::metadata::DecodeDifferent::Encode(#name),
+						index: #index,
+						fields: #fields,
+						documentation: #scrate::metadata::DecodeDifferent::Encode(&[ #docs ]),
+					}
+				});
+			}
+			Ok(quote! {
+				#scrate::metadata::TypeDefinition::Enum(
+					#scrate::metadata::DecodeDifferent::Encode(&[ #( #variants, )* ])
+				)
+			})
+		},
+		Data::Union(_) => Err(Error::new(Span::call_site(), "Unions are not encodable")),
+	}
+}
+
+/// Full implementation of `#[derive(DescribeType)]`.
+pub fn describe_type_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = syn::parse_macro_input!(input as DeriveInput);
+
+	let hidden_crate_name = format!("describe_type_{}", input.ident.to_string().to_lowercase());
+	let scrate = generate_crate_access(&hidden_crate_name, "srml-support");
+	let scrate_decl = generate_hidden_includes(&hidden_crate_name, "srml-support");
+
+	let definition = match definition(&scrate, &input.data) {
+		Ok(definition) => definition,
+		Err(e) => return e.to_compile_error().into(),
+	};
+
+	let ident = &input.ident;
+	let name = ident.to_string();
+	let parameters = input.generics.params.iter().filter_map(|param| match param {
+		GenericParam::Type(ty) => Some(ty.ident.to_string()),
+		_ => None,
+	});
+	let docs = docs(&input.attrs);
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let expanded = quote! {
+		#scrate_decl
+
+		impl #impl_generics #scrate::metadata::DescribeType for #ident #ty_generics #where_clause {
+			fn type_metadata() -> #scrate::metadata::TypeMetadata {
+				#scrate::metadata::TypeMetadata {
+					name: #scrate::metadata::DecodeDifferent::Encode(#name),
+					parameters: #scrate::metadata::DecodeDifferent::Encode(&[ #( #parameters, )* ]),
+					definition: #definition,
+					documentation: #scrate::metadata::DecodeDifferent::Encode(&[ #docs ]),
+				}
+			}
+		}
+	};
+
+	expanded.into()
+}
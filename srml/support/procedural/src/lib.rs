@@ -22,6 +22,7 @@
 
 extern crate proc_macro;
 
+mod describe_type;
 mod storage;
 
 use proc_macro::TokenStream;
@@ -215,3 +216,34 @@ use proc_macro::TokenStream;
 pub fn decl_storage(input: TokenStream) -> TokenStream {
 	storage::decl_storage_impl(input)
 }
+
+/// Derives `DescribeType`, which describes the fields and variants of a struct or an enum
+/// for the type registry of runtime.
+///
+/// The types of fields are described as written, so the generic parameters should be named
+/// as the types known by clients, e.g. `Dbox<Hash, Balance, AccountId>`.
+///
+/// The description follows the SCALE encoding derived by `parity-scale-codec`:
+/// - The index of a variant is given by `#[codec(index = "N")]`, else by its explicit
+///   discriminant, else by its order of declaration.
+/// - The fields with `#[codec(skip)]` are left out.
+/// - The fields with `#[codec(compact)]` are described as `Compact<T>`.
+///
+/// Any other codec attribute, a skipped variant or a discriminant that is not an integer literal
+/// is a compile error.
+///
+/// ## Example
+///
+/// ```nocompile
+/// #[derive(Encode, Decode, DescribeType)]
+/// pub struct Player<Balance> {
+/// 	/// Total bonus received
+/// 	pub total_bonus: Balance,
+/// 	/// Player status
+/// 	pub status: PlayerStatus,
+/// }
+/// ```
+#[proc_macro_derive(DescribeType)]
+pub fn describe_type(input: TokenStream) -> TokenStream {
+	describe_type::describe_type_impl(input)
+}
@@ -17,8 +17,11 @@
 pub use srml_metadata::{
 	DecodeDifferent, FnEncode, RuntimeMetadata, ModuleMetadata, RuntimeMetadataLastVersion,
 	DefaultByteGetter, RuntimeMetadataPrefixed, StorageEntryMetadata, StorageMetadata,
-	StorageEntryType, StorageEntryModifier, DefaultByte, StorageHasher, ModuleErrorMetadata
+	StorageEntryType, StorageEntryModifier, DefaultByte, StorageHasher, ModuleErrorMetadata,
+	DescribeType, FieldMetadata, ModuleTypeMetadata, ModuleTypesMetadata, TypeDefinition,
+	TypeMetadata, TypeRegistry, VariantMetadata,
 };
+pub use srml_support_procedural::DescribeType;
 
 /// Implements the metadata support for the given runtime and all its modules.
 ///
@@ -70,6 +73,44 @@ macro_rules! impl_runtime_metadata {
 	}
 }
 
+/// Implements the type registry of the given runtime, from the types described by its modules.
+///
+/// Each module implements `ModuleTypeMetadata`, usually listing the types which derive
+/// `DescribeType` and the aliases used by its storage and events.
+///
+/// Example:
+/// ```nocompile
+/// srml_support::impl_type_registry! {
+///     for Runtime with modules
+///         pandora::Module as PandoraModule,
+///         oracle::Module as Oracle,
+/// };
+/// ```
+#[macro_export]
+macro_rules! impl_type_registry {
+	(
+		for $runtime:ident with modules
+			$( $mod:ident::$module:ident as $name:ident ),* $(,)?
+	) => {
+		impl $runtime {
+			pub fn type_registry() -> $crate::metadata::TypeRegistry {
+				$crate::metadata::TypeRegistry {
+					modules: $crate::metadata::DecodeDifferent::Encode(&[ $(
+						$crate::metadata::ModuleTypesMetadata {
+							name: $crate::metadata::DecodeDifferent::Encode(stringify!($name)),
+							types: $crate::metadata::DecodeDifferent::Encode(
+								$crate::metadata::FnEncode(
+									<$mod::$module<$runtime> as $crate::metadata::ModuleTypeMetadata>::types
+								)
+							),
+						},
+					)* ]),
+				}
+			}
+		}
+	}
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __runtime_modules_to_metadata {
@@ -150,6 +191,63 @@ macro_rules! __runtime_modules_to_metadata_calls_call {
 	};
 }
 
+/// The type names used by storage and events which clients know without the type registry,
+/// i.e. the primitives, the generic parameter of modules and the types of the system module.
+#[cfg(feature = "std")]
+pub const WELL_KNOWN_TYPES: &[&str] = &[
+	"T", "bool", "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128", "Vec",
+	"Option", "Compact", "AccountId", "Balance", "BlockNumber", "Hash", "Perbill",
+];
+
+/// Returns the type names used by the storage and events of a module which are neither described
+/// by its types, nor in `known` or `WELL_KNOWN_TYPES`, so a module can check its
+/// `ModuleTypeMetadata` in tests.
+#[cfg(feature = "std")]
+pub fn undescribed_types(
+	types: &[TypeMetadata],
+	storage: &StorageMetadata,
+	events: &[srml_metadata::EventMetadata],
+	known: &[&str],
+) -> Vec<String> {
+	let mut used: Vec<&str> = Vec::new();
+	if let DecodeDifferent::Encode(entries) = storage.entries {
+		for entry in entries {
+			let type_names = match &entry.ty {
+				StorageEntryType::Plain(value) => vec![value],
+				StorageEntryType::Map { key, value, .. } => vec![key, value],
+				StorageEntryType::DoubleMap { key1, key2, value, .. } => vec![key1, key2, value],
+			};
+			for type_name in type_names {
+				if let DecodeDifferent::Encode(type_name) = *type_name {
+					used.push(type_name);
+				}
+			}
+		}
+	}
+	for event in events {
+		if let DecodeDifferent::Encode(arguments) = event.arguments {
+			used.extend(arguments.iter());
+		}
+	}
+
+	let described = |name: &str| {
+		types.iter().any(|ty| match &ty.name {
+			DecodeDifferent::Encode(ty_name) => *ty_name == name,
+			DecodeDifferent::Decoded(ty_name) => ty_name == name,
+		}) || known.iter().chain(WELL_KNOWN_TYPES).any(|known_name| *known_name == name)
+	};
+	let mut undescribed: Vec<String> = used
+		.iter()
+		.flat_map(|type_name| type_name.split(|c: char| !c.is_alphanumeric() && c != '_'))
+		.filter(|name| !name.is_empty() && !name.starts_with(|c: char| c.is_ascii_digit()))
+		.filter(|name| !described(*name))
+		.map(String::from)
+		.collect();
+	undescribed.sort();
+	undescribed.dedup();
+	undescribed
+}
+
 
 #[macro_export]
 #[doc(hidden)]
@@ -345,6 +443,32 @@ mod tests {
 				build(|_| {});
 			}
 		}
+
+		/// The kind of a record
+		#[derive(super::DescribeType)]
+		pub enum Kind {
+			Empty,
+			/// Locked until the block
+			Locked(u32),
+		}
+
+		#[derive(super::DescribeType)]
+		pub struct Record<Balance> {
+			/// The amount
+			pub amount: Balance,
+			pub kind: Kind,
+		}
+
+		impl<T: Trait> super::ModuleTypeMetadata for Module<T> {
+			fn types() -> Vec<super::TypeMetadata> {
+				use super::DescribeType;
+				vec![
+					Kind::type_metadata(),
+					Record::<T::Balance>::type_metadata(),
+					super::TypeMetadata::alias("RecordOf", "Record<Balance>"),
+				]
+			}
+		}
 	}
 
 	type EventModule = event_module::Module<TestRuntime>;
@@ -401,6 +525,11 @@ mod tests {
 			event_module2::Module as Module2 with Event Storage Call,
 	);
 
+	impl_type_registry!(
+		for TestRuntime with modules
+			event_module2::Module as Module2,
+	);
+
 	struct ConstantBlockNumberByteGetter;
 	impl DefaultByte for ConstantBlockNumberByteGetter {
 		fn default_byte(&self) -> Vec<u8> {
@@ -550,4 +679,136 @@ mod tests {
 
 		pretty_assertions::assert_eq!(expected_metadata, metadata_decoded.unwrap());
 	}
+
+	#[test]
+	fn type_registry() {
+		let expected_registry = TypeRegistry {
+			modules: DecodeDifferent::Encode(&[
+				ModuleTypesMetadata {
+					name: DecodeDifferent::Encode("Module2"),
+					types: DecodeDifferent::Encode(FnEncode(|| vec![
+						TypeMetadata {
+							name: DecodeDifferent::Encode("Kind"),
+							parameters: DecodeDifferent::Encode(&[]),
+							definition: TypeDefinition::Enum(DecodeDifferent::Encode(&[
+								VariantMetadata {
+									name: DecodeDifferent::Encode("Empty"),
+									index: 0,
+									fields: DecodeDifferent::Encode(&[]),
+									documentation: DecodeDifferent::Encode(&[]),
+								},
+								VariantMetadata {
+									name: DecodeDifferent::Encode("Locked"),
+									index: 1,
+									fields: DecodeDifferent::Encode(&[
+										FieldMetadata {
+											name: None,
+											ty: DecodeDifferent::Encode("u32"),
+											documentation: DecodeDifferent::Encode(&[]),
+										},
+									]),
+									documentation: DecodeDifferent::Encode(&[" Locked until the block"]),
+								},
+							])),
+							documentation: DecodeDifferent::Encode(&[" The kind of a record"]),
+						},
+						TypeMetadata {
+							name: DecodeDifferent::Encode("Record"),
+							parameters: DecodeDifferent::Encode(&["Balance"]),
+							definition: TypeDefinition::Struct(DecodeDifferent::Encode(&[
+								FieldMetadata {
+									name: Some(DecodeDifferent::Encode("amount")),
+									ty: DecodeDifferent::Encode("Balance"),
+									documentation: DecodeDifferent::Encode(&[" The amount"]),
+								},
+								FieldMetadata {
+									name: Some(DecodeDifferent::Encode("kind")),
+									ty: DecodeDifferent::Encode("Kind"),
+									documentation: DecodeDifferent::Encode(&[]),
+								},
+							])),
+							documentation: DecodeDifferent::Encode(&[]),
+						},
+						TypeMetadata::alias("RecordOf", "Record<Balance>"),
+					])),
+				},
+			]),
+		};
+
+		let registry_encoded = TestRuntime::type_registry().encode();
+		let registry_decoded = TypeRegistry::decode(&mut &registry_encoded[..]);
+
+		pretty_assertions::assert_eq!(expected_registry, registry_decoded.unwrap());
+	}
+
+	#[derive(Encode, DescribeType)]
+	enum Indexed {
+		First = 3,
+		#[codec(index = "7")]
+		Second,
+		Third,
+	}
+
+	#[derive(Encode, DescribeType)]
+	struct Annotated {
+		#[codec(compact)]
+		amount: u64,
+		#[codec(skip)]
+		#[allow(dead_code)]
+		cache: u32,
+		flag: bool,
+	}
+
+	#[test]
+	fn describe_type_follows_codec_attributes() {
+		let expected_indexed = TypeMetadata {
+			name: DecodeDifferent::Encode("Indexed"),
+			parameters: DecodeDifferent::Encode(&[]),
+			definition: TypeDefinition::Enum(DecodeDifferent::Encode(&[
+				VariantMetadata {
+					name: DecodeDifferent::Encode("First"),
+					index: 3,
+					fields: DecodeDifferent::Encode(&[]),
+					documentation: DecodeDifferent::Encode(&[]),
+				},
+				VariantMetadata {
+					name: DecodeDifferent::Encode("Second"),
+					index: 7,
+					fields: DecodeDifferent::Encode(&[]),
+					documentation: DecodeDifferent::Encode(&[]),
+				},
+				VariantMetadata {
+					name: DecodeDifferent::Encode("Third"),
+					index: 2,
+					fields: DecodeDifferent::Encode(&[]),
+					documentation: DecodeDifferent::Encode(&[]),
+				},
+			])),
+			documentation: DecodeDifferent::Encode(&[]),
+		};
+		pretty_assertions::assert_eq!(expected_indexed, Indexed::type_metadata());
+		assert_eq!(Indexed::First.encode()[0], 3);
+		assert_eq!(Indexed::Second.encode()[0], 7);
+		assert_eq!(Indexed::Third.encode()[0], 2);
+
+		let expected_annotated = TypeMetadata {
+			name: DecodeDifferent::Encode("Annotated"),
+			parameters: DecodeDifferent::Encode(&[]),
+			definition: TypeDefinition::Struct(DecodeDifferent::Encode(&[
+				FieldMetadata {
+					name: Some(DecodeDifferent::Encode("amount")),
+					ty: DecodeDifferent::Encode("Compact<u64>"),
+					documentation: DecodeDifferent::Encode(&[]),
+				},
+				FieldMetadata {
+					name: Some(DecodeDifferent::Encode("flag")),
+					ty: DecodeDifferent::Encode("bool"),
+					documentation: DecodeDifferent::Encode(&[]),
+				},
+			])),
+			documentation: DecodeDifferent::Encode(&[]),
+		};
+		pretty_assertions::assert_eq!(expected_annotated, Annotated::type_metadata());
+		assert_eq!(Annotated { amount: 1, cache: 5, flag: true }.encode(), vec![4, 1]);
+	}
 }
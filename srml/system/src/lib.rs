@@ -602,6 +602,15 @@ impl<T: Trait> Module<T> {
 		AllExtrinsicsWeight::get().unwrap_or_default()
 	}
 
+	/// Inform the system module of some additional weight that should be accounted for, in the
+	/// current block. This is typically the work done in `on_initialize` of the modules, which
+	/// is accounted before the extrinsics are applied.
+	///
+	/// The weight is added without checking the block limits.
+	pub fn register_extra_weight_unchecked(weight: Weight) {
+		AllExtrinsicsWeight::mutate(|w| *w = Some(w.unwrap_or_default().saturating_add(weight)));
+	}
+
 	pub fn all_extrinsics_len() -> u32 {
 		AllExtrinsicsLen::get().unwrap_or_default()
 	}
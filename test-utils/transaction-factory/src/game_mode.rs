@@ -0,0 +1,267 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+/// This module implements the `Game` manufacturing mode:
+///
+/// # Funding
+/// Fund `num` randomly created players from the master account,
+/// `per_block` transfers per block.
+///
+///   A -> P1, A -> P2, ... x `per_block`
+///   ... until `num` players are funded
+///
+/// # Playing
+/// Fill `rounds` blocks with one game extrinsic of each of `per_block`
+/// distinct players. The first dbox of a player is created with another
+/// player as invitor now and then, and the later calls are mixed as
+/// roughly 50% `create_dbox_with_invitor`, 30% `upgrade_dbox` and
+/// 20% `open_dbox`.
+///
+/// The time spent building each block, which includes `on_finalize`,
+/// is logged to find how many players per block the runtime sustains.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+
+use log::{info, warn};
+use client::Client;
+use block_builder_api::BlockBuilder;
+use sr_api::ConstructRuntimeApi;
+use codec::{Decode, Encode};
+use primitives::{Blake2Hasher, Hasher};
+use sr_primitives::traits::{Block as BlockT, ProvideRuntimeApi, One, UniqueSaturatedInto};
+use sr_primitives::generic::BlockId;
+
+use crate::{GameCall, GameRuntimeAdapter, RuntimeAdapter};
+
+/// A player of the game and the dboxes it has created.
+struct Player<AccountId, Secret> {
+	id: AccountId,
+	key: Secret,
+	nonce: u32,
+	/// The number of dboxes created, so the next one has this index.
+	dboxes: u64,
+	/// The indices of the opened dboxes.
+	opened: HashSet<u64>,
+}
+
+impl<AccountId, Secret> Player<AccountId, Secret> {
+	/// The indices of the dboxes which are not opened yet.
+	fn active_dboxes(&self) -> Vec<u64> {
+		(0..self.dboxes).filter(|index| !self.opened.contains(index)).collect()
+	}
+}
+
+/// The state of the game kept across blocks.
+pub struct GameState<RA: RuntimeAdapter> {
+	players: Vec<Player<RA::AccountId, RA::Secret>>,
+	per_block: usize,
+	rounds: u64,
+	master_nonce: u32,
+	funded: usize,
+	played: u64,
+	rng: u64,
+}
+
+impl<RA: RuntimeAdapter> GameState<RA> {
+	/// Creates `num` players of the factory state, acting `per_block` players per block.
+	pub fn new(factory_state: &RA, per_block: u32) -> Self {
+		let num: u32 = factory_state.num().unique_saturated_into();
+		let players = (0..num)
+			.map(|i| {
+				let seed = factory_state.start_number() + RA::Number::from(i);
+				Player {
+					id: RA::gen_random_account_id(&seed),
+					key: RA::gen_random_account_secret(&seed),
+					nonce: 0,
+					dboxes: 0,
+					opened: HashSet::new(),
+				}
+			})
+			.collect();
+
+		GameState {
+			players,
+			per_block: per_block as usize,
+			rounds: factory_state.rounds().unique_saturated_into(),
+			master_nonce: 0,
+			funded: 0,
+			played: 0,
+			rng: 0x2545_f491_4f6c_dd1d,
+		}
+	}
+
+	/// A deterministic pseudo random number below `bound` (xorshift).
+	fn random(&mut self, bound: usize) -> usize {
+		self.rng ^= self.rng << 13;
+		self.rng ^= self.rng >> 7;
+		self.rng ^= self.rng << 17;
+		(self.rng % bound as u64) as usize
+	}
+
+	/// Chooses the next call of the player, the model is changed by `apply`
+	/// once the extrinsic is pushed.
+	fn play(&mut self, player: usize) -> GameCall<RA::AccountId> where RA::AccountId: Clone {
+		let roll = self.random(100);
+		let active = self.players[player].active_dboxes();
+
+		if active.is_empty() || roll < 50 {
+			// Only a new player can be invited, by a player who has created a dbox
+			let invitor = self.random(self.players.len());
+			let invitor = if self.players[player].dboxes == 0
+				&& invitor != player
+				&& self.players[invitor].dboxes > 0
+			{
+				Some(self.players[invitor].id.clone())
+			} else {
+				None
+			};
+			GameCall::CreateDbox(invitor)
+		} else if roll < 80 {
+			// The dbox created by a successful upgrade is not tracked, since
+			// it depends on the bonus of the upgraded dbox
+			let index = active[self.random(active.len())];
+			GameCall::UpgradeDbox(index)
+		} else {
+			let index = active[self.random(active.len())];
+			GameCall::OpenDbox(index)
+		}
+	}
+
+	/// Applies the call pushed into the block to the model of the player.
+	fn apply(&mut self, player: usize, call: &GameCall<RA::AccountId>) {
+		let player = &mut self.players[player];
+		player.nonce += 1;
+		match call {
+			GameCall::CreateDbox(_) => player.dboxes += 1,
+			GameCall::UpgradeDbox(_) => {},
+			GameCall::OpenDbox(index) => {
+				player.opened.insert(*index);
+			},
+		}
+	}
+}
+
+pub fn next<RA, Backend, Exec, Block, RtApi>(
+	factory_state: &mut RA,
+	game: &mut GameState<RA>,
+	client: &Arc<Client<Backend, Exec, Block, RtApi>>,
+	version: u32,
+	genesis_hash: <RA::Block as BlockT>::Hash,
+	prior_block_hash: <RA::Block as BlockT>::Hash,
+	prior_block_id: BlockId<Block>,
+) -> Option<Block>
+where
+	Block: BlockT<Hash = <Blake2Hasher as Hasher>::Out>,
+	Exec: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + Clone,
+	Backend: client::backend::Backend<Block, Blake2Hasher> + Send,
+	Client<Backend, Exec, Block, RtApi>: ProvideRuntimeApi,
+	<Client<Backend, Exec, Block, RtApi> as ProvideRuntimeApi>::Api:
+		BlockBuilder<Block, Error = client::error::Error>,
+	RtApi: ConstructRuntimeApi<Block, Client<Backend, Exec, Block, RtApi>> + Send + Sync,
+	RA: GameRuntimeAdapter,
+	RA::AccountId: Clone,
+{
+	if game.funded >= game.players.len() && game.played >= game.rounds {
+		return None;
+	}
+
+	let inherents = RA::inherent_extrinsics(&factory_state);
+	let inherents = client.runtime_api().inherent_extrinsics(&prior_block_id, inherents)
+		.expect("Failed to create inherent extrinsics");
+
+	let mut block = client.new_block(Default::default()).expect("Failed to create new block");
+	for inherent in inherents {
+		block.push(inherent).expect("Failed to push inherent extrinsic into block");
+	}
+
+	let started = Instant::now();
+	let phase = if game.funded < game.players.len() {
+		let master = (RA::master_account_id(), RA::master_account_secret());
+		let amount = RA::player_funds();
+		let end = (game.funded + game.per_block).min(game.players.len());
+
+		for player in &game.players[game.funded..end] {
+			let transfer = factory_state.transfer_extrinsic_with_nonce(
+				&master.0,
+				&master.1,
+				game.master_nonce,
+				&player.id,
+				&amount,
+				version,
+				&genesis_hash,
+				&prior_block_hash,
+			);
+			block.push(
+				Decode::decode(&mut &transfer.encode()[..])
+					.expect("Failed to decode transfer extrinsic")
+			).expect("Failed to push transfer extrinsic into block");
+			game.master_nonce += 1;
+		}
+
+		let funded = end - game.funded;
+		game.funded = end;
+		format!("Funded {} players with {} each", funded, amount)
+	} else {
+		let offset = game.random(game.players.len());
+		let mut pushed = 0;
+
+		for i in 0..game.per_block {
+			let player = (offset + i) % game.players.len();
+			let call = game.play(player);
+			let extrinsic = factory_state.game_extrinsic(
+				&game.players[player].id,
+				&game.players[player].key,
+				game.players[player].nonce,
+				call.clone(),
+				version,
+				&genesis_hash,
+				&prior_block_hash,
+			);
+			let pushed_extrinsic = block.push(
+				Decode::decode(&mut &extrinsic.encode()[..])
+					.expect("Failed to decode game extrinsic")
+			);
+			match pushed_extrinsic {
+				Ok(_) => {
+					game.apply(player, &call);
+					pushed += 1;
+				},
+				// The player is out of funds for the fee, neither the nonce nor the call is used
+				Err(e) => warn!("Failed to push extrinsic of {}: {:?}", game.players[player].id, e),
+			}
+		}
+
+		game.played += 1;
+		format!("Played {} game extrinsics in round {}/{}", pushed, game.played, game.rounds)
+	};
+
+	let block = block.bake().expect("Failed to bake block");
+	let elapsed = started.elapsed();
+
+	factory_state.set_block_no(factory_state.block_no() + RA::Number::one());
+
+	info!(
+		"Created block {} with parent {} in {} ms. {}.",
+		factory_state.block_no(),
+		prior_block_hash,
+		elapsed.as_millis(),
+		phase,
+	);
+
+	Some(block)
+}
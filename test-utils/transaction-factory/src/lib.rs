@@ -17,14 +17,18 @@
 //! Simple transaction factory which distributes tokens from a master
 //! account to a specified number of newly created accounts.
 //!
+//! In mode `Game` the accounts are funded as players, who then play
+//! a dbox game, see `game_factory`.
+//!
 //! The factory currently only works on an empty database!
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::cmp::PartialOrd;
 use std::fmt::Display;
+use std::time::Instant;
 
-use log::info;
+use log::{info, warn};
 
 use client::Client;
 use block_builder_api::BlockBuilder;
@@ -45,6 +49,7 @@ pub use crate::modes::Mode;
 
 pub mod modes;
 mod complex_mode;
+mod game_mode;
 mod simple_modes;
 
 pub trait RuntimeAdapter {
@@ -92,6 +97,52 @@ pub trait RuntimeAdapter {
 	fn gen_random_account_secret(seed: &Self::Number) -> Self::Secret;
 }
 
+/// A call of the dbox game manufactured in mode `Game`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameCall<AccountId> {
+	/// Create a dbox, with the invitor of a new player.
+	CreateDbox(Option<AccountId>),
+	/// Upgrade the dbox of the player by its index.
+	UpgradeDbox(u64),
+	/// Open the dbox of the player by its index.
+	OpenDbox(u64),
+}
+
+/// Manufactures the extrinsics of the dbox game in mode `Game`.
+///
+/// A block carries many extrinsics of the same sender here,
+/// so the nonce is given explicitly instead of `extract_index`.
+pub trait GameRuntimeAdapter: RuntimeAdapter {
+	/// The balance each player is funded with, covering a few dboxes and fees.
+	fn player_funds() -> Self::Balance;
+
+	/// The target duration of a block in milliseconds.
+	fn block_duration() -> u64;
+
+	fn transfer_extrinsic_with_nonce(
+		&self,
+		sender: &Self::AccountId,
+		key: &Self::Secret,
+		nonce: u32,
+		destination: &Self::AccountId,
+		amount: &Self::Balance,
+		version: u32,
+		genesis_hash: &<Self::Block as BlockT>::Hash,
+		prior_block_hash: &<Self::Block as BlockT>::Hash,
+	) -> <Self::Block as BlockT>::Extrinsic;
+
+	fn game_extrinsic(
+		&self,
+		sender: &Self::AccountId,
+		key: &Self::Secret,
+		nonce: u32,
+		call: GameCall<Self::AccountId>,
+		version: u32,
+		genesis_hash: &<Self::Block as BlockT>::Hash,
+		prior_block_hash: &<Self::Block as BlockT>::Hash,
+	) -> <Self::Block as BlockT>::Extrinsic;
+}
+
 /// Manufactures transactions. The exact amount depends on
 /// `mode`, `num` and `rounds`.
 pub fn factory<RA, Backend, Exec, Block, RtApi, Sc>(
@@ -111,6 +162,11 @@ where
 	RA: RuntimeAdapter,
 	<<RA as RuntimeAdapter>::Block as BlockT>::Hash: From<primitives::H256>,
 {
+	if *factory_state.mode() == Mode::Game {
+		let msg = "The mode Game is only supported by the game factory.".into();
+		return Err(cli::error::Error::Input(msg));
+	}
+
 	if *factory_state.mode() != Mode::MasterToNToM && factory_state.rounds() > RA::Number::one() {
 		let msg = "The factory can only be used with rounds set to 1 in this mode.".into();
 		return Err(cli::error::Error::Input(msg));
@@ -152,6 +208,82 @@ where
 	Ok(())
 }
 
+/// Manufactures the blocks of a dbox game. First `num` players are funded
+/// by the master account, then `rounds` blocks are filled with game
+/// extrinsics of `per_block` distinct players each.
+///
+/// The import time of each block is compared to the block duration,
+/// so the number of players per block which the runtime sustains
+/// can be found by raising `per_block`.
+pub fn game_factory<RA, Backend, Exec, Block, RtApi, Sc>(
+	mut factory_state: RA,
+	per_block: u32,
+	client: &Arc<Client<Backend, Exec, Block, RtApi>>,
+	select_chain: &Sc,
+) -> cli::error::Result<()>
+where
+	Block: BlockT<Hash = <Blake2Hasher as Hasher>::Out>,
+	Exec: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + Clone,
+	Backend: client::backend::Backend<Block, Blake2Hasher> + Send,
+	Client<Backend, Exec, Block, RtApi>: ProvideRuntimeApi,
+	<Client<Backend, Exec, Block, RtApi> as ProvideRuntimeApi>::Api:
+		BlockBuilder<Block, Error = client::error::Error>,
+	RtApi: ConstructRuntimeApi<Block, Client<Backend, Exec, Block, RtApi>> + Send + Sync,
+	Sc: SelectChain<Block>,
+	RA: GameRuntimeAdapter,
+	RA::AccountId: Clone,
+	<<RA as RuntimeAdapter>::Block as BlockT>::Hash: From<primitives::H256>,
+{
+	if *factory_state.mode() != Mode::Game {
+		let msg = "The game factory can only be used in mode Game.".into();
+		return Err(cli::error::Error::Input(msg));
+	}
+
+	if per_block == 0 || RA::Number::from(per_block) > factory_state.num() {
+		let msg = "The players per block should be between 1 and num.".into();
+		return Err(cli::error::Error::Input(msg));
+	}
+
+	let best_header: Result<<Block as BlockT>::Header, cli::error::Error> =
+		select_chain.best_chain().map_err(|e| format!("{:?}", e).into());
+	let mut best_hash = best_header?.hash();
+	let mut best_block_id = BlockId::<Block>::hash(best_hash);
+	let version = client.runtime_version_at(&best_block_id)?.spec_version;
+	let genesis_hash = client.block_hash(Zero::zero())?
+		.expect("Genesis block always exists; qed").into();
+
+	let mut game = game_mode::GameState::new(&factory_state, per_block);
+	while let Some(block) = game_mode::next::<RA, _, _, _, _>(
+		&mut factory_state,
+		&mut game,
+		&client,
+		version,
+		genesis_hash,
+		best_hash.into(),
+		best_block_id,
+	) {
+		best_hash = block.header().hash();
+		best_block_id = BlockId::<Block>::hash(best_hash);
+
+		let started = Instant::now();
+		import_block(&client, block);
+		let elapsed = started.elapsed().as_millis();
+
+		if elapsed > RA::block_duration() as u128 {
+			warn!(
+				"Imported block at {} in {} ms, falling behind the block duration of {} ms",
+				factory_state.block_no(),
+				elapsed,
+				RA::block_duration(),
+			);
+		} else {
+			info!("Imported block at {} in {} ms", factory_state.block_no(), elapsed);
+		}
+	}
+
+	Ok(())
+}
+
 /// Create a baked block from a transfer extrinsic and timestamp inherent.
 pub fn create_block<RA, Backend, Exec, Block, RtApi>(
 	client: &Arc<Client<Backend, Exec, Block, RtApi>>,